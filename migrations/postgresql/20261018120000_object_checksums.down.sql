@@ -0,0 +1,3 @@
+ALTER TABLE "objects" DROP COLUMN "crc32c";
+ALTER TABLE "objects" DROP COLUMN "md5";
+ALTER TABLE "objects" DROP COLUMN "sha256";
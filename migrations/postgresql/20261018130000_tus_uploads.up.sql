@@ -0,0 +1,6 @@
+CREATE TABLE "tus_uploads" (
+	"object_id" INTEGER PRIMARY KEY REFERENCES "objects" ("id") ON DELETE CASCADE,
+	"length" BIGINT NOT NULL,
+	"metadata" TEXT,
+	"created_at" BIGINT NOT NULL DEFAULT extract(epoch from now() at time zone 'utc')
+);
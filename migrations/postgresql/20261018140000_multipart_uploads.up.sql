@@ -0,0 +1,16 @@
+CREATE TABLE "multipart_uploads" (
+	"id" TEXT PRIMARY KEY,
+	"path" TEXT NOT NULL,
+	"type" TEXT,
+	"updated_at" BIGINT NOT NULL DEFAULT extract(epoch from now() at time zone 'utc'),
+	"created_at" BIGINT NOT NULL DEFAULT extract(epoch from now() at time zone 'utc')
+);
+CREATE INDEX "multipart_uploads_updated_at_idx" ON "multipart_uploads" ("updated_at");
+CREATE TABLE "multipart_parts" (
+	"upload_id" TEXT NOT NULL REFERENCES "multipart_uploads" ("id") ON DELETE CASCADE,
+	"part_number" INTEGER NOT NULL,
+	"etag" TEXT NOT NULL,
+	"size" BIGINT NOT NULL,
+	"updated_at" BIGINT NOT NULL DEFAULT extract(epoch from now() at time zone 'utc'),
+	PRIMARY KEY ("upload_id", "part_number")
+);
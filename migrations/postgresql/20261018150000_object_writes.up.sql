@@ -0,0 +1,8 @@
+CREATE TABLE "object_writes" (
+	"id" SERIAL PRIMARY KEY,
+	"object_id" INTEGER NOT NULL,
+	"operation" TEXT NOT NULL,
+	"size" BIGINT NOT NULL,
+	"created_at" BIGINT NOT NULL DEFAULT extract(epoch from now() at time zone 'utc')
+);
+CREATE INDEX "object_writes_object_id_idx" ON "object_writes" ("object_id");
@@ -0,0 +1,10 @@
+CREATE TABLE "object_acls" (
+	"id" BIGSERIAL PRIMARY KEY,
+	"app" BIGINT NOT NULL,
+	"sub" BIGINT,
+	"sub_type" TEXT,
+	"prefix" TEXT NOT NULL,
+	"permission" TEXT NOT NULL,
+	"created_at" BIGINT NOT NULL DEFAULT extract(epoch from now() at time zone 'utc')
+);
+CREATE INDEX "object_acls_app_sub_idx" ON "object_acls" ("app", "sub");
@@ -0,0 +1,15 @@
+CREATE TABLE "object_versions" (
+	"id" BIGSERIAL PRIMARY KEY,
+	"app" BIGINT NOT NULL,
+	"object_id" BIGINT NOT NULL,
+	"path" TEXT NOT NULL,
+	"version" BIGINT NOT NULL,
+	"type" TEXT,
+	"size" BIGINT NOT NULL,
+	"sha256" TEXT,
+	"md5" TEXT,
+	"crc32c" TEXT,
+	"updated_at" BIGINT NOT NULL,
+	"created_at" BIGINT NOT NULL DEFAULT extract(epoch from now() at time zone 'utc')
+);
+CREATE UNIQUE INDEX "object_versions_app_path_version_unique_idx" ON "object_versions" ("app", "path", "version");
@@ -0,0 +1,15 @@
+CREATE TABLE "trashed_objects" (
+	"id" BIGINT PRIMARY KEY,
+	"app" BIGINT NOT NULL,
+	"path" TEXT NOT NULL,
+	"type" TEXT,
+	"size" BIGINT NOT NULL,
+	"sha256" TEXT,
+	"md5" TEXT,
+	"crc32c" TEXT,
+	"updated_at" BIGINT NOT NULL,
+	"created_at" BIGINT NOT NULL,
+	"trashed_at" BIGINT NOT NULL DEFAULT extract(epoch from now() at time zone 'utc')
+);
+CREATE INDEX "trashed_objects_app_path_idx" ON "trashed_objects" ("app", "path");
+CREATE INDEX "trashed_objects_trashed_at_idx" ON "trashed_objects" ("trashed_at");
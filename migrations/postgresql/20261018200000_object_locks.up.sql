@@ -0,0 +1,3 @@
+ALTER TABLE "objects" ADD COLUMN "retention_mode" TEXT;
+ALTER TABLE "objects" ADD COLUMN "retain_until" BIGINT;
+ALTER TABLE "objects" ADD COLUMN "legal_hold" BIGINT NOT NULL DEFAULT 0;
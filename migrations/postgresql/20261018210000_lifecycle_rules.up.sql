@@ -0,0 +1,10 @@
+CREATE TABLE "lifecycle_rules" (
+	"id" BIGSERIAL PRIMARY KEY,
+	"app" BIGINT NOT NULL,
+	"prefix" TEXT NOT NULL,
+	"age_from" TEXT NOT NULL,
+	"days" BIGINT NOT NULL,
+	"updated_at" BIGINT NOT NULL DEFAULT extract(epoch from now() at time zone 'utc'),
+	"created_at" BIGINT NOT NULL DEFAULT extract(epoch from now() at time zone 'utc')
+);
+CREATE INDEX "lifecycle_rules_app_idx" ON "lifecycle_rules" ("app");
@@ -0,0 +1,3 @@
+ALTER TABLE "objects" ADD COLUMN "sha256" TEXT;
+ALTER TABLE "objects" ADD COLUMN "md5" TEXT;
+ALTER TABLE "objects" ADD COLUMN "crc32c" TEXT;
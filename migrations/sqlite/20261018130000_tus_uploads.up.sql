@@ -0,0 +1,7 @@
+CREATE TABLE "tus_uploads" (
+	"object_id" INTEGER NOT NULL PRIMARY KEY,
+	"length" INTEGER NOT NULL,
+	"metadata" TEXT,
+	"created_at" INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+	FOREIGN KEY ("object_id") REFERENCES "objects" ("id") ON DELETE CASCADE
+) STRICT;
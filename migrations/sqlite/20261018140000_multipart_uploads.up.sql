@@ -0,0 +1,17 @@
+CREATE TABLE "multipart_uploads" (
+	"id" TEXT NOT NULL PRIMARY KEY,
+	"path" TEXT NOT NULL,
+	"type" TEXT,
+	"updated_at" INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+	"created_at" INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
+CREATE INDEX "multipart_uploads_updated_at_idx" ON "multipart_uploads" ("updated_at");
+CREATE TABLE "multipart_parts" (
+	"upload_id" TEXT NOT NULL,
+	"part_number" INTEGER NOT NULL,
+	"etag" TEXT NOT NULL,
+	"size" INTEGER NOT NULL,
+	"updated_at" INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+	PRIMARY KEY ("upload_id", "part_number"),
+	FOREIGN KEY ("upload_id") REFERENCES "multipart_uploads" ("id") ON DELETE CASCADE
+) STRICT;
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS "object_writes";
\ No newline at end of file
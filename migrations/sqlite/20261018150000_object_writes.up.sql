@@ -0,0 +1,8 @@
+CREATE TABLE "object_writes" (
+	"id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"object_id" INTEGER NOT NULL,
+	"operation" TEXT NOT NULL,
+	"size" INTEGER NOT NULL,
+	"created_at" INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
+CREATE INDEX "object_writes_object_id_idx" ON "object_writes" ("object_id");
@@ -0,0 +1,4 @@
+ALTER TABLE "multipart_uploads" DROP COLUMN "app";
+DROP INDEX IF EXISTS "objects_app_path_unique_idx";
+CREATE UNIQUE INDEX "objects_path_unique_idx" ON "objects" ("path");
+ALTER TABLE "objects" DROP COLUMN "app";
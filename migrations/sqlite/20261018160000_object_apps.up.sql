@@ -0,0 +1,4 @@
+ALTER TABLE "objects" ADD COLUMN "app" INTEGER NOT NULL DEFAULT 0;
+DROP INDEX IF EXISTS "objects_path_unique_idx";
+CREATE UNIQUE INDEX "objects_app_path_unique_idx" ON "objects" ("app", "path");
+ALTER TABLE "multipart_uploads" ADD COLUMN "app" INTEGER NOT NULL DEFAULT 0;
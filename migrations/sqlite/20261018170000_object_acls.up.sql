@@ -0,0 +1,10 @@
+CREATE TABLE "object_acls" (
+	"id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"app" INTEGER NOT NULL,
+	"sub" INTEGER,
+	"sub_type" TEXT,
+	"prefix" TEXT NOT NULL,
+	"permission" TEXT NOT NULL,
+	"created_at" INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
+CREATE INDEX "object_acls_app_sub_idx" ON "object_acls" ("app", "sub");
@@ -0,0 +1,15 @@
+CREATE TABLE "object_versions" (
+	"id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"app" INTEGER NOT NULL,
+	"object_id" INTEGER NOT NULL,
+	"path" TEXT NOT NULL,
+	"version" INTEGER NOT NULL,
+	"type" TEXT,
+	"size" INTEGER NOT NULL,
+	"sha256" TEXT,
+	"md5" TEXT,
+	"crc32c" TEXT,
+	"updated_at" INTEGER NOT NULL,
+	"created_at" INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
+CREATE UNIQUE INDEX "object_versions_app_path_version_unique_idx" ON "object_versions" ("app", "path", "version");
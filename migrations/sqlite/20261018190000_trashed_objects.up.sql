@@ -0,0 +1,15 @@
+CREATE TABLE "trashed_objects" (
+	"id" INTEGER NOT NULL PRIMARY KEY,
+	"app" INTEGER NOT NULL,
+	"path" TEXT NOT NULL,
+	"type" TEXT,
+	"size" INTEGER NOT NULL,
+	"sha256" TEXT,
+	"md5" TEXT,
+	"crc32c" TEXT,
+	"updated_at" INTEGER NOT NULL,
+	"created_at" INTEGER NOT NULL,
+	"trashed_at" INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
+CREATE INDEX "trashed_objects_app_path_idx" ON "trashed_objects" ("app", "path");
+CREATE INDEX "trashed_objects_trashed_at_idx" ON "trashed_objects" ("trashed_at");
@@ -0,0 +1,10 @@
+CREATE TABLE "lifecycle_rules" (
+	"id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	"app" INTEGER NOT NULL,
+	"prefix" TEXT NOT NULL,
+	"age_from" TEXT NOT NULL,
+	"days" INTEGER NOT NULL,
+	"updated_at" INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+	"created_at" INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+) STRICT;
+CREATE INDEX "lifecycle_rules_app_idx" ON "lifecycle_rules" ("app");
@@ -0,0 +1,3 @@
+ALTER TABLE "trashed_objects" DROP COLUMN "legal_hold";
+ALTER TABLE "trashed_objects" DROP COLUMN "retain_until";
+ALTER TABLE "trashed_objects" DROP COLUMN "retention_mode";
@@ -0,0 +1,3 @@
+ALTER TABLE "trashed_objects" ADD COLUMN "retention_mode" TEXT;
+ALTER TABLE "trashed_objects" ADD COLUMN "retain_until" INTEGER;
+ALTER TABLE "trashed_objects" ADD COLUMN "legal_hold" INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,233 @@
+use std::{
+  fmt,
+  sync::{Arc, RwLock},
+};
+
+use dashmap::DashMap;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::core::{
+  config::JwtConfig,
+  jwt::{self, Jwk, JwkSet, JwtError, Validation},
+  sigv4::sha256_hex,
+};
+
+/// the JWKS is refetched for an unknown `kid` at most this often in seconds
+const MIN_JWKS_REFRESH_INTERVAL: i64 = 30;
+
+#[derive(Debug)]
+pub enum TokenError {
+  Jwt(JwtError),
+  Jwks(reqwest::Error),
+  Remote(String),
+}
+
+impl fmt::Display for TokenError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Jwt(err) => write!(f, "{}", err),
+      Self::Jwks(err) => write!(f, "failed to fetch jwks: {}", err),
+      Self::Remote(err) => write!(f, "auth service rejected token: {}", err),
+    }
+  }
+}
+
+impl From<JwtError> for TokenError {
+  fn from(err: JwtError) -> Self {
+    Self::Jwt(err)
+  }
+}
+
+impl TokenError {
+  /// whether the token could not be checked at all rather than being found invalid
+  pub fn is_unverifiable(&self) -> bool {
+    matches!(
+      self,
+      Self::Jwks(_)
+        | Self::Jwt(JwtError::UnknownKey)
+        | Self::Jwt(JwtError::UnsupportedAlgorithm(_))
+    )
+  }
+}
+
+#[derive(Deserialize)]
+struct OpenIdConfiguration {
+  jwks_uri: String,
+}
+
+struct Jwks {
+  keys: Arc<Vec<Jwk>>,
+  fetched_at: i64,
+}
+
+/// verifies JWTs with static keys or a cached JWKS and caches the claims of verified tokens
+pub struct JwtVerifier {
+  jwks_uri: String,
+  /// whether `jwks_uri` is an OpenID configuration naming the JWKS rather than the JWKS itself
+  discover: bool,
+  keys: Vec<Jwk>,
+  issuer: Option<String>,
+  audience: Option<String>,
+  leeway: i64,
+  cache_size: usize,
+  jwks: RwLock<Jwks>,
+  jwks_refresh: tokio::sync::Mutex<()>,
+  /// claims of verified tokens with their expiry keyed by the sha-256 of the token
+  verified_tokens: DashMap<String, (Map<String, Value>, i64)>,
+}
+
+impl JwtVerifier {
+  pub fn new(
+    config: &JwtConfig,
+    jwks_uri: String,
+    discover: bool,
+    issuer: Option<String>,
+    audience: Option<String>,
+  ) -> Self {
+    Self {
+      jwks_uri,
+      discover,
+      keys: config.keys.clone(),
+      issuer: issuer.filter(|issuer| !issuer.is_empty()),
+      audience: audience.filter(|audience| !audience.is_empty()),
+      leeway: config.leeway,
+      cache_size: config.cache_size,
+      jwks: RwLock::new(Jwks {
+        keys: Arc::new(Vec::new()),
+        fetched_at: 0,
+      }),
+      jwks_refresh: tokio::sync::Mutex::new(()),
+      verified_tokens: DashMap::new(),
+    }
+  }
+
+  /// verifies `token` and returns its claims, verified tokens are cached until they expire
+  pub async fn validate(&self, token: &str) -> Result<Map<String, Value>, TokenError> {
+    if let Some(claims) = self.cached(token) {
+      return Ok(claims);
+    }
+    let claims = self.verify(token).await?;
+    self.cache(token, &claims);
+    Ok(claims)
+  }
+
+  /// the claims of `token` if it was verified before and has not expired since
+  pub fn cached(&self, token: &str) -> Option<Map<String, Value>> {
+    let cache_key = sha256_hex(token.as_bytes());
+    if let Some(entry) = self.verified_tokens.get(&cache_key) {
+      if chrono::Utc::now().timestamp() <= entry.1 + self.leeway {
+        return Some(entry.0.clone());
+      }
+    }
+    self.verified_tokens.remove(&cache_key);
+    None
+  }
+
+  /// caches the claims of a verified token, evicting expired tokens and then arbitrary ones once
+  /// `cache_size` tokens are cached, tokens without an `exp` are never cached
+  pub fn cache(&self, token: &str, claims: &Map<String, Value>) {
+    let exp = match claims.get("exp").and_then(Value::as_i64) {
+      Some(exp) => exp,
+      None => return,
+    };
+    if self.cache_size == 0 {
+      return;
+    }
+    if self.verified_tokens.len() >= self.cache_size {
+      let now = chrono::Utc::now().timestamp();
+      self
+        .verified_tokens
+        .retain(|_, (_, exp)| now <= *exp + self.leeway);
+    }
+    while self.verified_tokens.len() >= self.cache_size {
+      let evicted = self
+        .verified_tokens
+        .iter()
+        .next()
+        .map(|entry| entry.key().clone());
+      match evicted {
+        Some(evicted) => self.verified_tokens.remove(&evicted),
+        None => break,
+      };
+    }
+    self
+      .verified_tokens
+      .insert(sha256_hex(token.as_bytes()), (claims.clone(), exp));
+  }
+
+  /// verifies the signature and claims of `token` without the cache
+  pub async fn verify(&self, token: &str) -> Result<Map<String, Value>, TokenError> {
+    let validation = Validation {
+      now: chrono::Utc::now().timestamp(),
+      leeway: self.leeway,
+      issuer: self.issuer.as_deref(),
+      audience: self.audience.as_deref(),
+    };
+    if !self.keys.is_empty() {
+      return Ok(jwt::verify(token, &self.keys, &validation)?);
+    }
+    let keys = match self.jwks_keys() {
+      Some(keys) => keys,
+      None => self.refresh_jwks(MIN_JWKS_REFRESH_INTERVAL).await?,
+    };
+    match jwt::verify(token, &keys, &validation) {
+      // the issuer may have rotated its keys since they were fetched
+      Err(JwtError::UnknownKey) => {
+        let keys = self.refresh_jwks(MIN_JWKS_REFRESH_INTERVAL).await?;
+        Ok(jwt::verify(token, &keys, &validation)?)
+      }
+      result => Ok(result?),
+    }
+  }
+
+  /// refetches the JWKS unless keys are configured statically
+  pub async fn refresh(&self) -> Result<(), TokenError> {
+    if self.keys.is_empty() {
+      self.refresh_jwks(0).await?;
+    }
+    Ok(())
+  }
+
+  fn jwks_keys(&self) -> Option<Arc<Vec<Jwk>>> {
+    let jwks = self.jwks.read().unwrap_or_else(|err| err.into_inner());
+    if jwks.fetched_at == 0 {
+      None
+    } else {
+      Some(jwks.keys.clone())
+    }
+  }
+
+  /// fetches the JWKS unless it was fetched less than `min_age` seconds ago
+  async fn refresh_jwks(&self, min_age: i64) -> Result<Arc<Vec<Jwk>>, TokenError> {
+    let _guard = self.jwks_refresh.lock().await;
+    {
+      let jwks = self.jwks.read().unwrap_or_else(|err| err.into_inner());
+      if jwks.fetched_at != 0 && chrono::Utc::now().timestamp() - jwks.fetched_at < min_age {
+        return Ok(jwks.keys.clone());
+      }
+    }
+    let jwks_uri = if self.discover {
+      fetch_json::<OpenIdConfiguration>(&self.jwks_uri)
+        .await?
+        .jwks_uri
+    } else {
+      self.jwks_uri.clone()
+    };
+    let keys = Arc::new(fetch_json::<JwkSet>(&jwks_uri).await?.keys);
+    let mut jwks = self.jwks.write().unwrap_or_else(|err| err.into_inner());
+    jwks.keys = keys.clone();
+    jwks.fetched_at = chrono::Utc::now().timestamp();
+    Ok(keys)
+  }
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(uri: &str) -> Result<T, TokenError> {
+  reqwest::get(uri)
+    .await
+    .and_then(reqwest::Response::error_for_status)
+    .map_err(TokenError::Jwks)?
+    .json::<T>()
+    .await
+    .map_err(TokenError::Jwks)
+}
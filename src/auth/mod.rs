@@ -0,0 +1,82 @@
+pub mod disabled;
+pub mod jwt;
+pub mod oidc;
+pub mod remote;
+pub mod static_tokens;
+
+use std::{fmt, future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use disabled::DisabledAuthProvider;
+use oidc::OidcAuthProvider;
+use remote::RemoteAuthProvider;
+use static_tokens::StaticAuthProvider;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+  core::config::{AuthProviderConfig, Config},
+  middleware::authorization::Claims,
+};
+
+pub type AuthFuture<'a, T> = Pin<Box<dyn Send + Future<Output = Result<T, AuthError>> + 'a>>;
+
+#[derive(Debug)]
+pub enum AuthError {
+  /// the request has no bearer token
+  Missing,
+  /// the bearer token was rejected
+  Invalid(String),
+}
+
+impl fmt::Display for AuthError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Missing => write!(f, "no bearer token"),
+      Self::Invalid(err) => write!(f, "{}", err),
+    }
+  }
+}
+
+pub trait AuthProvider: Send + Sync {
+  /// returns the claims of the bearer token of a request, `None` when it has no `Authorization`
+  /// header
+  fn authenticate<'a>(&'a self, token: Option<&'a str>) -> AuthFuture<'a, Claims>;
+
+  /// refreshes the keys tokens are verified with, called every `auth.jwt.jwks_refresh_interval`
+  /// seconds
+  fn refresh(&self) -> AuthFuture<'_, ()> {
+    Box::pin(async { Ok(()) })
+  }
+}
+
+pub fn create_auth_provider(config: Arc<Config>) -> Arc<dyn AuthProvider> {
+  match &config.auth.provider {
+    AuthProviderConfig::Remote => Arc::new(RemoteAuthProvider::new(config.clone())),
+    AuthProviderConfig::Static(static_config) => Arc::new(StaticAuthProvider::new(static_config)),
+    AuthProviderConfig::Oidc(oidc_config) => {
+      Arc::new(OidcAuthProvider::new(&config.auth.jwt, oidc_config))
+    }
+    AuthProviderConfig::Disabled(disabled_config) => {
+      log::warn!("Authentication is disabled, every request is allowed");
+      Arc::new(DisabledAuthProvider::new(disabled_config))
+    }
+  }
+}
+
+pub async fn refresh_task(
+  auth: Arc<dyn AuthProvider>,
+  config: Arc<Config>,
+  cancellation_token: CancellationToken,
+) {
+  let mut interval = tokio::time::interval(Duration::from_secs(
+    config.auth.jwt.jwks_refresh_interval.max(1),
+  ));
+  loop {
+    tokio::select! {
+      _ = cancellation_token.cancelled() => break,
+      _ = interval.tick() => {}
+    }
+    if let Err(err) = auth.refresh().await {
+      log::error!("Error refreshing auth keys: {}", err);
+    }
+  }
+}
@@ -0,0 +1,108 @@
+use serde_json::{Map, Value};
+
+use super::{jwt::JwtVerifier, AuthError, AuthFuture, AuthProvider};
+use crate::{
+  core::config::{JwtConfig, OidcAuthConfig},
+  middleware::authorization::Claims,
+};
+
+pub const OIDC_TOKEN_TYPE: &str = "oidc";
+
+/// JWTs from any OpenID Connect issuer, verified with the JWKS named by its discovery document
+pub struct OidcAuthProvider {
+  verifier: JwtVerifier,
+  app: i64,
+  sub_type: String,
+}
+
+impl OidcAuthProvider {
+  pub fn new(jwt_config: &JwtConfig, config: &OidcAuthConfig) -> Self {
+    let (jwks_uri, discover) = if jwt_config.jwks_uri.is_empty() {
+      (
+        format!(
+          "{}/.well-known/openid-configuration",
+          config.issuer.trim_end_matches('/')
+        ),
+        true,
+      )
+    } else {
+      (jwt_config.jwks_uri.clone(), false)
+    };
+    Self {
+      verifier: JwtVerifier::new(
+        jwt_config,
+        jwks_uri,
+        discover,
+        Some(config.issuer.clone()),
+        Some(config.audience.clone()),
+      ),
+      app: config.app,
+      sub_type: config.sub_type.clone(),
+    }
+  }
+
+  /// maps standard claims onto ours, tokens whose `sub` is not an integer are rejected since ACLs
+  /// name subjects by number, scopes are read from `scopes`, `scope` or `scp` as an array or space
+  /// separated string
+  fn claims(&self, claims: Map<String, Value>) -> Result<Claims, AuthError> {
+    let int = |name: &str| {
+      claims.get(name).and_then(|value| match value {
+        Value::String(value) => value.parse().ok(),
+        value => value.as_i64(),
+      })
+    };
+    let string = |name: &str| claims.get(name).and_then(Value::as_str).map(str::to_owned);
+    let scopes = ["scopes", "scope", "scp"]
+      .iter()
+      .find_map(|name| match claims.get(*name) {
+        Some(Value::String(scopes)) => Some(scopes.split_whitespace().map(str::to_owned).collect()),
+        Some(Value::Array(scopes)) => Some(
+          scopes
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_owned)
+            .collect(),
+        ),
+        _ => None,
+      })
+      .unwrap_or_default();
+    let sub =
+      int("sub").ok_or_else(|| AuthError::Invalid("token sub is not an integer".to_owned()))?;
+    Ok(Claims {
+      r#type: OIDC_TOKEN_TYPE.to_owned(),
+      exp: int("exp").unwrap_or_default(),
+      iat: int("iat").unwrap_or_default(),
+      nbf: int("nbf").unwrap_or_default(),
+      iss: string("iss").unwrap_or_default(),
+      aud: string("aud"),
+      sub_type: string("sub_type").unwrap_or_else(|| self.sub_type.clone()),
+      sub,
+      app: int("app").unwrap_or(self.app),
+      scopes,
+    })
+  }
+}
+
+impl AuthProvider for OidcAuthProvider {
+  fn authenticate<'a>(&'a self, token: Option<&'a str>) -> AuthFuture<'a, Claims> {
+    Box::pin(async move {
+      let token = token.ok_or(AuthError::Missing)?;
+      let claims = self
+        .verifier
+        .validate(token)
+        .await
+        .map_err(|err| AuthError::Invalid(err.to_string()))?;
+      self.claims(claims)
+    })
+  }
+
+  fn refresh(&self) -> AuthFuture<'_, ()> {
+    Box::pin(async move {
+      self
+        .verifier
+        .refresh()
+        .await
+        .map_err(|err| AuthError::Invalid(err.to_string()))
+    })
+  }
+}
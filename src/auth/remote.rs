@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use auth_client::apis::jwt_api;
+use serde_json::{Map, Value};
+
+use super::{
+  jwt::{JwtVerifier, TokenError},
+  AuthError, AuthFuture, AuthProvider,
+};
+use crate::{
+  core::config::Config, middleware::authorization::Claims, service::auth::auth_token_configuration,
+};
+
+/// tokens issued by the auth service at `auth.uri` for the tenant
+/// `object-storage.tenant_client_id`, verified with its JWKS and checked by the service itself
+/// when `auth.jwt.remote_fallback` is set and they cannot be verified locally
+pub struct RemoteAuthProvider {
+  config: Arc<Config>,
+  verifier: JwtVerifier,
+}
+
+impl RemoteAuthProvider {
+  pub fn new(config: Arc<Config>) -> Self {
+    let jwks_uri = if config.auth.jwt.jwks_uri.is_empty() {
+      format!(
+        "{}/.well-known/jwks.json",
+        config.auth.uri.trim_end_matches('/')
+      )
+    } else {
+      config.auth.jwt.jwks_uri.clone()
+    };
+    let verifier = JwtVerifier::new(
+      &config.auth.jwt,
+      jwks_uri,
+      false,
+      Some(config.auth.jwt.issuer.clone()),
+      // tokens of other tenants are signed with the same keys, only the audience tells them apart
+      Some(config.object_storage.tenant_client_id.to_string()),
+    );
+    Self { config, verifier }
+  }
+
+  async fn validate(&self, token: &str) -> Result<Map<String, Value>, TokenError> {
+    if let Some(claims) = self.verifier.cached(token) {
+      return Ok(claims);
+    }
+    let claims = match self.verifier.verify(token).await {
+      Ok(claims) => claims,
+      Err(err) if self.config.auth.jwt.remote_fallback && err.is_unverifiable() => {
+        log::warn!("verifying token with the auth service: {}", err);
+        self.remote_validate(token).await?
+      }
+      Err(err) => return Err(err),
+    };
+    self.verifier.cache(token, &claims);
+    Ok(claims)
+  }
+
+  async fn remote_validate(&self, token: &str) -> Result<Map<String, Value>, TokenError> {
+    match jwt_api::jwt_is_valid(
+      &auth_token_configuration(&self.config, token),
+      &self.config.object_storage.tenant_client_id.to_string(),
+    )
+    .await
+    {
+      Ok(claims) => Ok(Map::from_iter(claims)),
+      Err(err) => Err(TokenError::Remote(format!("{:?}", err))),
+    }
+  }
+}
+
+impl AuthProvider for RemoteAuthProvider {
+  fn authenticate<'a>(&'a self, token: Option<&'a str>) -> AuthFuture<'a, Claims> {
+    Box::pin(async move {
+      let token = token.ok_or(AuthError::Missing)?;
+      let claims = self
+        .validate(token)
+        .await
+        .map_err(|err| AuthError::Invalid(err.to_string()))?;
+      serde_json::from_value(Value::Object(claims))
+        .map_err(|err| AuthError::Invalid(format!("invalid token claims: {}", err)))
+    })
+  }
+
+  fn refresh(&self) -> AuthFuture<'_, ()> {
+    Box::pin(async move {
+      self
+        .verifier
+        .refresh()
+        .await
+        .map_err(|err| AuthError::Invalid(err.to_string()))
+    })
+  }
+}
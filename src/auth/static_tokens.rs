@@ -0,0 +1,51 @@
+use super::{AuthError, AuthFuture, AuthProvider};
+use crate::{
+  core::{
+    config::{StaticAuthConfig, StaticTokenConfig},
+    sigv4::signatures_match,
+  },
+  middleware::authorization::Claims,
+};
+
+pub const STATIC_TOKEN_TYPE: &str = "static";
+
+/// API keys listed in `auth.provider.tokens`, each acting as a fixed subject with fixed scopes
+pub struct StaticAuthProvider {
+  tokens: Vec<StaticTokenConfig>,
+}
+
+impl StaticAuthProvider {
+  pub fn new(config: &StaticAuthConfig) -> Self {
+    Self {
+      tokens: config.tokens.clone(),
+    }
+  }
+}
+
+impl AuthProvider for StaticAuthProvider {
+  fn authenticate<'a>(&'a self, token: Option<&'a str>) -> AuthFuture<'a, Claims> {
+    Box::pin(async move {
+      let token = token.ok_or(AuthError::Missing)?;
+      // every key is compared so the time taken does not reveal which one matched
+      let static_token = self
+        .tokens
+        .iter()
+        .fold(None, |matched, static_token| {
+          if signatures_match(&static_token.token, token) {
+            Some(static_token)
+          } else {
+            matched
+          }
+        })
+        .ok_or_else(|| AuthError::Invalid("unknown api key".to_owned()))?;
+      Ok(Claims {
+        r#type: STATIC_TOKEN_TYPE.to_owned(),
+        sub_type: static_token.sub_type.clone(),
+        sub: static_token.sub,
+        app: static_token.app,
+        scopes: static_token.scopes.clone(),
+        ..Default::default()
+      })
+    })
+  }
+}
@@ -0,0 +1,122 @@
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crc::{Crc, Digest as CrcDigest, CRC_32_ISCSI};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+pub const CONTENT_DIGEST_HEADER: &str = "content-digest";
+pub const CONTENT_MD5_HEADER: &str = "content-md5";
+
+static CRC32C: Crc<u32> = Crc::<u32>::new(&CRC_32_ISCSI);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checksums {
+  pub sha256: String,
+  pub md5: String,
+  pub crc32c: String,
+}
+
+/// hashes bytes as they are written, cloning it resumes from the same point
+#[derive(Clone)]
+pub struct Hasher {
+  sha256: Sha256,
+  md5: Md5,
+  crc32c: CrcDigest<'static, u32>,
+}
+
+impl Hasher {
+  pub fn new() -> Self {
+    Self {
+      sha256: Sha256::new(),
+      md5: Md5::new(),
+      crc32c: CRC32C.digest(),
+    }
+  }
+
+  pub fn update(&mut self, bytes: &[u8]) {
+    self.sha256.update(bytes);
+    self.md5.update(bytes);
+    self.crc32c.update(bytes);
+  }
+
+  pub fn checksums(&self) -> Checksums {
+    Checksums {
+      sha256: hex::encode(self.sha256.clone().finalize()),
+      md5: hex::encode(self.md5.clone().finalize()),
+      crc32c: hex::encode(self.crc32c.clone().finalize().to_be_bytes()),
+    }
+  }
+}
+
+impl Default for Hasher {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// digests the client sent for the request content in `Content-Digest` and `Content-MD5`
+#[derive(Debug, Default)]
+pub struct ExpectedDigests {
+  sha256: Option<Vec<u8>>,
+  md5: Option<(&'static str, Vec<u8>)>,
+  crc32c: Option<Vec<u8>>,
+}
+
+impl ExpectedDigests {
+  /// parses the headers, returning the name of the header that is malformed on error,
+  /// algorithms other than sha-256, md5 and crc32c are ignored
+  pub fn from_headers(headers: &HeaderMap) -> Result<Self, &'static str> {
+    let mut expected = Self::default();
+    if let Some(content_digest) = headers.get(CONTENT_DIGEST_HEADER) {
+      let content_digest = content_digest.to_str().map_err(|_| CONTENT_DIGEST_HEADER)?;
+      for member in content_digest.split(',') {
+        let (algorithm, value) = member.trim().split_once('=').ok_or(CONTENT_DIGEST_HEADER)?;
+        let value = value
+          .trim()
+          .strip_prefix(':')
+          .and_then(|value| value.strip_suffix(':'))
+          .and_then(|value| STANDARD.decode(value).ok())
+          .ok_or(CONTENT_DIGEST_HEADER)?;
+        match algorithm.trim().to_lowercase().as_str() {
+          "sha-256" => expected.sha256 = Some(value),
+          "md5" => expected.md5 = Some((CONTENT_DIGEST_HEADER, value)),
+          "crc32c" => expected.crc32c = Some(value),
+          _ => {}
+        }
+      }
+    }
+    if let Some(content_md5) = headers.get(CONTENT_MD5_HEADER) {
+      let value = content_md5
+        .to_str()
+        .ok()
+        .and_then(|value| STANDARD.decode(value.trim()).ok())
+        .ok_or(CONTENT_MD5_HEADER)?;
+      expected.md5 = Some((CONTENT_MD5_HEADER, value));
+    }
+    Ok(expected)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.sha256.is_none() && self.md5.is_none() && self.crc32c.is_none()
+  }
+
+  /// returns the name of the header whose digest does not match `checksums`
+  pub fn verify(&self, checksums: &Checksums) -> Result<(), &'static str> {
+    if let Some(sha256) = &self.sha256 {
+      if hex::encode(sha256) != checksums.sha256 {
+        return Err(CONTENT_DIGEST_HEADER);
+      }
+    }
+    if let Some((header, md5)) = &self.md5 {
+      if hex::encode(md5) != checksums.md5 {
+        return Err(*header);
+      }
+    }
+    if let Some(crc32c) = &self.crc32c {
+      if hex::encode(crc32c) != checksums.crc32c {
+        return Err(CONTENT_DIGEST_HEADER);
+      }
+    }
+    Ok(())
+  }
+}
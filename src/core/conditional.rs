@@ -0,0 +1,230 @@
+use std::time::SystemTime;
+
+use axum::http::{header, HeaderMap, HeaderName};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Precondition {
+  Passed,
+  /// the client's cached copy is current, only returned for safe methods
+  NotModified,
+  /// the named conditional header did not match
+  Failed(HeaderName),
+}
+
+/// evaluates the conditional request headers against the current validators in the order given
+/// by RFC 9110 section 13.2.2, `safe` is true for GET and HEAD requests
+pub fn evaluate_preconditions(
+  headers: &HeaderMap,
+  safe: bool,
+  etag: &str,
+  modified: SystemTime,
+) -> Precondition {
+  if let Some(if_match) = header_str(headers, &header::IF_MATCH) {
+    if !etag_list_matches(if_match, etag, false) {
+      return Precondition::Failed(header::IF_MATCH);
+    }
+  } else if let Some(if_unmodified_since) = header_date(headers, &header::IF_UNMODIFIED_SINCE) {
+    if truncate(modified) > if_unmodified_since {
+      return Precondition::Failed(header::IF_UNMODIFIED_SINCE);
+    }
+  }
+
+  if let Some(if_none_match) = header_str(headers, &header::IF_NONE_MATCH) {
+    if etag_list_matches(if_none_match, etag, true) {
+      return if safe {
+        Precondition::NotModified
+      } else {
+        Precondition::Failed(header::IF_NONE_MATCH)
+      };
+    }
+  } else if safe {
+    if let Some(if_modified_since) = header_date(headers, &header::IF_MODIFIED_SINCE) {
+      if truncate(modified) <= if_modified_since {
+        return Precondition::NotModified;
+      }
+    }
+  }
+
+  Precondition::Passed
+}
+
+/// matches `etag` against a `*` or comma separated entity tag list, weak comparison ignores the
+/// `W/` prefix while strong comparison never matches weak tags
+pub fn etag_list_matches(list: &str, etag: &str, weak: bool) -> bool {
+  let list = list.trim();
+  if list == "*" {
+    return true;
+  }
+  list
+    .split(',')
+    .map(str::trim)
+    .any(|candidate| match (candidate.strip_prefix("W/"), weak) {
+      (Some(candidate), true) => candidate == etag.trim_start_matches("W/"),
+      (Some(_), false) => false,
+      (None, _) => candidate == etag.trim_start_matches("W/"),
+    })
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+  headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn header_date(headers: &HeaderMap, name: &HeaderName) -> Option<SystemTime> {
+  header_str(headers, name).and_then(|value| httpdate::parse_http_date(value).ok())
+}
+
+/// HTTP dates only have second precision
+fn truncate(time: SystemTime) -> SystemTime {
+  httpdate::HttpDate::from(time).into()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{Duration, UNIX_EPOCH};
+
+  use axum::http::HeaderValue;
+
+  use super::*;
+
+  const ETAG: &str = "\"abc\"";
+
+  fn modified() -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(1_000_000_000_500)
+  }
+
+  fn date(seconds: u64) -> String {
+    httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(seconds))
+  }
+
+  fn evaluate(headers: &[(HeaderName, &str)], safe: bool) -> Precondition {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+      map.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+    }
+    evaluate_preconditions(&map, safe, ETAG, modified())
+  }
+
+  #[test]
+  fn no_conditions() {
+    assert_eq!(evaluate(&[], true), Precondition::Passed);
+    assert_eq!(evaluate(&[], false), Precondition::Passed);
+  }
+
+  #[test]
+  fn if_match() {
+    assert_eq!(
+      evaluate(&[(header::IF_MATCH, "\"other\", \"abc\"")], false),
+      Precondition::Passed
+    );
+    assert_eq!(
+      evaluate(&[(header::IF_MATCH, "*")], false),
+      Precondition::Passed
+    );
+    assert_eq!(
+      evaluate(&[(header::IF_MATCH, "\"other\"")], false),
+      Precondition::Failed(header::IF_MATCH)
+    );
+    // If-Match uses the strong comparison
+    assert_eq!(
+      evaluate(&[(header::IF_MATCH, "W/\"abc\"")], true),
+      Precondition::Failed(header::IF_MATCH)
+    );
+  }
+
+  #[test]
+  fn if_match_takes_precedence_over_if_unmodified_since() {
+    let stale = date(999_999_999);
+    assert_eq!(
+      evaluate(&[(header::IF_UNMODIFIED_SINCE, &stale)], false),
+      Precondition::Failed(header::IF_UNMODIFIED_SINCE)
+    );
+    assert_eq!(
+      evaluate(
+        &[
+          (header::IF_MATCH, ETAG),
+          (header::IF_UNMODIFIED_SINCE, &stale)
+        ],
+        false
+      ),
+      Precondition::Passed
+    );
+    // the modification time is compared at second precision
+    assert_eq!(
+      evaluate(
+        &[(header::IF_UNMODIFIED_SINCE, &date(1_000_000_000))],
+        false
+      ),
+      Precondition::Passed
+    );
+  }
+
+  #[test]
+  fn if_none_match() {
+    assert_eq!(
+      evaluate(&[(header::IF_NONE_MATCH, "W/\"abc\"")], true),
+      Precondition::NotModified
+    );
+    assert_eq!(
+      evaluate(&[(header::IF_NONE_MATCH, "*")], false),
+      Precondition::Failed(header::IF_NONE_MATCH)
+    );
+    assert_eq!(
+      evaluate(&[(header::IF_NONE_MATCH, "\"other\"")], true),
+      Precondition::Passed
+    );
+  }
+
+  #[test]
+  fn if_none_match_takes_precedence_over_if_modified_since() {
+    let current = date(1_000_000_000);
+    assert_eq!(
+      evaluate(&[(header::IF_MODIFIED_SINCE, &current)], true),
+      Precondition::NotModified
+    );
+    assert_eq!(
+      evaluate(
+        &[
+          (header::IF_NONE_MATCH, "\"other\""),
+          (header::IF_MODIFIED_SINCE, &current)
+        ],
+        true
+      ),
+      Precondition::Passed
+    );
+    // If-Modified-Since only applies to GET and HEAD
+    assert_eq!(
+      evaluate(&[(header::IF_MODIFIED_SINCE, &current)], false),
+      Precondition::Passed
+    );
+    assert_eq!(
+      evaluate(&[(header::IF_MODIFIED_SINCE, &date(999_999_999))], true),
+      Precondition::Passed
+    );
+  }
+
+  #[test]
+  fn failed_match_is_checked_before_not_modified() {
+    assert_eq!(
+      evaluate(
+        &[
+          (header::IF_MATCH, "\"other\""),
+          (header::IF_NONE_MATCH, ETAG)
+        ],
+        true
+      ),
+      Precondition::Failed(header::IF_MATCH)
+    );
+  }
+
+  #[test]
+  fn invalid_dates_are_ignored() {
+    assert_eq!(
+      evaluate(&[(header::IF_UNMODIFIED_SINCE, "yesterday")], false),
+      Precondition::Passed
+    );
+    assert_eq!(
+      evaluate(&[(header::IF_MODIFIED_SINCE, "yesterday")], true),
+      Precondition::Passed
+    );
+  }
+}
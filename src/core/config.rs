@@ -2,6 +2,8 @@ use config::ConfigError;
 use serde::Deserialize;
 use std::net::IpAddr;
 
+use super::jwt::Jwk;
+
 #[derive(Debug, Deserialize)]
 pub struct ServerConfig {
   pub address: IpAddr,
@@ -20,31 +22,214 @@ pub struct DatabaseConfig {
   pub max_lifetime: u64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct AuthServiceAccountConfig {
   pub client_id: uuid::Uuid,
   pub client_secret: uuid::Uuid,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct JwtConfig {
+  /// the JWKS tokens are verified with, `{auth.uri}/.well-known/jwks.json` or the one named by
+  /// the OpenID configuration of the `oidc` issuer when empty
+  pub jwks_uri: String,
+  /// seconds between JWKS refreshes
+  pub jwks_refresh_interval: u64,
+  /// keys tokens are verified with instead of the JWKS
+  pub keys: Vec<Jwk>,
+  /// the `iss` tokens of the `remote` provider must have, not checked when empty
+  pub issuer: String,
+  /// seconds of clock skew allowed when checking `exp` and `nbf`
+  pub leeway: i64,
+  /// the most verified tokens kept in memory
+  pub cache_size: usize,
+  /// whether tokens that cannot be verified locally are checked by the auth service
+  pub remote_fallback: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StaticTokenConfig {
+  pub token: String,
+  #[serde(default)]
+  pub app: i64,
+  #[serde(default)]
+  pub sub: i64,
+  #[serde(default)]
+  pub sub_type: String,
+  #[serde(default)]
+  pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StaticAuthConfig {
+  pub tokens: Vec<StaticTokenConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcAuthConfig {
+  /// the `iss` of accepted tokens, whose OpenID configuration names the JWKS
+  pub issuer: String,
+  /// the `aud` tokens must have, required since other clients of the issuer get tokens too
+  pub audience: String,
+  /// the app of tokens without an `app` claim
+  #[serde(default)]
+  pub app: i64,
+  /// the subject type of tokens without a `sub_type` claim
+  #[serde(default)]
+  pub sub_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisabledAuthConfig {
+  /// the app every request acts as
+  #[serde(default)]
+  pub app: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AuthProviderConfig {
+  Remote,
+  Static(StaticAuthConfig),
+  Oidc(OidcAuthConfig),
+  Disabled(DisabledAuthConfig),
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AuthConfig {
   pub uri: String,
+  pub provider: AuthProviderConfig,
+  #[serde(default)]
   pub service_account: AuthServiceAccountConfig,
+  pub jwt: JwtConfig,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct ObjectStorageConfig {
   pub tenant_client_id: uuid::Uuid,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3StorageConfig {
+  pub endpoint: String,
+  pub region: String,
+  pub bucket: String,
+  pub access_key_id: String,
+  pub secret_access_key: String,
+  #[serde(default)]
+  pub prefix: String,
+  #[serde(default)]
+  pub path_style: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum StorageConfig {
+  Local,
+  S3(S3StorageConfig),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct S3AccessKeyConfig {
+  pub access_key_id: String,
+  pub secret_access_key: String,
+  /// the app whose objects requests signed with this key can access
+  #[serde(default)]
+  pub app: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct S3ApiConfig {
+  pub enabled: bool,
+  pub path: String,
+  pub region: String,
+  pub access_keys: Vec<S3AccessKeyConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MultipartConfig {
+  /// seconds an upload may go without a new part before it is aborted
+  pub expiration: u64,
+  /// seconds between cleanups of abandoned uploads
+  pub cleanup_interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PresignConfig {
+  /// the HMAC key presigned urls are signed with, presigning is disabled while it is empty
+  pub key: String,
+  /// the longest a presigned url may stay valid in seconds
+  pub max_expires: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuotaConfig {
+  /// the app whose objects count towards the quota
+  #[serde(default)]
+  pub app: i64,
+  /// only objects whose path starts with the prefix count, every object of the app when empty
+  #[serde(default)]
+  pub prefix: String,
+  /// the most bytes the objects may take up in total
+  pub max_bytes: Option<u64>,
+  /// the most objects there may be
+  pub max_objects: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersioningConfig {
+  /// the app whose objects are versioned
+  #[serde(default)]
+  pub app: i64,
+  /// only objects whose path starts with the prefix are versioned, every object of the app when
+  /// empty
+  #[serde(default)]
+  pub prefix: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrashConfig {
+  /// deleted objects are moved to the trash instead of being removed right away
+  pub enabled: bool,
+  /// seconds an object stays in the trash before it is purged
+  pub retention: u64,
+  /// seconds between purges of expired trash
+  pub purge_interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LifecycleConfig {
+  /// seconds between evaluations of the lifecycle rules
+  pub interval: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FolderDeleteConfig {
+  /// folders with more objects than this are only deleted when the request confirms their count
+  pub confirm_threshold: u64,
+  /// objects deleted per transaction
+  pub batch_size: usize,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Config {
   pub server: ServerConfig,
   pub database: DatabaseConfig,
-  #[serde(rename = "object-storage")]
+  #[serde(rename = "object-storage", default)]
   pub object_storage: ObjectStorageConfig,
   pub auth: AuthConfig,
+  pub storage: StorageConfig,
+  pub s3: S3ApiConfig,
+  pub multipart: MultipartConfig,
+  pub presign: PresignConfig,
+  pub quotas: Vec<QuotaConfig>,
+  pub versioning: Vec<VersioningConfig>,
+  pub trash: TrashConfig,
+  pub lifecycle: LifecycleConfig,
+  pub folder_delete: FolderDeleteConfig,
   pub objects_dir: String,
+  /// the largest request body accepted by the raw write endpoints in bytes
+  pub max_body_size: u64,
   pub log_level: String,
 }
 
@@ -68,14 +253,57 @@ impl Config {
       .set_default("database.max_lifetime", 300)?
       // Auth
       .set_default("auth.uri", "https://api.auth.aicacia.com".to_owned())?
+      .set_default("auth.provider.type", "remote")?
+      .set_default("auth.jwt.jwks_uri", "")?
+      .set_default("auth.jwt.jwks_refresh_interval", 5 * 60)?
+      .set_default("auth.jwt.keys", Vec::<String>::new())?
+      .set_default("auth.jwt.issuer", "")?
+      .set_default("auth.jwt.leeway", 30)?
+      .set_default("auth.jwt.cache_size", 10_000)?
+      .set_default("auth.jwt.remote_fallback", false)?
+      // Storage
+      .set_default("storage.type", "local")?
+      .set_default("storage.region", "us-east-1")?
+      // S3 API
+      .set_default("s3.enabled", false)?
+      .set_default("s3.path", "/s3")?
+      .set_default("s3.region", "us-east-1")?
+      .set_default("s3.access_keys", Vec::<String>::new())?
+      // Multipart Uploads
+      .set_default("multipart.expiration", 24 * 60 * 60)?
+      .set_default("multipart.cleanup_interval", 60 * 60)?
+      // Presigned URLs
+      .set_default("presign.key", "")?
+      .set_default("presign.max_expires", 7 * 24 * 60 * 60)?
+      // Quotas
+      .set_default("quotas", Vec::<String>::new())?
+      // Versioning
+      .set_default("versioning", Vec::<String>::new())?
+      // Trash
+      .set_default("trash.enabled", true)?
+      .set_default("trash.retention", 30 * 24 * 60 * 60)?
+      .set_default("trash.purge_interval", 60 * 60)?
+      // Lifecycle Rules
+      .set_default("lifecycle.interval", 60 * 60)?
+      // Folder Deletes
+      .set_default("folder_delete.confirm_threshold", 1000)?
+      .set_default("folder_delete.batch_size", 100)?
       // Defaults
       .set_default("objects_dir", "./objects")?
+      .set_default("max_body_size", 5_u64 * 1024 * 1024 * 1024)?
       .set_default("log_level", "debug")?
       .add_source(config::File::with_name(config_path))
       .add_source(config::Environment::with_prefix("APP"))
       .build()?;
 
-    let config = config_builder.try_deserialize()?;
+    let config: Config = config_builder.try_deserialize()?;
+    if let AuthProviderConfig::Oidc(oidc) = &config.auth.provider {
+      if oidc.audience.trim().is_empty() {
+        return Err(ConfigError::Message(
+          "auth.provider.audience is required for the oidc provider".to_owned(),
+        ));
+      }
+    }
     Ok(config)
   }
 }
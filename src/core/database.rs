@@ -17,10 +17,15 @@ static POOL: AtomicOption<sqlx::AnyPool> = AtomicOption::none();
 static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
 static POSTGRESQL_MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgresql");
 
+/// in-memory sqlite databases live only as long as their connection and have no file to create
+fn is_sqlite_memory(url: &str) -> bool {
+  url == "sqlite::memory:" || url.contains("mode=memory")
+}
+
 pub async fn init_pool(config: &Config) -> Result<sqlx::AnyPool, sqlx::Error> {
   log::info!("Creating pool for database: {}", config.database.url);
 
-  if config.database.url.starts_with("sqlite:") {
+  if config.database.url.starts_with("sqlite:") && !is_sqlite_memory(&config.database.url) {
     let path = Path::new(&config.database.url["sqlite:".len()..]);
     if let Some(parent) = path.parent() {
       if !parent.as_os_str().is_empty() && !parent.exists() {
@@ -54,15 +59,12 @@ pub async fn init_pool(config: &Config) -> Result<sqlx::AnyPool, sqlx::Error> {
     .max_lifetime(Duration::from_secs(config.database.max_lifetime))
     .after_connect(|conn, _meta| {
       Box::pin(async move {
-        match conn.backend_name().to_lowercase().as_str() {
-          "sqlite" => {
-            conn
-              .execute(
-                "PRAGMA journal_mode = wal; PRAGMA synchronous = normal; PRAGMA foreign_keys = on;",
-              )
-              .await?;
-          }
-          _ => (),
+        if conn.backend_name().eq_ignore_ascii_case("sqlite") {
+          conn
+            .execute(
+              "PRAGMA journal_mode = wal; PRAGMA synchronous = normal; PRAGMA foreign_keys = on;",
+            )
+            .await?;
         }
         Ok(())
       })
@@ -116,15 +118,12 @@ pub async fn close_pool() -> Result<(), sqlx::Error> {
   let pool = POOL.take(Ordering::SeqCst).expect("Pool not initialized");
   {
     let conn = pool.acquire().await?;
-    match conn.backend_name().to_lowercase().as_str() {
-      "sqlite" => {
-        log::info!("Optimizing database");
-        pool
-          .execute("PRAGMA analysis_limit=400; PRAGMA optimize;")
-          .await?;
-        log::info!("Optimized database");
-      }
-      _ => {}
+    if conn.backend_name().eq_ignore_ascii_case("sqlite") {
+      log::info!("Optimizing database");
+      pool
+        .execute("PRAGMA analysis_limit=400; PRAGMA optimize;")
+        .await?;
+      log::info!("Optimized database");
     }
   }
   pool.close().await;
@@ -21,6 +21,10 @@ pub const DATEBASE_ERROR: &str = "database";
 pub const NOT_ALLOWED_ERROR: &str = "not-allowed";
 pub const ALREADY_USED_ERROR: &str = "already-used";
 pub const ALREADY_EXISTS_ERROR: &str = "already-exists";
+pub const PRECONDITION_FAILED_ERROR: &str = "precondition-failed";
+pub const TOO_LARGE_ERROR: &str = "too-large";
+pub const QUOTA_EXCEEDED_ERROR: &str = "quota-exceeded";
+pub const OBJECT_LOCKED_ERROR: &str = "object-locked";
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ErrorMessage {
@@ -121,7 +125,7 @@ impl From<StatusCode> for InternalError {
 impl From<ValidationErrors> for InternalError {
   fn from(validation_errors: ValidationErrors) -> Self {
     let mut new = Self::bad_request();
-    handle_validation_errors(&mut new, &mut String::new(), &validation_errors);
+    handle_validation_errors(&mut new, "", &validation_errors);
     new
   }
 }
@@ -178,6 +182,7 @@ impl InternalError {
     Self::from(StatusCode::BAD_REQUEST)
   }
 
+  #[allow(clippy::self_named_constructors)]
   pub fn internal_error() -> Self {
     Self::from(StatusCode::INTERNAL_SERVER_ERROR)
   }
@@ -194,6 +199,14 @@ impl InternalError {
     Self::from(StatusCode::FORBIDDEN)
   }
 
+  pub fn precondition_failed() -> Self {
+    Self::from(StatusCode::PRECONDITION_FAILED)
+  }
+
+  pub fn payload_too_large() -> Self {
+    Self::from(StatusCode::PAYLOAD_TOO_LARGE)
+  }
+
   pub fn status(&mut self, status: StatusCode) -> &mut Self {
     self.status_code = status.as_u16();
     self
@@ -205,11 +218,7 @@ impl InternalError {
   }
 
   pub fn error(&mut self, name: impl Into<String>, msg: impl Into<ErrorMessage>) -> &mut Self {
-    self
-      .messages
-      .entry(name.into())
-      .or_insert_with(Default::default)
-      .error(msg);
+    self.messages.entry(name.into()).or_default().error(msg);
     self
   }
 
@@ -256,7 +265,7 @@ fn handle_validation_errors_kind(
       for (index, e) in validation_errors {
         let mut name = current_name.to_owned();
         name.push_str(&format!("[{}]", index));
-        handle_validation_errors(errors, &mut name, e);
+        handle_validation_errors(errors, &name, e);
       }
     }
     ValidationErrorsKind::Field(validation_errors) => {
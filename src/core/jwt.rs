@@ -0,0 +1,533 @@
+use std::fmt;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ring::{hmac, signature};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// a JSON Web Key as served in a JWKS or configured statically
+#[derive(Debug, Clone, Deserialize)]
+pub struct Jwk {
+  pub kty: String,
+  pub kid: Option<String>,
+  pub alg: Option<String>,
+  pub crv: Option<String>,
+  pub n: Option<String>,
+  pub e: Option<String>,
+  pub x: Option<String>,
+  pub y: Option<String>,
+  pub k: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JwkSet {
+  pub keys: Vec<Jwk>,
+}
+
+#[derive(Debug)]
+pub enum JwtError {
+  Malformed,
+  UnsupportedAlgorithm(String),
+  UnknownKey,
+  InvalidSignature,
+  Expired,
+  NotYetValid,
+  InvalidIssuer,
+  InvalidAudience,
+}
+
+impl fmt::Display for JwtError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Malformed => write!(f, "token is malformed"),
+      Self::UnsupportedAlgorithm(alg) => write!(f, "token algorithm {} is not supported", alg),
+      Self::UnknownKey => write!(f, "token was signed with an unknown key"),
+      Self::InvalidSignature => write!(f, "token signature is invalid"),
+      Self::Expired => write!(f, "token has expired"),
+      Self::NotYetValid => write!(f, "token is not valid yet"),
+      Self::InvalidIssuer => write!(f, "token issuer is not trusted"),
+      Self::InvalidAudience => write!(f, "token is not meant for this service"),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct Header {
+  alg: String,
+  kid: Option<String>,
+}
+
+/// the checks made on the claims of a token once its signature is verified
+pub struct Validation<'a> {
+  pub now: i64,
+  /// seconds of clock skew allowed when checking `exp` and `nbf`
+  pub leeway: i64,
+  pub issuer: Option<&'a str>,
+  /// an `aud` the token must have, as a string or one of an array
+  pub audience: Option<&'a str>,
+}
+
+/// verifies the signature of `token` with one of `keys` and returns its claims
+pub fn verify(
+  token: &str,
+  keys: &[Jwk],
+  validation: &Validation,
+) -> Result<Map<String, Value>, JwtError> {
+  let (message, signature) = token.rsplit_once('.').ok_or(JwtError::Malformed)?;
+  let (header, payload) = message.split_once('.').ok_or(JwtError::Malformed)?;
+  if payload.contains('.') {
+    return Err(JwtError::Malformed);
+  }
+  let header: Header = decode_json(header)?;
+  let signature = URL_SAFE_NO_PAD
+    .decode(signature)
+    .map_err(|_| JwtError::Malformed)?;
+
+  let mut candidates = keys
+    .iter()
+    .filter(|key| match (&header.kid, &key.kid) {
+      (Some(kid), Some(key_kid)) => kid == key_kid,
+      (Some(_), None) => false,
+      (None, _) => true,
+    })
+    .filter(|key| key.alg.as_deref().map_or(true, |alg| alg == header.alg))
+    .peekable();
+  if candidates.peek().is_none() {
+    return Err(JwtError::UnknownKey);
+  }
+  let mut verified = false;
+  for key in candidates {
+    if verify_signature(&header.alg, key, message.as_bytes(), &signature)? {
+      verified = true;
+      break;
+    }
+  }
+  if !verified {
+    return Err(JwtError::InvalidSignature);
+  }
+
+  let claims: Map<String, Value> = decode_json(payload)?;
+  if let Some(exp) = claims.get("exp").and_then(Value::as_i64) {
+    if validation.now > exp + validation.leeway {
+      return Err(JwtError::Expired);
+    }
+  }
+  if let Some(nbf) = claims.get("nbf").and_then(Value::as_i64) {
+    if validation.now + validation.leeway < nbf {
+      return Err(JwtError::NotYetValid);
+    }
+  }
+  if let Some(issuer) = validation.issuer {
+    if claims.get("iss").and_then(Value::as_str) != Some(issuer) {
+      return Err(JwtError::InvalidIssuer);
+    }
+  }
+  if let Some(audience) = validation.audience {
+    let allowed = match claims.get("aud") {
+      Some(Value::String(aud)) => aud == audience,
+      Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+      _ => false,
+    };
+    if !allowed {
+      return Err(JwtError::InvalidAudience);
+    }
+  }
+  Ok(claims)
+}
+
+fn decode_json<T: serde::de::DeserializeOwned>(segment: &str) -> Result<T, JwtError> {
+  let bytes = URL_SAFE_NO_PAD
+    .decode(segment)
+    .map_err(|_| JwtError::Malformed)?;
+  serde_json::from_slice(&bytes).map_err(|_| JwtError::Malformed)
+}
+
+fn decode_param(param: &Option<String>) -> Result<Vec<u8>, JwtError> {
+  param
+    .as_deref()
+    .and_then(|param| URL_SAFE_NO_PAD.decode(param).ok())
+    .ok_or(JwtError::UnknownKey)
+}
+
+fn verify_signature(
+  alg: &str,
+  key: &Jwk,
+  message: &[u8],
+  signature: &[u8],
+) -> Result<bool, JwtError> {
+  let rsa = |parameters: &'static signature::RsaParameters| {
+    if key.kty != "RSA" {
+      return Ok(false);
+    }
+    let components = signature::RsaPublicKeyComponents {
+      n: decode_param(&key.n)?,
+      e: decode_param(&key.e)?,
+    };
+    Ok(components.verify(parameters, message, signature).is_ok())
+  };
+  let ecdsa = |crv: &str, algorithm: &'static signature::EcdsaVerificationAlgorithm| {
+    if key.kty != "EC" || key.crv.as_deref() != Some(crv) {
+      return Ok(false);
+    }
+    let mut point = vec![0x04];
+    point.extend(decode_param(&key.x)?);
+    point.extend(decode_param(&key.y)?);
+    Ok(
+      signature::UnparsedPublicKey::new(algorithm, point)
+        .verify(message, signature)
+        .is_ok(),
+    )
+  };
+  let hmac = |algorithm: hmac::Algorithm| {
+    if key.kty != "oct" {
+      return Ok(false);
+    }
+    let secret = hmac::Key::new(algorithm, &decode_param(&key.k)?);
+    Ok(hmac::verify(&secret, message, signature).is_ok())
+  };
+  match alg {
+    "RS256" => rsa(&signature::RSA_PKCS1_2048_8192_SHA256),
+    "RS384" => rsa(&signature::RSA_PKCS1_2048_8192_SHA384),
+    "RS512" => rsa(&signature::RSA_PKCS1_2048_8192_SHA512),
+    "PS256" => rsa(&signature::RSA_PSS_2048_8192_SHA256),
+    "PS384" => rsa(&signature::RSA_PSS_2048_8192_SHA384),
+    "PS512" => rsa(&signature::RSA_PSS_2048_8192_SHA512),
+    "ES256" => ecdsa("P-256", &signature::ECDSA_P256_SHA256_FIXED),
+    "ES384" => ecdsa("P-384", &signature::ECDSA_P384_SHA384_FIXED),
+    "EdDSA" => {
+      if key.kty != "OKP" || key.crv.as_deref() != Some("Ed25519") {
+        return Ok(false);
+      }
+      Ok(
+        signature::UnparsedPublicKey::new(&signature::ED25519, decode_param(&key.x)?)
+          .verify(message, signature)
+          .is_ok(),
+      )
+    }
+    "HS256" => hmac(hmac::HMAC_SHA256),
+    "HS384" => hmac(hmac::HMAC_SHA384),
+    "HS512" => hmac(hmac::HMAC_SHA512),
+    alg => Err(JwtError::UnsupportedAlgorithm(alg.to_owned())),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, Ed25519KeyPair, KeyPair},
+  };
+  use serde_json::json;
+
+  use super::*;
+
+  const NOW: i64 = 1_700_000_000;
+  const SECRET: &[u8] = b"a secret of at least thirty-two bytes";
+
+  fn encode(value: &Value) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(value).unwrap())
+  }
+
+  fn token(header: Value, claims: Value, sign: impl Fn(&[u8]) -> Vec<u8>) -> String {
+    let message = format!("{}.{}", encode(&header), encode(&claims));
+    let signature = sign(message.as_bytes());
+    format!("{}.{}", message, URL_SAFE_NO_PAD.encode(signature))
+  }
+
+  fn hs256(secret: &[u8], claims: Value) -> String {
+    token(json!({ "alg": "HS256" }), claims, |message| {
+      let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+      hmac::sign(&key, message).as_ref().to_vec()
+    })
+  }
+
+  fn jwk(kty: &str) -> Jwk {
+    Jwk {
+      kty: kty.to_owned(),
+      kid: None,
+      alg: None,
+      crv: None,
+      n: None,
+      e: None,
+      x: None,
+      y: None,
+      k: None,
+    }
+  }
+
+  fn oct(secret: &[u8]) -> Jwk {
+    Jwk {
+      k: Some(URL_SAFE_NO_PAD.encode(secret)),
+      ..jwk("oct")
+    }
+  }
+
+  fn validation() -> Validation<'static> {
+    Validation {
+      now: NOW,
+      leeway: 10,
+      issuer: Some("https://issuer.example.com"),
+      audience: Some("object-storage"),
+    }
+  }
+
+  fn claims() -> Value {
+    json!({
+      "iss": "https://issuer.example.com",
+      "aud": "object-storage",
+      "exp": NOW + 60,
+      "nbf": NOW - 60,
+      "sub": 1,
+    })
+  }
+
+  fn with(claims: Value, name: &str, value: Value) -> Value {
+    let mut claims = claims;
+    claims[name] = value;
+    claims
+  }
+
+  fn es256() -> (EcdsaKeyPair, Jwk) {
+    let rng = SystemRandom::new();
+    let algorithm = &signature::ECDSA_P256_SHA256_FIXED_SIGNING;
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(algorithm, &rng).unwrap();
+    let pair = EcdsaKeyPair::from_pkcs8(algorithm, pkcs8.as_ref(), &rng).unwrap();
+    let (x, y) = pair.public_key().as_ref()[1..].split_at(32);
+    let key = Jwk {
+      crv: Some("P-256".to_owned()),
+      x: Some(URL_SAFE_NO_PAD.encode(x)),
+      y: Some(URL_SAFE_NO_PAD.encode(y)),
+      ..jwk("EC")
+    };
+    (pair, key)
+  }
+
+  fn es256_token(pair: &EcdsaKeyPair, claims: Value) -> String {
+    token(json!({ "alg": "ES256" }), claims, |message| {
+      pair
+        .sign(&SystemRandom::new(), message)
+        .unwrap()
+        .as_ref()
+        .to_vec()
+    })
+  }
+
+  fn ed25519() -> (Ed25519KeyPair, Jwk) {
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+    let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+    let key = Jwk {
+      crv: Some("Ed25519".to_owned()),
+      x: Some(URL_SAFE_NO_PAD.encode(pair.public_key().as_ref())),
+      ..jwk("OKP")
+    };
+    (pair, key)
+  }
+
+  fn eddsa_token(pair: &Ed25519KeyPair, claims: Value) -> String {
+    token(json!({ "alg": "EdDSA" }), claims, |message| {
+      pair.sign(message).as_ref().to_vec()
+    })
+  }
+
+  #[test]
+  fn verified_tokens() {
+    let verified = verify(&hs256(SECRET, claims()), &[oct(SECRET)], &validation()).unwrap();
+    assert_eq!(verified.get("sub"), Some(&json!(1)));
+
+    let (pair, key) = es256();
+    assert!(verify(&es256_token(&pair, claims()), &[key], &validation()).is_ok());
+    let (pair, key) = ed25519();
+    assert!(verify(&eddsa_token(&pair, claims()), &[key], &validation()).is_ok());
+  }
+
+  #[test]
+  fn tampered_tokens() {
+    let token = hs256(SECRET, claims());
+    assert!(matches!(
+      verify(&token, &[oct(b"another secret")], &validation()),
+      Err(JwtError::InvalidSignature)
+    ));
+    let (_, signature) = token.rsplit_once('.').unwrap();
+    let forged = format!(
+      "{}.{}.{}",
+      encode(&json!({ "alg": "HS256" })),
+      encode(&with(claims(), "sub", json!(2))),
+      signature
+    );
+    assert!(matches!(
+      verify(&forged, &[oct(SECRET)], &validation()),
+      Err(JwtError::InvalidSignature)
+    ));
+    for malformed in ["", "a.b", "a.b.c.d", &format!("!{}", token)] {
+      assert!(matches!(
+        verify(malformed, &[oct(SECRET)], &validation()),
+        Err(JwtError::Malformed)
+      ));
+    }
+  }
+
+  #[test]
+  fn algorithm_confusion() {
+    // a token signed with HS256 using the public key of an asymmetric key as the secret
+    let n = b"the modulus of an rsa public key".to_vec();
+    let rsa = Jwk {
+      n: Some(URL_SAFE_NO_PAD.encode(&n)),
+      e: Some("AQAB".to_owned()),
+      ..jwk("RSA")
+    };
+    assert!(matches!(
+      verify(&hs256(&n, claims()), &[rsa], &validation()),
+      Err(JwtError::InvalidSignature)
+    ));
+    let (_, ec) = es256();
+    let x = URL_SAFE_NO_PAD.decode(ec.x.as_deref().unwrap()).unwrap();
+    assert!(matches!(
+      verify(&hs256(&x, claims()), &[ec], &validation()),
+      Err(JwtError::InvalidSignature)
+    ));
+
+    // a key pinned to an algorithm is never used with another one
+    let pinned = Jwk {
+      alg: Some("HS512".to_owned()),
+      ..oct(SECRET)
+    };
+    assert!(matches!(
+      verify(&hs256(SECRET, claims()), &[pinned], &validation()),
+      Err(JwtError::UnknownKey)
+    ));
+
+    let unsigned = format!(
+      "{}.{}.",
+      encode(&json!({ "alg": "none" })),
+      encode(&claims())
+    );
+    assert!(matches!(
+      verify(&unsigned, &[oct(SECRET)], &validation()),
+      Err(JwtError::UnsupportedAlgorithm(alg)) if alg == "none"
+    ));
+  }
+
+  #[test]
+  fn key_type_confusion() {
+    // the key parameters match but the key is declared as another type or curve
+    let (pair, key) = es256();
+    let token = es256_token(&pair, claims());
+    for key in [
+      Jwk {
+        kty: "oct".to_owned(),
+        ..key.clone()
+      },
+      Jwk {
+        kty: "OKP".to_owned(),
+        ..key.clone()
+      },
+      Jwk {
+        crv: Some("P-384".to_owned()),
+        ..key
+      },
+    ] {
+      assert!(matches!(
+        verify(&token, &[key], &validation()),
+        Err(JwtError::InvalidSignature)
+      ));
+    }
+
+    let (pair, key) = ed25519();
+    let token = eddsa_token(&pair, claims());
+    assert!(matches!(
+      verify(
+        &token,
+        &[Jwk {
+          kty: "EC".to_owned(),
+          ..key
+        }],
+        &validation()
+      ),
+      Err(JwtError::InvalidSignature)
+    ));
+
+    let secret = Jwk {
+      k: None,
+      ..jwk("oct")
+    };
+    assert!(matches!(
+      verify(&hs256(SECRET, claims()), &[secret], &validation()),
+      Err(JwtError::UnknownKey)
+    ));
+  }
+
+  #[test]
+  fn key_ids() {
+    let keys = [
+      Jwk {
+        kid: Some("old".to_owned()),
+        ..oct(b"old secret")
+      },
+      Jwk {
+        kid: Some("new".to_owned()),
+        ..oct(SECRET)
+      },
+    ];
+    let signed = |kid: &str| {
+      token(json!({ "alg": "HS256", "kid": kid }), claims(), |message| {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, SECRET);
+        hmac::sign(&key, message).as_ref().to_vec()
+      })
+    };
+    assert!(verify(&signed("new"), &keys, &validation()).is_ok());
+    assert!(matches!(
+      verify(&signed("old"), &keys, &validation()),
+      Err(JwtError::InvalidSignature)
+    ));
+    assert!(matches!(
+      verify(&signed("other"), &keys, &validation()),
+      Err(JwtError::UnknownKey)
+    ));
+    // a token without a kid is checked against every key
+    assert!(verify(&hs256(SECRET, claims()), &keys, &validation()).is_ok());
+    // a token with a kid is never checked against keys without one
+    assert!(matches!(
+      verify(&signed("new"), &[oct(SECRET)], &validation()),
+      Err(JwtError::UnknownKey)
+    ));
+  }
+
+  #[test]
+  fn expiry() {
+    let verify_claims = |claims| verify(&hs256(SECRET, claims), &[oct(SECRET)], &validation());
+    assert!(verify_claims(with(claims(), "exp", json!(NOW - 5))).is_ok());
+    assert!(matches!(
+      verify_claims(with(claims(), "exp", json!(NOW - 11))),
+      Err(JwtError::Expired)
+    ));
+    assert!(verify_claims(with(claims(), "nbf", json!(NOW + 5))).is_ok());
+    assert!(matches!(
+      verify_claims(with(claims(), "nbf", json!(NOW + 11))),
+      Err(JwtError::NotYetValid)
+    ));
+  }
+
+  #[test]
+  fn issuer_and_audience() {
+    let verify_claims = |claims| verify(&hs256(SECRET, claims), &[oct(SECRET)], &validation());
+    assert!(verify_claims(with(claims(), "aud", json!(["other", "object-storage"]))).is_ok());
+    for aud in [json!("other"), json!(["other"]), json!([]), Value::Null] {
+      assert!(matches!(
+        verify_claims(with(claims(), "aud", aud)),
+        Err(JwtError::InvalidAudience)
+      ));
+    }
+    assert!(matches!(
+      verify_claims(with(claims(), "iss", json!("https://other.example.com"))),
+      Err(JwtError::InvalidIssuer)
+    ));
+
+    let unchecked = Validation {
+      issuer: None,
+      audience: None,
+      ..validation()
+    };
+    let token = hs256(SECRET, json!({ "exp": NOW + 60 }));
+    assert!(verify(&token, &[oct(SECRET)], &unchecked).is_ok());
+  }
+}
@@ -1,4 +1,10 @@
+pub mod checksum;
+pub mod conditional;
 pub mod config;
 pub mod database;
 pub mod error;
+pub mod jwt;
 pub mod openapi;
+pub mod presign;
+pub mod range;
+pub mod sigv4;
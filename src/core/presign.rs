@@ -0,0 +1,216 @@
+use super::sigv4::{hmac_sha256, signatures_match, uri_encode};
+
+pub const PATH_PARAM: &str = "path";
+pub const APP_PARAM: &str = "app";
+pub const EXPIRES_PARAM: &str = "expires";
+pub const CONTENT_TYPE_PARAM: &str = "content_type";
+pub const MAX_SIZE_PARAM: &str = "max_size";
+pub const SIGNATURE_PARAM: &str = "signature";
+
+/// a request allowed by a presigned url, every field is covered by the signature
+#[derive(Debug, Clone)]
+pub struct Presigned {
+  pub method: String,
+  /// the path of the url, which names the endpoint and object
+  pub uri_path: String,
+  /// the object path of by-path endpoints
+  pub path: Option<String>,
+  pub app: i64,
+  /// unix time after which the url is rejected
+  pub expires: i64,
+  /// the `Content-Type` a write must be sent with
+  pub content_type: Option<String>,
+  /// the largest body a write may send in bytes
+  pub max_size: Option<u64>,
+}
+
+impl Presigned {
+  /// reads the presigned parameters from a request query, `Ok(None)` when the request is not
+  /// presigned and otherwise the name of the malformed parameter on error
+  pub fn from_query(
+    method: &str,
+    uri_path: &str,
+    query: &[(String, String)],
+  ) -> Result<Option<(Self, String)>, &'static str> {
+    let value = |name: &str| {
+      query
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.clone())
+    };
+    let signature = match value(SIGNATURE_PARAM) {
+      Some(signature) => signature,
+      None => return Ok(None),
+    };
+    let app = value(APP_PARAM)
+      .and_then(|app| app.parse().ok())
+      .ok_or(APP_PARAM)?;
+    let expires = value(EXPIRES_PARAM)
+      .and_then(|expires| expires.parse().ok())
+      .ok_or(EXPIRES_PARAM)?;
+    let max_size = match value(MAX_SIZE_PARAM) {
+      Some(max_size) => Some(max_size.parse().map_err(|_| MAX_SIZE_PARAM)?),
+      None => None,
+    };
+    Ok(Some((
+      Self {
+        method: method.to_owned(),
+        uri_path: uri_path.to_owned(),
+        path: value(PATH_PARAM),
+        app,
+        expires,
+        content_type: value(CONTENT_TYPE_PARAM),
+        max_size,
+      },
+      signature,
+    )))
+  }
+
+  fn string_to_sign(&self) -> String {
+    [
+      self.method.clone(),
+      self.uri_path.clone(),
+      self.path.clone().unwrap_or_default(),
+      self.app.to_string(),
+      self.expires.to_string(),
+      self.content_type.clone().unwrap_or_default(),
+      self
+        .max_size
+        .map(|max_size| max_size.to_string())
+        .unwrap_or_default(),
+    ]
+    .join("\n")
+  }
+
+  pub fn signature(&self, key: &str) -> String {
+    hex::encode(hmac_sha256(
+      key.as_bytes(),
+      self.string_to_sign().as_bytes(),
+    ))
+  }
+
+  pub fn verify(&self, key: &str, signature: &str) -> bool {
+    signatures_match(&self.signature(key), signature)
+  }
+
+  /// whether the url expired before the unix time `now`
+  pub fn expired(&self, now: i64) -> bool {
+    now > self.expires
+  }
+
+  /// the query string of the url including its signature
+  pub fn query(&self, key: &str) -> String {
+    let mut query = Vec::new();
+    if let Some(path) = &self.path {
+      query.push((PATH_PARAM, path.clone()));
+    }
+    query.push((APP_PARAM, self.app.to_string()));
+    query.push((EXPIRES_PARAM, self.expires.to_string()));
+    if let Some(content_type) = &self.content_type {
+      query.push((CONTENT_TYPE_PARAM, content_type.clone()));
+    }
+    if let Some(max_size) = self.max_size {
+      query.push((MAX_SIZE_PARAM, max_size.to_string()));
+    }
+    query.push((SIGNATURE_PARAM, self.signature(key)));
+    query
+      .into_iter()
+      .map(|(key, value)| format!("{}={}", key, uri_encode(&value, true)))
+      .collect::<Vec<_>>()
+      .join("&")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::middleware::s3_authorization::parse_query;
+
+  const KEY: &str = "presign-key";
+
+  fn presigned() -> Presigned {
+    Presigned {
+      method: "PUT".to_owned(),
+      uri_path: "/objects/by-path/write".to_owned(),
+      path: Some("photos/cat 1.png".to_owned()),
+      app: 1,
+      expires: 1_700_000_000,
+      content_type: Some("image/png".to_owned()),
+      max_size: Some(1024),
+    }
+  }
+
+  fn parse(method: &str, uri_path: &str, query: &str) -> (Presigned, String) {
+    Presigned::from_query(method, uri_path, &parse_query(query))
+      .unwrap()
+      .unwrap()
+  }
+
+  #[test]
+  fn round_trip() {
+    let query = presigned().query(KEY);
+    let (parsed, signature) = parse("PUT", "/objects/by-path/write", &query);
+    assert_eq!(parsed.path.as_deref(), Some("photos/cat 1.png"));
+    assert_eq!(parsed.app, 1);
+    assert_eq!(parsed.expires, 1_700_000_000);
+    assert_eq!(parsed.content_type.as_deref(), Some("image/png"));
+    assert_eq!(parsed.max_size, Some(1024));
+    assert!(parsed.verify(KEY, &signature));
+    assert!(!parsed.verify("other-key", &signature));
+  }
+
+  #[test]
+  fn tampered_urls() {
+    let query = presigned().query(KEY);
+    let (_, signature) = parse("PUT", "/objects/by-path/write", &query);
+    let tampered: [fn(&mut Presigned); 7] = [
+      |presigned| presigned.method = "GET".to_owned(),
+      |presigned| presigned.uri_path = "/objects/by-path/read".to_owned(),
+      |presigned| presigned.path = Some("photos/dog.png".to_owned()),
+      |presigned| presigned.app = 2,
+      |presigned| presigned.expires += 1,
+      |presigned| presigned.content_type = None,
+      |presigned| presigned.max_size = Some(1_000_000),
+    ];
+    for tamper in tampered {
+      let mut presigned = presigned();
+      tamper(&mut presigned);
+      assert!(!presigned.verify(KEY, &signature));
+    }
+
+    // the signature is bound to the method and path the url is used with
+    let (parsed, signature) = parse("GET", "/objects/by-path/write", &query);
+    assert!(!parsed.verify(KEY, &signature));
+    let edited = query.replace("max_size=1024", "max_size=4096");
+    let (parsed, signature) = parse("PUT", "/objects/by-path/write", &edited);
+    assert!(!parsed.verify(KEY, &signature));
+    assert!(!presigned().verify(KEY, &signature[1..]));
+  }
+
+  #[test]
+  fn expired_urls() {
+    let presigned = presigned();
+    assert!(!presigned.expired(presigned.expires - 1));
+    assert!(!presigned.expired(presigned.expires));
+    assert!(presigned.expired(presigned.expires + 1));
+  }
+
+  #[test]
+  fn malformed_queries() {
+    assert!(Presigned::from_query("GET", "/", &parse_query("path=a"))
+      .unwrap()
+      .is_none());
+    for (query, name) in [
+      ("expires=1&signature=00", APP_PARAM),
+      ("app=x&expires=1&signature=00", APP_PARAM),
+      ("app=1&signature=00", EXPIRES_PARAM),
+      ("app=1&expires=soon&signature=00", EXPIRES_PARAM),
+      ("app=1&expires=1&max_size=-1&signature=00", MAX_SIZE_PARAM),
+    ] {
+      assert_eq!(
+        Presigned::from_query("GET", "/", &parse_query(query)).err(),
+        Some(name)
+      );
+    }
+  }
+}
@@ -0,0 +1,136 @@
+use std::ops::Range;
+
+pub const BYTES_UNIT: &str = "bytes";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RangeError {
+  /// the header could not be parsed and should be ignored
+  Invalid,
+  /// none of the requested ranges overlap the object
+  Unsatisfiable,
+}
+
+/// parses a `Range` header value into byte ranges with exclusive ends clamped to `size`
+pub fn parse_range(value: &str, size: u64) -> Result<Vec<Range<u64>>, RangeError> {
+  let specs = match value.trim().split_once('=') {
+    Some((unit, specs)) if unit.trim().eq_ignore_ascii_case(BYTES_UNIT) => specs,
+    _ => return Err(RangeError::Invalid),
+  };
+
+  let mut ranges = Vec::new();
+  for spec in specs.split(',') {
+    let (start, end) = match spec.trim().split_once('-') {
+      Some((start, end)) => (start.trim(), end.trim()),
+      None => return Err(RangeError::Invalid),
+    };
+    let range = if start.is_empty() {
+      let suffix = end.parse::<u64>().map_err(|_| RangeError::Invalid)?;
+      if suffix == 0 {
+        continue;
+      }
+      size.saturating_sub(suffix)..size
+    } else {
+      let start = start.parse::<u64>().map_err(|_| RangeError::Invalid)?;
+      let end = if end.is_empty() {
+        size
+      } else {
+        let end = end.parse::<u64>().map_err(|_| RangeError::Invalid)?;
+        if end < start {
+          return Err(RangeError::Invalid);
+        }
+        end.saturating_add(1).min(size)
+      };
+      start..end
+    };
+    if range.start < size {
+      ranges.push(range);
+    }
+  }
+
+  if ranges.is_empty() {
+    return Err(RangeError::Unsatisfiable);
+  }
+  Ok(ranges)
+}
+
+pub fn content_range(range: &Range<u64>, size: u64) -> String {
+  format!(
+    "{} {}-{}/{}",
+    BYTES_UNIT,
+    range.start,
+    range.end.saturating_sub(1),
+    size
+  )
+}
+
+pub fn unsatisfied_content_range(size: u64) -> String {
+  format!("{} */{}", BYTES_UNIT, size)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn single(range: Range<u64>) -> Vec<Range<u64>> {
+    vec![range]
+  }
+
+  #[test]
+  fn single_ranges() {
+    assert_eq!(parse_range("bytes=0-9", 100), Ok(single(0..10)));
+    assert_eq!(parse_range(" Bytes = 10 - ", 100), Ok(single(10..100)));
+    assert_eq!(parse_range("bytes=-20", 100), Ok(single(80..100)));
+    assert_eq!(parse_range("bytes=-200", 100), Ok(single(0..100)));
+    assert_eq!(parse_range("bytes=90-200", 100), Ok(single(90..100)));
+  }
+
+  #[test]
+  fn multiple_ranges() {
+    assert_eq!(
+      parse_range("bytes=0-1, 4-5,-2", 10),
+      Ok(vec![0..2, 4..6, 8..10])
+    );
+    // ranges past the end are dropped as long as one of them is satisfiable
+    assert_eq!(parse_range("bytes=50-60,0-0", 10), Ok(single(0..1)));
+  }
+
+  #[test]
+  fn malformed_ranges() {
+    for value in [
+      "",
+      "0-9",
+      "items=0-9",
+      "bytes=",
+      "bytes=9",
+      "bytes=a-9",
+      "bytes=0-b",
+      "bytes=-",
+      "bytes=9-0",
+      "bytes=0-1,x",
+      "bytes=--1",
+    ] {
+      assert_eq!(parse_range(value, 100), Err(RangeError::Invalid), "{value}");
+    }
+  }
+
+  #[test]
+  fn unsatisfiable_ranges() {
+    assert_eq!(
+      parse_range("bytes=100-", 100),
+      Err(RangeError::Unsatisfiable)
+    );
+    assert_eq!(
+      parse_range("bytes=100-200,300-", 100),
+      Err(RangeError::Unsatisfiable)
+    );
+    assert_eq!(parse_range("bytes=-0", 100), Err(RangeError::Unsatisfiable));
+    assert_eq!(parse_range("bytes=0-", 0), Err(RangeError::Unsatisfiable));
+    assert_eq!(unsatisfied_content_range(100), "bytes */100");
+  }
+
+  #[test]
+  fn content_ranges() {
+    assert_eq!(content_range(&(0..10), 100), "bytes 0-9/100");
+    assert_eq!(content_range(&(99..100), 100), "bytes 99-99/100");
+  }
+}
@@ -1,9 +1,11 @@
 #[macro_use]
 extern crate lazy_static;
 
+pub mod auth;
 pub mod core;
 pub mod middleware;
 pub mod model;
 pub mod repository;
 pub mod router;
 pub mod service;
+pub mod storage;
@@ -3,12 +3,17 @@ use std::{net::SocketAddr, str::FromStr, sync::Arc};
 use axum::Router;
 use clap::Parser;
 use object_storage::{
+  auth::{create_auth_provider, refresh_task},
   core::{
     config::Config,
     database::{close_pool, init_pool},
     error::InternalError,
   },
   router::{create_router, RouterState},
+  service::{
+    lifecycle::lifecycle_task, multipart::cleanup_task, object::recover_writes, trash::purge_task,
+  },
+  storage::create_storage,
 };
 use tokio::fs::create_dir_all;
 use tokio_util::sync::CancellationToken;
@@ -51,9 +56,47 @@ async fn main() -> Result<(), InternalError> {
 
   let cancellation_token = CancellationToken::new();
 
+  let storage = create_storage(config.as_ref())?;
+
+  let recovered = recover_writes(&pool, storage.as_ref()).await?;
+  if recovered > 0 {
+    log::info!("Recovered {} interrupted object writes", recovered);
+  }
+
+  let cleanup_handle = tokio::spawn(cleanup_task(
+    pool.clone(),
+    storage.clone(),
+    config.clone(),
+    cancellation_token.clone(),
+  ));
+
+  let purge_handle = tokio::spawn(purge_task(
+    pool.clone(),
+    storage.clone(),
+    config.clone(),
+    cancellation_token.clone(),
+  ));
+
+  let lifecycle_handle = tokio::spawn(lifecycle_task(
+    pool.clone(),
+    storage.clone(),
+    config.clone(),
+    cancellation_token.clone(),
+  ));
+
+  let auth = create_auth_provider(config.clone());
+
+  let refresh_handle = tokio::spawn(refresh_task(
+    auth.clone(),
+    config.clone(),
+    cancellation_token.clone(),
+  ));
+
   let router = create_router(RouterState {
     config: config.clone(),
     pool: pool.clone(),
+    storage,
+    auth,
   });
   let serve_handle = tokio::spawn(serve(
     router.clone(),
@@ -69,6 +112,30 @@ async fn main() -> Result<(), InternalError> {
       log::error!("Error serving: {}", e);
     }
   }
+  match cleanup_handle.await {
+    Ok(_) => {}
+    Err(e) => {
+      log::error!("Error cleaning up multipart uploads: {}", e);
+    }
+  }
+  match purge_handle.await {
+    Ok(_) => {}
+    Err(e) => {
+      log::error!("Error purging the trash: {}", e);
+    }
+  }
+  match lifecycle_handle.await {
+    Ok(_) => {}
+    Err(e) => {
+      log::error!("Error applying lifecycle rules: {}", e);
+    }
+  }
+  match refresh_handle.await {
+    Ok(_) => {}
+    Err(e) => {
+      log::error!("Error refreshing auth keys: {}", e);
+    }
+  }
   match close_pool().await {
     Ok(_) => {}
     Err(e) => {
@@ -1,21 +1,110 @@
-use auth_client::apis::jwt_api;
 use axum::extract::{FromRef, FromRequestParts};
-use http::request::Parts;
+use chrono::Utc;
+use http::{header, request::Parts, HeaderMap};
 use serde::Deserialize;
 
 use crate::{
+  auth::AuthError,
   core::{
-    error::{InternalError, INVALID_ERROR, REQUIRED_ERROR},
+    error::{InternalError, INTERNAL_ERROR, INVALID_ERROR, NOT_ALLOWED_ERROR, REQUIRED_ERROR},
     openapi::AUTHORIZATION_HEADER,
+    presign::{Presigned, EXPIRES_PARAM, SIGNATURE_PARAM},
+  },
+  middleware::s3_authorization::parse_query,
+  repository::{
+    self,
+    acl::{ObjectAclRow, DELETE_PERMISSION, READ_PERMISSION, WRITE_PERMISSION},
   },
   router::RouterState,
-  service::auth::auth_token_configuration,
+  service::lock::BYPASS_GOVERNANCE_RETENTION_HEADER,
 };
 
 pub const TOKEN_TYPE_BEARER: &str = "bearer";
 
+pub const OBJECTS_READ_SCOPE: &str = "objects:read";
+pub const OBJECTS_WRITE_SCOPE: &str = "objects:write";
+pub const OBJECTS_DELETE_SCOPE: &str = "objects:delete";
+pub const OBJECTS_LIST_SCOPE: &str = "objects:list";
+pub const OBJECTS_ADMIN_SCOPE: &str = "objects:admin";
+
 pub struct Authorization {
   pub claims: Claims,
+  /// the path prefix grants that apply to the token
+  pub acls: Vec<ObjectAclRow>,
+  /// set instead of a token when the request was made with a presigned url
+  pub presigned: Option<Presigned>,
+}
+
+impl Authorization {
+  /// whether the token may act on `path` with `scope`, either through the scope itself or a grant
+  /// on a prefix of `path`
+  pub fn can(&self, scope: &str, path: &str) -> bool {
+    let path = path.trim_start_matches('/');
+    if let Some(presigned) = &self.presigned {
+      return presigned_scope(&presigned.method) == Some(scope)
+        && presigned.path.as_deref().map_or(true, |presigned_path| {
+          presigned_path.trim_start_matches('/') == path
+        });
+    }
+    if self.claims.has_scope(scope) {
+      return true;
+    }
+    match scope_permission(scope) {
+      Some(permission) => self.acls.iter().any(|acl| acl.allows(permission, path)),
+      None => false,
+    }
+  }
+
+  /// fails with 403 unless the token may act on `path` with `scope`
+  pub fn require(&self, scope: &str, path: &str) -> Result<(), InternalError> {
+    if self.can(scope, path) {
+      return Ok(());
+    }
+    log::error!(
+      "token for {} is not allowed {} on {}",
+      self.claims.sub,
+      scope,
+      path
+    );
+    Err(InternalError::forbidden().with_error("scopes", NOT_ALLOWED_ERROR))
+  }
+
+  /// whether the request asks to bypass governance retention and the token is an admin's
+  pub fn bypass_governance(&self, headers: &HeaderMap) -> bool {
+    self.presigned.is_none()
+      && self.claims.has_scope(OBJECTS_ADMIN_SCOPE)
+      && headers
+        .get(BYPASS_GOVERNANCE_RETENTION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+  }
+
+  /// the body limit for a write, lowered to the size a presigned url allows
+  pub fn max_body_size(&self, max_body_size: u64) -> u64 {
+    match self
+      .presigned
+      .as_ref()
+      .and_then(|presigned| presigned.max_size)
+    {
+      Some(max_size) => max_size.min(max_body_size),
+      None => max_body_size,
+    }
+  }
+
+  /// the prefixes the token may list, `None` when it may list every object
+  pub fn list_prefixes(&self) -> Option<Vec<String>> {
+    if self.claims.has_scope(OBJECTS_LIST_SCOPE) {
+      return None;
+    }
+    Some(
+      self
+        .acls
+        .iter()
+        .filter(|acl| acl.permission == READ_PERMISSION)
+        .map(|acl| acl.prefix.clone())
+        .collect(),
+    )
+  }
 }
 
 impl<S> FromRequestParts<S> for Authorization
@@ -45,37 +134,98 @@ where
           );
         }
       };
-      let claims_value = match jwt_api::jwt_is_valid(
-        &auth_token_configuration(&state.config, authorization_string),
-        &state.config.object_storage.tenant_client_id.to_string(),
-      )
-      .await
-      {
-        Ok(claims_value) => claims_value,
-        Err(e) => {
-          log::error!("failed to validate authorization header: {:?}", e);
-          return Err(
-            InternalError::unauthorized().with_error(AUTHORIZATION_HEADER, INVALID_ERROR),
-          );
-        }
-      };
-      let claims = match serde_json::from_value(serde_json::Value::Object(
-        serde_json::Map::from_iter(claims_value.into_iter()),
-      )) {
-        Ok(claims) => claims,
-        Err(e) => {
-          log::error!("failed to parse auth jwt claims response: {:?}", e);
-          return Err(
-            InternalError::unauthorized().with_error(AUTHORIZATION_HEADER, INVALID_ERROR),
-          );
-        }
-      };
-      return Ok(Self { claims });
+      return token_authorization(&state, Some(authorization_string)).await;
+    }
+    let query = parse_query(parts.uri.query().unwrap_or_default());
+    match Presigned::from_query(parts.method.as_str(), parts.uri.path(), &query) {
+      Ok(Some((presigned, signature))) => {
+        presigned_authorization(&state, parts, presigned, &signature)
+      }
+      Ok(None) => token_authorization(&state, None).await,
+      Err(name) => {
+        log::error!("invalid presigned url parameter {}", name);
+        Err(InternalError::unauthorized().with_error(name, INVALID_ERROR))
+      }
     }
-    Err(InternalError::unauthorized().with_error(AUTHORIZATION_HEADER, REQUIRED_ERROR))
   }
 }
 
+async fn token_authorization(
+  state: &RouterState,
+  token: Option<&str>,
+) -> Result<Authorization, InternalError> {
+  let claims = match state.auth.authenticate(token).await {
+    Ok(claims) => claims,
+    Err(AuthError::Missing) => {
+      return Err(InternalError::unauthorized().with_error(AUTHORIZATION_HEADER, REQUIRED_ERROR));
+    }
+    Err(e) => {
+      log::error!("failed to validate authorization header: {}", e);
+      return Err(InternalError::unauthorized().with_error(AUTHORIZATION_HEADER, INVALID_ERROR));
+    }
+  };
+  let acls = match repository::acl::get_subject_object_acls(
+    &state.pool,
+    claims.app,
+    claims.sub,
+    &claims.sub_type,
+  )
+  .await
+  {
+    Ok(acls) => acls,
+    Err(e) => {
+      log::error!("failed to get object acls: {}", e);
+      return Err(InternalError::internal_error().with_application_error(INTERNAL_ERROR));
+    }
+  };
+  Ok(Authorization {
+    claims,
+    acls,
+    presigned: None,
+  })
+}
+
+fn presigned_authorization(
+  state: &RouterState,
+  parts: &Parts,
+  presigned: Presigned,
+  signature: &str,
+) -> Result<Authorization, InternalError> {
+  let key = &state.config.presign.key;
+  if key.is_empty() || !presigned.verify(key, signature) {
+    log::error!("invalid presigned url signature for {}", presigned.uri_path);
+    return Err(InternalError::unauthorized().with_error(SIGNATURE_PARAM, INVALID_ERROR));
+  }
+  if presigned.expired(Utc::now().timestamp()) {
+    log::error!("presigned url for {} expired", presigned.uri_path);
+    return Err(InternalError::unauthorized().with_error(EXPIRES_PARAM, INVALID_ERROR));
+  }
+  if let Some(content_type) = &presigned.content_type {
+    let request_content_type = parts
+      .headers
+      .get(header::CONTENT_TYPE)
+      .and_then(|value| value.to_str().ok());
+    if request_content_type != Some(content_type.as_str()) {
+      log::error!(
+        "presigned url for {} requires {}",
+        presigned.uri_path,
+        content_type
+      );
+      return Err(
+        InternalError::forbidden().with_error(header::CONTENT_TYPE.as_str(), INVALID_ERROR),
+      );
+    }
+  }
+  Ok(Authorization {
+    claims: Claims {
+      app: presigned.app,
+      ..Default::default()
+    },
+    acls: Vec::new(),
+    presigned: Some(presigned),
+  })
+}
+
 #[derive(Debug, Deserialize, Default, Clone)]
 pub struct Claims {
   pub r#type: String,
@@ -90,3 +240,177 @@ pub struct Claims {
   pub app: i64,
   pub scopes: Vec<String>,
 }
+
+impl Claims {
+  pub fn has_scope(&self, scope: &str) -> bool {
+    self.scopes.iter().any(|s| s == scope)
+  }
+
+  /// fails with 403 unless the token was granted `scope`
+  pub fn require_scope(&self, scope: &str) -> Result<(), InternalError> {
+    if self.has_scope(scope) {
+      return Ok(());
+    }
+    log::error!("token for {} is missing scope {}", self.sub, scope);
+    Err(InternalError::forbidden().with_error("scopes", NOT_ALLOWED_ERROR))
+  }
+}
+
+fn presigned_scope(method: &str) -> Option<&'static str> {
+  match method {
+    "GET" | "HEAD" => Some(OBJECTS_READ_SCOPE),
+    "PUT" => Some(OBJECTS_WRITE_SCOPE),
+    _ => None,
+  }
+}
+
+fn scope_permission(scope: &str) -> Option<&'static str> {
+  match scope {
+    OBJECTS_READ_SCOPE | OBJECTS_LIST_SCOPE => Some(READ_PERMISSION),
+    OBJECTS_WRITE_SCOPE => Some(WRITE_PERMISSION),
+    OBJECTS_DELETE_SCOPE => Some(DELETE_PERMISSION),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use http::HeaderValue;
+
+  use super::*;
+
+  fn acl(prefix: &str, permission: &str) -> ObjectAclRow {
+    ObjectAclRow {
+      id: 1,
+      app: 1,
+      sub: None,
+      sub_type: None,
+      prefix: prefix.to_owned(),
+      permission: permission.to_owned(),
+      created_at: 0,
+    }
+  }
+
+  fn token(scopes: &[&str], acls: Vec<ObjectAclRow>) -> Authorization {
+    Authorization {
+      claims: Claims {
+        app: 1,
+        sub: 1,
+        scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+        ..Default::default()
+      },
+      acls,
+      presigned: None,
+    }
+  }
+
+  fn presigned(method: &str, path: Option<&str>) -> Authorization {
+    Authorization {
+      presigned: Some(Presigned {
+        method: method.to_owned(),
+        uri_path: "/objects/by-path".to_owned(),
+        path: path.map(str::to_owned),
+        app: 1,
+        expires: 0,
+        content_type: None,
+        max_size: Some(1024),
+      }),
+      ..token(&[], Vec::new())
+    }
+  }
+
+  #[test]
+  fn scopes() {
+    let authorization = token(&[OBJECTS_READ_SCOPE, OBJECTS_WRITE_SCOPE], Vec::new());
+    assert!(authorization.can(OBJECTS_READ_SCOPE, "any/path"));
+    assert!(authorization.can(OBJECTS_WRITE_SCOPE, "any/path"));
+    assert!(!authorization.can(OBJECTS_DELETE_SCOPE, "any/path"));
+    assert!(authorization
+      .require(OBJECTS_DELETE_SCOPE, "any/path")
+      .is_err());
+    assert_eq!(authorization.list_prefixes(), Some(Vec::new()));
+  }
+
+  #[test]
+  fn acl_prefixes() {
+    let authorization = token(
+      &[],
+      vec![
+        acl("photos/", READ_PERMISSION),
+        acl("photos/shared/", WRITE_PERMISSION),
+      ],
+    );
+    assert!(authorization.can(OBJECTS_READ_SCOPE, "photos/cat.png"));
+    assert!(authorization.can(OBJECTS_READ_SCOPE, "/photos/cat.png"));
+    assert!(authorization.can(OBJECTS_LIST_SCOPE, "photos/"));
+    assert!(authorization.can(OBJECTS_WRITE_SCOPE, "photos/shared/cat.png"));
+    assert!(authorization
+      .require(OBJECTS_READ_SCOPE, "photos/cat.png")
+      .is_ok());
+
+    // the grant only covers paths under its prefix and its own permission
+    assert!(!authorization.can(OBJECTS_READ_SCOPE, "photo"));
+    assert!(!authorization.can(OBJECTS_READ_SCOPE, "videos/photos/cat.png"));
+    assert!(!authorization.can(OBJECTS_WRITE_SCOPE, "photos/cat.png"));
+    assert!(!authorization.can(OBJECTS_DELETE_SCOPE, "photos/shared/cat.png"));
+    assert!(!authorization.can(OBJECTS_ADMIN_SCOPE, "photos/cat.png"));
+    assert!(authorization
+      .require(OBJECTS_WRITE_SCOPE, "photos/cat.png")
+      .is_err());
+    assert_eq!(
+      authorization.list_prefixes(),
+      Some(vec!["photos/".to_owned()])
+    );
+  }
+
+  #[test]
+  fn presigned_requests() {
+    let authorization = presigned("GET", Some("/photos/cat.png"));
+    assert!(authorization.can(OBJECTS_READ_SCOPE, "photos/cat.png"));
+    assert!(!authorization.can(OBJECTS_READ_SCOPE, "photos/dog.png"));
+    assert!(!authorization.can(OBJECTS_WRITE_SCOPE, "photos/cat.png"));
+    assert!(!authorization.can(OBJECTS_LIST_SCOPE, "photos/cat.png"));
+
+    let authorization = presigned("PUT", Some("photos/cat.png"));
+    assert!(authorization.can(OBJECTS_WRITE_SCOPE, "photos/cat.png"));
+    assert!(!authorization.can(OBJECTS_READ_SCOPE, "photos/cat.png"));
+    assert_eq!(authorization.max_body_size(4096), 1024);
+    assert_eq!(authorization.max_body_size(512), 512);
+
+    // unsupported methods allow nothing
+    assert!(
+      !presigned("DELETE", Some("photos/cat.png")).can(OBJECTS_DELETE_SCOPE, "photos/cat.png")
+    );
+  }
+
+  #[test]
+  fn presigned_requests_ignore_scopes_and_acls() {
+    let authorization = Authorization {
+      claims: Claims {
+        app: 1,
+        scopes: vec![
+          OBJECTS_READ_SCOPE.to_owned(),
+          OBJECTS_WRITE_SCOPE.to_owned(),
+          OBJECTS_DELETE_SCOPE.to_owned(),
+          OBJECTS_ADMIN_SCOPE.to_owned(),
+        ],
+        ..Default::default()
+      },
+      acls: vec![acl("", READ_PERMISSION), acl("", DELETE_PERMISSION)],
+      ..presigned("GET", Some("photos/cat.png"))
+    };
+    assert!(authorization.can(OBJECTS_READ_SCOPE, "photos/cat.png"));
+    assert!(!authorization.can(OBJECTS_READ_SCOPE, "photos/dog.png"));
+    assert!(!authorization.can(OBJECTS_WRITE_SCOPE, "photos/cat.png"));
+    assert!(!authorization.can(OBJECTS_DELETE_SCOPE, "photos/cat.png"));
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+      BYPASS_GOVERNANCE_RETENTION_HEADER,
+      HeaderValue::from_static("true"),
+    );
+    assert!(!authorization.bypass_governance(&headers));
+    assert!(token(&[OBJECTS_ADMIN_SCOPE], Vec::new()).bypass_governance(&headers));
+    assert!(!token(&[OBJECTS_WRITE_SCOPE], Vec::new()).bypass_governance(&headers));
+  }
+}
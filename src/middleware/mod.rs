@@ -1,3 +1,4 @@
 pub mod authorization;
 pub mod json;
+pub mod s3_authorization;
 pub mod validated_json;
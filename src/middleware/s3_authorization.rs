@@ -0,0 +1,280 @@
+use axum::extract::{FromRef, FromRequestParts};
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use http::{header, request::Parts, HeaderMap};
+use percent_encoding::percent_decode_str;
+
+use crate::{
+  core::sigv4::{self, Credentials},
+  model::s3::S3Error,
+  router::RouterState,
+};
+
+pub const AMZ_DATE_HEADER: &str = "x-amz-date";
+pub const AMZ_CONTENT_SHA256_HEADER: &str = "x-amz-content-sha256";
+pub const STREAMING_PAYLOAD_PREFIX: &str = "STREAMING-";
+pub const STREAMING_SIGNED_PAYLOAD: &str = "STREAMING-AWS4-HMAC-SHA256-PAYLOAD";
+pub const STREAMING_SIGNED_PAYLOAD_TRAILER: &str = "STREAMING-AWS4-HMAC-SHA256-PAYLOAD-TRAILER";
+
+const S3_SERVICE: &str = "s3";
+const MAX_CLOCK_SKEW_SECONDS: i64 = 15 * 60;
+const MAX_PRESIGNED_EXPIRES_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// the values needed to verify the signatures of an `aws-chunked` payload
+#[derive(Clone)]
+pub struct ChunkSigning {
+  pub date: DateTime<Utc>,
+  pub scope: String,
+  pub signing_key: Vec<u8>,
+  pub seed_signature: String,
+}
+
+pub struct S3Authorization {
+  pub access_key_id: String,
+  pub app: i64,
+  pub payload_hash: String,
+  pub chunk_signing: Option<ChunkSigning>,
+}
+
+struct SignedRequest {
+  access_key_id: String,
+  date: DateTime<Utc>,
+  region: String,
+  signed_headers: Vec<String>,
+  signature: String,
+  payload_hash: String,
+}
+
+impl<S> FromRequestParts<S> for S3Authorization
+where
+  RouterState: FromRef<S>,
+  S: Send + Sync,
+{
+  type Rejection = S3Error;
+
+  async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+    let state = RouterState::from_ref(state);
+    let query = parse_query(parts.uri.query().unwrap_or_default());
+    let now = Utc::now();
+
+    let signed_request = match parts.headers.get(header::AUTHORIZATION) {
+      Some(authorization) => {
+        let authorization = authorization
+          .to_str()
+          .map_err(|_| S3Error::authorization_header_malformed("invalid Authorization header"))?;
+        let signed_request = parse_authorization_header(authorization, &parts.headers)?;
+        if (now - signed_request.date).num_seconds().abs() > MAX_CLOCK_SKEW_SECONDS {
+          return Err(S3Error::request_time_too_skewed());
+        }
+        signed_request
+      }
+      None => {
+        let signed_request = parse_presigned_query(&query)?;
+        let expires = query_value(&query, "X-Amz-Expires")
+          .and_then(|expires| expires.parse::<i64>().ok())
+          .filter(|expires| (1..=MAX_PRESIGNED_EXPIRES_SECONDS).contains(expires))
+          .ok_or_else(|| S3Error::authorization_header_malformed("invalid X-Amz-Expires"))?;
+        if now > signed_request.date + TimeDelta::seconds(expires) {
+          return Err(S3Error::access_denied());
+        }
+        signed_request
+      }
+    };
+
+    let access_key = state
+      .config
+      .s3
+      .access_keys
+      .iter()
+      .find(|access_key| access_key.access_key_id == signed_request.access_key_id)
+      .ok_or_else(S3Error::invalid_access_key_id)?;
+    if signed_request.region != state.config.s3.region {
+      return Err(S3Error::authorization_header_malformed(format!(
+        "the region '{}' is wrong; expecting '{}'",
+        signed_request.region, state.config.s3.region
+      )));
+    }
+    let credentials = Credentials {
+      access_key_id: &signed_request.access_key_id,
+      secret_access_key: &access_key.secret_access_key,
+      region: &state.config.s3.region,
+      service: S3_SERVICE,
+    };
+
+    let mut canonical_headers = Vec::with_capacity(signed_request.signed_headers.len());
+    for name in &signed_request.signed_headers {
+      let values = parts
+        .headers
+        .get_all(name.as_str())
+        .iter()
+        .map(|value| value.to_str().map(str::trim))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| S3Error::signature_does_not_match())?;
+      if values.is_empty() {
+        return Err(S3Error::signature_does_not_match());
+      }
+      canonical_headers.push((name.clone(), values.join(",")));
+    }
+    let (canonical_headers, signed_headers) = sigv4::canonical_headers(&canonical_headers);
+    let canonical_uri = sigv4::uri_encode(
+      &percent_decode_str(parts.uri.path()).decode_utf8_lossy(),
+      false,
+    );
+    let canonical_query = sigv4::canonical_query_string(
+      &query
+        .iter()
+        .filter(|(key, _)| key != "X-Amz-Signature")
+        .cloned()
+        .collect::<Vec<_>>(),
+    );
+    let canonical_request = sigv4::canonical_request(
+      parts.method.as_str(),
+      &canonical_uri,
+      &canonical_query,
+      &canonical_headers,
+      &signed_headers,
+      &signed_request.payload_hash,
+    );
+    let scope = sigv4::scope(&signed_request.date, &credentials);
+    let string_to_sign = sigv4::string_to_sign(&signed_request.date, &scope, &canonical_request);
+    let signing_key = sigv4::signing_key(&signed_request.date, &credentials);
+    let signature = hex::encode(sigv4::hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+    if !sigv4::signatures_match(&signature, &signed_request.signature) {
+      return Err(S3Error::signature_does_not_match());
+    }
+
+    let chunk_signing = match signed_request.payload_hash.as_str() {
+      STREAMING_SIGNED_PAYLOAD | STREAMING_SIGNED_PAYLOAD_TRAILER => Some(ChunkSigning {
+        date: signed_request.date,
+        scope,
+        signing_key,
+        seed_signature: signature,
+      }),
+      _ => None,
+    };
+
+    Ok(Self {
+      access_key_id: signed_request.access_key_id,
+      app: access_key.app,
+      payload_hash: signed_request.payload_hash,
+      chunk_signing,
+    })
+  }
+}
+
+fn parse_authorization_header(
+  authorization: &str,
+  headers: &HeaderMap,
+) -> Result<SignedRequest, S3Error> {
+  let fields = match authorization.strip_prefix(sigv4::ALGORITHM) {
+    Some(fields) => fields,
+    None => return Err(S3Error::access_denied()),
+  };
+  let mut credential = None;
+  let mut signed_headers = None;
+  let mut signature = None;
+  for field in fields.split(',') {
+    match field.trim().split_once('=') {
+      Some(("Credential", value)) => credential = Some(value),
+      Some(("SignedHeaders", value)) => signed_headers = Some(value),
+      Some(("Signature", value)) => signature = Some(value),
+      _ => {}
+    }
+  }
+  let (credential, signed_headers, signature) = match (credential, signed_headers, signature) {
+    (Some(credential), Some(signed_headers), Some(signature)) => {
+      (credential, signed_headers, signature)
+    }
+    _ => {
+      return Err(S3Error::authorization_header_malformed(
+        "the authorization header is malformed",
+      ))
+    }
+  };
+  let amz_date = headers
+    .get(AMZ_DATE_HEADER)
+    .and_then(|value| value.to_str().ok())
+    .ok_or_else(S3Error::access_denied)?;
+  let payload_hash = headers
+    .get(AMZ_CONTENT_SHA256_HEADER)
+    .and_then(|value| value.to_str().ok())
+    .ok_or_else(|| S3Error::invalid_argument("x-amz-content-sha256 header is required"))?;
+  let (access_key_id, region) = parse_credential(credential)?;
+  Ok(SignedRequest {
+    access_key_id,
+    date: parse_amz_date(amz_date)?,
+    region,
+    signed_headers: signed_headers.split(';').map(str::to_owned).collect(),
+    signature: signature.to_owned(),
+    payload_hash: payload_hash.to_owned(),
+  })
+}
+
+fn parse_presigned_query(query: &[(String, String)]) -> Result<SignedRequest, S3Error> {
+  if query_value(query, "X-Amz-Algorithm") != Some(sigv4::ALGORITHM) {
+    return Err(S3Error::access_denied());
+  }
+  let (credential, amz_date, signed_headers, signature) = match (
+    query_value(query, "X-Amz-Credential"),
+    query_value(query, "X-Amz-Date"),
+    query_value(query, "X-Amz-SignedHeaders"),
+    query_value(query, "X-Amz-Signature"),
+  ) {
+    (Some(credential), Some(amz_date), Some(signed_headers), Some(signature)) => {
+      (credential, amz_date, signed_headers, signature)
+    }
+    _ => {
+      return Err(S3Error::authorization_header_malformed(
+        "the presigned query is missing required parameters",
+      ))
+    }
+  };
+  let (access_key_id, region) = parse_credential(credential)?;
+  Ok(SignedRequest {
+    access_key_id,
+    date: parse_amz_date(amz_date)?,
+    region,
+    signed_headers: signed_headers.split(';').map(str::to_owned).collect(),
+    signature: signature.to_owned(),
+    payload_hash: sigv4::UNSIGNED_PAYLOAD.to_owned(),
+  })
+}
+
+/// splits `<access key>/<date>/<region>/s3/aws4_request` into the access key and region
+fn parse_credential(credential: &str) -> Result<(String, String), S3Error> {
+  let parts = credential.split('/').collect::<Vec<_>>();
+  match parts.as_slice() {
+    [access_key_id, _, region, S3_SERVICE, sigv4::TERMINATOR] => {
+      Ok(((*access_key_id).to_owned(), (*region).to_owned()))
+    }
+    _ => Err(S3Error::authorization_header_malformed(
+      "the credential is malformed",
+    )),
+  }
+}
+
+fn parse_amz_date(amz_date: &str) -> Result<DateTime<Utc>, S3Error> {
+  NaiveDateTime::parse_from_str(amz_date, sigv4::AMZ_DATE_FORMAT)
+    .map(|date| date.and_utc())
+    .map_err(|_| S3Error::access_denied())
+}
+
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+  query
+    .split('&')
+    .filter(|pair| !pair.is_empty())
+    .map(|pair| {
+      let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+      (
+        percent_decode_str(key).decode_utf8_lossy().into_owned(),
+        percent_decode_str(value).decode_utf8_lossy().into_owned(),
+      )
+    })
+    .collect()
+}
+
+fn query_value<'a>(query: &'a [(String, String)], name: &str) -> Option<&'a str> {
+  query
+    .iter()
+    .find(|(key, _)| key == name)
+    .map(|(_, value)| value.as_str())
+}
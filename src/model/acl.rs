@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::repository::acl::{ObjectAclRow, DELETE_PERMISSION, READ_PERMISSION, WRITE_PERMISSION};
+
+use super::util::Pagination;
+
+#[derive(Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AclPermission {
+  Read,
+  Write,
+  Delete,
+}
+
+impl AclPermission {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Read => READ_PERMISSION,
+      Self::Write => WRITE_PERMISSION,
+      Self::Delete => DELETE_PERMISSION,
+    }
+  }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateObjectAclRequest {
+  /// the subject the grant applies to, any subject when omitted
+  pub sub: Option<i64>,
+  /// the subject type the grant applies to, any type when omitted
+  pub sub_type: Option<String>,
+  /// paths starting with the prefix are covered, e.g. `users/42/`
+  pub prefix: String,
+  pub permission: AclPermission,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ObjectAcl {
+  pub id: i64,
+  pub sub: Option<i64>,
+  pub sub_type: Option<String>,
+  pub prefix: String,
+  pub permission: String,
+  pub created_at: DateTime<Utc>,
+}
+
+impl From<ObjectAclRow> for ObjectAcl {
+  fn from(row: ObjectAclRow) -> Self {
+    Self {
+      id: row.id,
+      sub: row.sub,
+      sub_type: row.sub_type,
+      prefix: row.prefix,
+      permission: row.permission,
+      created_at: DateTime::<Utc>::from_timestamp(row.created_at, 0).unwrap_or_default(),
+    }
+  }
+}
+
+pub type ObjectAclPagination = Pagination<ObjectAcl>;
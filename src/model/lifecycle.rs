@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::repository::lifecycle::{LifecycleRuleRow, CREATED_AGE, UPDATED_AGE};
+
+use super::util::Pagination;
+
+#[derive(Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LifecycleAgeFrom {
+  /// objects expire `days` after they were created
+  Created,
+  /// objects expire `days` after they were last written
+  Updated,
+}
+
+impl LifecycleAgeFrom {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Created => CREATED_AGE,
+      Self::Updated => UPDATED_AGE,
+    }
+  }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LifecycleRuleRequest {
+  /// paths starting with the prefix are covered, every object of the app when empty
+  #[serde(default)]
+  pub prefix: String,
+  pub age_from: LifecycleAgeFrom,
+  /// at least 1
+  pub days: u32,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LifecycleRule {
+  pub id: i64,
+  pub prefix: String,
+  pub age_from: String,
+  pub days: i64,
+  pub updated_at: DateTime<Utc>,
+  pub created_at: DateTime<Utc>,
+}
+
+impl From<LifecycleRuleRow> for LifecycleRule {
+  fn from(row: LifecycleRuleRow) -> Self {
+    Self {
+      id: row.id,
+      prefix: row.prefix,
+      age_from: row.age_from,
+      days: row.days,
+      updated_at: DateTime::<Utc>::from_timestamp(row.updated_at, 0).unwrap_or_default(),
+      created_at: DateTime::<Utc>::from_timestamp(row.created_at, 0).unwrap_or_default(),
+    }
+  }
+}
+
+pub type LifecycleRulePagination = Pagination<LifecycleRule>;
@@ -1,2 +1,8 @@
+pub mod acl;
+pub mod lifecycle;
+pub mod multipart;
 pub mod object;
+pub mod quota;
+pub mod s3;
+pub mod trash;
 pub mod util;
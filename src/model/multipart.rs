@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::repository::multipart::{MultipartPartRow, MultipartUploadRow};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateMultipartUploadRequest {
+  pub path: String,
+  pub r#type: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CompletedPart {
+  pub part_number: u32,
+  pub etag: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CompleteMultipartUploadRequest {
+  /// the parts to concatenate in order, every uploaded part is used when omitted
+  pub parts: Option<Vec<CompletedPart>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MultipartUpload {
+  pub id: String,
+  pub path: String,
+  pub r#type: Option<String>,
+  pub updated_at: DateTime<Utc>,
+  pub created_at: DateTime<Utc>,
+}
+
+impl From<MultipartUploadRow> for MultipartUpload {
+  fn from(row: MultipartUploadRow) -> Self {
+    Self {
+      id: row.id,
+      path: row.path,
+      r#type: row.r#type,
+      updated_at: DateTime::<Utc>::from_timestamp(row.updated_at, 0).unwrap_or_default(),
+      created_at: DateTime::<Utc>::from_timestamp(row.created_at, 0).unwrap_or_default(),
+    }
+  }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MultipartPart {
+  pub part_number: u32,
+  pub etag: String,
+  pub size: u64,
+  pub updated_at: DateTime<Utc>,
+}
+
+impl From<MultipartPartRow> for MultipartPart {
+  fn from(row: MultipartPartRow) -> Self {
+    Self {
+      part_number: row.part_number as u32,
+      etag: row.etag,
+      size: row.size as u64,
+      updated_at: DateTime::<Utc>::from_timestamp(row.updated_at, 0).unwrap_or_default(),
+    }
+  }
+}
@@ -2,7 +2,10 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 
-use crate::repository::object::ObjectRow;
+use crate::repository::{
+  object::{ObjectRow, COMPLIANCE_MODE, GOVERNANCE_MODE},
+  version::ObjectVersionRow,
+};
 
 use super::util::Pagination;
 
@@ -39,12 +42,141 @@ pub struct MoveObjectRequest {
   pub r#type: Option<String>,
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct MoveFolderRequest {
+  /// the folder the objects are moved into
+  pub path: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MoveFolderResponse {
+  pub from: String,
+  pub to: String,
+  /// the number of objects moved
+  pub moved: u64,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct DeleteFolderQuery {
+  pub path: String,
+  /// only report what would be deleted
+  #[serde(default)]
+  pub dry_run: bool,
+  /// the number of objects in the folder, required to delete folders over the confirmation
+  /// threshold
+  pub confirm: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteFolderResponse {
+  pub path: String,
+  pub dry_run: bool,
+  /// the number of objects in the folder that are not locked
+  pub objects: u64,
+  /// the total size of the objects in the folder that are not locked
+  pub bytes: u64,
+  /// the number of objects deleted, always zero for dry runs
+  pub deleted: u64,
+  /// the number of objects kept because they are locked, not counted in `objects` or `bytes`
+  pub locked: u64,
+  /// the objects that would be deleted, only listed for dry runs
+  pub items: Vec<ObjectInstance>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CopyObjectRequest {
+  /// the path of the copy, or the folder the objects are copied into when copying a folder
+  pub path: String,
+  /// the type of the copies, defaults to the type of each source
+  pub r#type: Option<String>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PresignMethod {
+  /// reads the object through its read endpoint
+  Get,
+  /// writes the object through its write endpoint
+  Put,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PresignObjectRequest {
+  pub method: PresignMethod,
+  /// the object to sign a url for, either `object_id` or `path` is required
+  pub object_id: Option<i64>,
+  /// the path to sign a url for, which may not exist yet for writes
+  pub path: Option<String>,
+  /// seconds until the url expires, 15 minutes by default
+  pub expires_in: Option<u64>,
+  /// the `Content-Type` writes must be sent with
+  pub content_type: Option<String>,
+  /// the largest body writes may send in bytes
+  pub max_size: Option<u64>,
+}
+
+/// the parameters of a presigned url, which replace the `Authorization` header on the read and
+/// write endpoints
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PresignedQuery {
+  pub app: Option<i64>,
+  pub expires: Option<i64>,
+  pub content_type: Option<String>,
+  pub max_size: Option<u64>,
+  pub signature: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PresignedObjectUrl {
+  pub method: PresignMethod,
+  pub url: String,
+  pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionMode {
+  /// admins may bypass the retention with the `X-Bypass-Governance-Retention` header
+  Governance,
+  /// nobody may bypass the retention and it may only be extended
+  Compliance,
+}
+
+impl RetentionMode {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Governance => GOVERNANCE_MODE,
+      Self::Compliance => COMPLIANCE_MODE,
+    }
+  }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ObjectRetentionRequest {
+  pub mode: RetentionMode,
+  /// the object may not be modified or deleted before then
+  pub retain_until: DateTime<Utc>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ObjectLegalHoldRequest {
+  /// while set the object may not be modified or deleted
+  pub legal_hold: bool,
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct ObjectInstance {
   pub id: i64,
   pub path: String,
   pub r#type: Option<String>,
   pub size: u64,
+  pub sha256: Option<String>,
+  pub md5: Option<String>,
+  pub crc32c: Option<String>,
+  pub retention_mode: Option<String>,
+  pub retain_until: Option<DateTime<Utc>>,
+  pub legal_hold: bool,
   pub updated_at: DateTime<Utc>,
   pub created_at: DateTime<Utc>,
 }
@@ -56,6 +188,14 @@ impl From<ObjectRow> for ObjectInstance {
       path: row.path,
       r#type: row.r#type,
       size: row.size as u64,
+      sha256: row.sha256,
+      md5: row.md5,
+      crc32c: row.crc32c,
+      retention_mode: row.retention_mode,
+      retain_until: row
+        .retain_until
+        .and_then(|retain_until| DateTime::<Utc>::from_timestamp(retain_until, 0)),
+      legal_hold: row.legal_hold != 0,
       updated_at: DateTime::<Utc>::from_timestamp(row.updated_at, 0).unwrap_or_default(),
       created_at: DateTime::<Utc>::from_timestamp(row.created_at, 0).unwrap_or_default(),
     }
@@ -63,3 +203,41 @@ impl From<ObjectRow> for ObjectInstance {
 }
 
 pub type ObjectInstancePagination = Pagination<ObjectInstance>;
+
+#[derive(Serialize, ToSchema)]
+pub struct ObjectVersion {
+  pub id: i64,
+  /// the object the version was taken from, which may have been deleted since
+  pub object_id: i64,
+  pub path: String,
+  /// numbered from 1 per path, the highest is the latest
+  pub version: i64,
+  pub r#type: Option<String>,
+  pub size: u64,
+  pub sha256: Option<String>,
+  pub md5: Option<String>,
+  pub crc32c: Option<String>,
+  pub updated_at: DateTime<Utc>,
+  /// when the version was taken
+  pub created_at: DateTime<Utc>,
+}
+
+impl From<ObjectVersionRow> for ObjectVersion {
+  fn from(row: ObjectVersionRow) -> Self {
+    Self {
+      id: row.id,
+      object_id: row.object_id,
+      path: row.path,
+      version: row.version,
+      r#type: row.r#type,
+      size: row.size as u64,
+      sha256: row.sha256,
+      md5: row.md5,
+      crc32c: row.crc32c,
+      updated_at: DateTime::<Utc>::from_timestamp(row.updated_at, 0).unwrap_or_default(),
+      created_at: DateTime::<Utc>::from_timestamp(row.created_at, 0).unwrap_or_default(),
+    }
+  }
+}
+
+pub type ObjectVersionPagination = Pagination<ObjectVersion>;
@@ -0,0 +1,35 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::service::quota::QuotaUsage as QuotaUsageRow;
+
+#[derive(Serialize, ToSchema)]
+pub struct QuotaUsage {
+  /// objects whose path starts with the prefix count towards the quota
+  pub prefix: String,
+  pub max_bytes: Option<u64>,
+  pub max_objects: Option<u64>,
+  pub bytes: u64,
+  pub objects: u64,
+}
+
+impl<'a> From<QuotaUsageRow<'a>> for QuotaUsage {
+  fn from(usage: QuotaUsageRow<'a>) -> Self {
+    Self {
+      prefix: usage.quota.prefix.trim_start_matches('/').to_owned(),
+      max_bytes: usage.quota.max_bytes,
+      max_objects: usage.quota.max_objects,
+      bytes: usage.bytes,
+      objects: usage.objects,
+    }
+  }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Usage {
+  /// the total size of every object of the app
+  pub bytes: u64,
+  /// the number of objects of the app
+  pub objects: u64,
+  pub quotas: Vec<QuotaUsage>,
+}
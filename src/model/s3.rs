@@ -0,0 +1,284 @@
+use std::fmt::{self, Display, Write};
+
+use axum::{
+  http::{header, StatusCode},
+  response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+
+pub const XML_CONTENT_TYPE: &str = "application/xml";
+pub const S3_XMLNS: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+
+#[derive(Debug)]
+pub struct S3Error {
+  status: StatusCode,
+  code: &'static str,
+  message: String,
+}
+
+impl S3Error {
+  pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+    Self {
+      status,
+      code,
+      message: message.into(),
+    }
+  }
+
+  pub fn code(&self) -> &'static str {
+    self.code
+  }
+
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+
+  pub fn access_denied() -> Self {
+    Self::new(StatusCode::FORBIDDEN, "AccessDenied", "Access Denied")
+  }
+
+  pub fn invalid_access_key_id() -> Self {
+    Self::new(
+      StatusCode::FORBIDDEN,
+      "InvalidAccessKeyId",
+      "The AWS access key Id you provided does not exist in our records.",
+    )
+  }
+
+  pub fn signature_does_not_match() -> Self {
+    Self::new(
+      StatusCode::FORBIDDEN,
+      "SignatureDoesNotMatch",
+      "The request signature we calculated does not match the signature you provided.",
+    )
+  }
+
+  pub fn request_time_too_skewed() -> Self {
+    Self::new(
+      StatusCode::FORBIDDEN,
+      "RequestTimeTooSkewed",
+      "The difference between the request time and the server's time is too large.",
+    )
+  }
+
+  pub fn authorization_header_malformed(message: impl Into<String>) -> Self {
+    Self::new(
+      StatusCode::BAD_REQUEST,
+      "AuthorizationHeaderMalformed",
+      message,
+    )
+  }
+
+  pub fn invalid_argument(message: impl Into<String>) -> Self {
+    Self::new(StatusCode::BAD_REQUEST, "InvalidArgument", message)
+  }
+
+  pub fn malformed_xml() -> Self {
+    Self::new(
+      StatusCode::BAD_REQUEST,
+      "MalformedXML",
+      "The XML you provided was not well-formed or did not validate against our published schema.",
+    )
+  }
+
+  pub fn no_such_key() -> Self {
+    Self::new(
+      StatusCode::NOT_FOUND,
+      "NoSuchKey",
+      "The specified key does not exist.",
+    )
+  }
+
+  pub fn no_such_upload() -> Self {
+    Self::new(
+      StatusCode::NOT_FOUND,
+      "NoSuchUpload",
+      "The specified multipart upload does not exist.",
+    )
+  }
+
+  pub fn invalid_part() -> Self {
+    Self::new(
+      StatusCode::BAD_REQUEST,
+      "InvalidPart",
+      "One or more of the specified parts could not be found.",
+    )
+  }
+
+  pub fn invalid_part_order() -> Self {
+    Self::new(
+      StatusCode::BAD_REQUEST,
+      "InvalidPartOrder",
+      "The list of parts was not in ascending order.",
+    )
+  }
+
+  pub fn invalid_range() -> Self {
+    Self::new(
+      StatusCode::RANGE_NOT_SATISFIABLE,
+      "InvalidRange",
+      "The requested range is not satisfiable",
+    )
+  }
+
+  pub fn bad_digest() -> Self {
+    Self::new(
+      StatusCode::BAD_REQUEST,
+      "XAmzContentSHA256Mismatch",
+      "The provided 'x-amz-content-sha256' header does not match what was computed.",
+    )
+  }
+
+  pub fn entity_too_large() -> Self {
+    Self::new(
+      StatusCode::BAD_REQUEST,
+      "EntityTooLarge",
+      "Your proposed upload exceeds the maximum allowed object size.",
+    )
+  }
+
+  pub fn quota_exceeded(message: impl Into<String>) -> Self {
+    Self::new(StatusCode::FORBIDDEN, "QuotaExceeded", message)
+  }
+
+  pub fn bucket_not_empty() -> Self {
+    Self::new(
+      StatusCode::CONFLICT,
+      "BucketNotEmpty",
+      "The bucket you tried to delete is not empty",
+    )
+  }
+
+  pub fn not_implemented() -> Self {
+    Self::new(
+      StatusCode::NOT_IMPLEMENTED,
+      "NotImplemented",
+      "A header you provided implies functionality that is not implemented",
+    )
+  }
+
+  pub fn internal_error() -> Self {
+    Self::new(
+      StatusCode::INTERNAL_SERVER_ERROR,
+      "InternalError",
+      "We encountered an internal error. Please try again.",
+    )
+  }
+}
+
+impl Display for S3Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {}", self.code, self.message)
+  }
+}
+
+impl IntoResponse for S3Error {
+  fn into_response(self) -> Response {
+    let mut xml = XmlBuilder::new();
+    xml.open("Error");
+    xml.element("Code", self.code);
+    xml.element("Message", &self.message);
+    xml.close("Error");
+    (self.status, xml).into_response()
+  }
+}
+
+pub struct XmlBuilder(String);
+
+impl XmlBuilder {
+  pub fn new() -> Self {
+    Self(String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"))
+  }
+
+  pub fn open(&mut self, name: &str) -> &mut Self {
+    let _ = write!(self.0, "<{}>", name);
+    self
+  }
+
+  pub fn open_with_xmlns(&mut self, name: &str) -> &mut Self {
+    let _ = write!(self.0, "<{} xmlns=\"{}\">", name, S3_XMLNS);
+    self
+  }
+
+  pub fn close(&mut self, name: &str) -> &mut Self {
+    let _ = write!(self.0, "</{}>", name);
+    self
+  }
+
+  pub fn text(&mut self, value: impl Display) -> &mut Self {
+    self.0.push_str(&xml_escape(&value.to_string()));
+    self
+  }
+
+  pub fn element(&mut self, name: &str, value: impl Display) -> &mut Self {
+    let _ = write!(
+      self.0,
+      "<{}>{}</{}>",
+      name,
+      xml_escape(&value.to_string()),
+      name
+    );
+    self
+  }
+
+  pub fn date_element(&mut self, name: &str, timestamp: i64) -> &mut Self {
+    let date = DateTime::<Utc>::from_timestamp(timestamp, 0).unwrap_or_default();
+    self.element(name, date.format("%Y-%m-%dT%H:%M:%S.000Z"))
+  }
+}
+
+impl Default for XmlBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl IntoResponse for XmlBuilder {
+  fn into_response(self) -> Response {
+    ([(header::CONTENT_TYPE, XML_CONTENT_TYPE)], self.0).into_response()
+  }
+}
+
+pub fn xml_escape(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+  for c in value.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+pub fn xml_unescape(value: &str) -> String {
+  value
+    .replace("&quot;", "\"")
+    .replace("&apos;", "'")
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&amp;", "&")
+}
+
+/// returns the unescaped text of every `<name>` element in `xml`, the documents S3 clients send
+/// are small and flat so this avoids pulling in a full XML parser
+pub fn xml_values(xml: &str, name: &str) -> Vec<String> {
+  let open = format!("<{}>", name);
+  let close = format!("</{}>", name);
+  let mut values = Vec::new();
+  let mut rest = xml;
+  while let Some(start) = rest.find(&open) {
+    rest = &rest[(start + open.len())..];
+    match rest.find(&close) {
+      Some(end) => {
+        values.push(xml_unescape(&rest[..end]));
+        rest = &rest[(end + close.len())..];
+      }
+      None => break,
+    }
+  }
+  values
+}
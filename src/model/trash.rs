@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::repository::trash::TrashedObjectRow;
+
+use super::util::Pagination;
+
+#[derive(Serialize, ToSchema)]
+pub struct TrashedObject {
+  pub id: i64,
+  pub path: String,
+  pub r#type: Option<String>,
+  pub size: u64,
+  pub sha256: Option<String>,
+  pub md5: Option<String>,
+  pub crc32c: Option<String>,
+  pub updated_at: DateTime<Utc>,
+  pub created_at: DateTime<Utc>,
+  /// when the object was deleted
+  pub trashed_at: DateTime<Utc>,
+  /// when the object will be purged
+  pub expires_at: DateTime<Utc>,
+}
+
+impl TrashedObject {
+  pub fn new(row: TrashedObjectRow, retention: u64) -> Self {
+    Self {
+      id: row.id,
+      path: row.path,
+      r#type: row.r#type,
+      size: row.size as u64,
+      sha256: row.sha256,
+      md5: row.md5,
+      crc32c: row.crc32c,
+      updated_at: DateTime::<Utc>::from_timestamp(row.updated_at, 0).unwrap_or_default(),
+      created_at: DateTime::<Utc>::from_timestamp(row.created_at, 0).unwrap_or_default(),
+      trashed_at: DateTime::<Utc>::from_timestamp(row.trashed_at, 0).unwrap_or_default(),
+      expires_at: DateTime::<Utc>::from_timestamp(row.trashed_at + retention as i64, 0)
+        .unwrap_or_default(),
+    }
+  }
+}
+
+pub type TrashedObjectPagination = Pagination<TrashedObject>;
@@ -0,0 +1,88 @@
+pub const READ_PERMISSION: &str = "read";
+pub const WRITE_PERMISSION: &str = "write";
+pub const DELETE_PERMISSION: &str = "delete";
+
+/// grants `permission` on every path starting with `prefix` to the tokens of `app` matching `sub`
+/// and `sub_type`, a missing `sub` or `sub_type` matches any
+#[derive(Clone, sqlx::FromRow)]
+pub struct ObjectAclRow {
+  pub id: i64,
+  pub app: i64,
+  pub sub: Option<i64>,
+  pub sub_type: Option<String>,
+  pub prefix: String,
+  pub permission: String,
+  pub created_at: i64,
+}
+
+impl ObjectAclRow {
+  pub fn allows(&self, permission: &str, path: &str) -> bool {
+    self.permission == permission && path.starts_with(&self.prefix)
+  }
+}
+
+pub async fn get_object_acls(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  limit: Option<usize>,
+  offset: Option<usize>,
+) -> sqlx::Result<Vec<ObjectAclRow>> {
+  let mut qb = sqlx::QueryBuilder::new("SELECT a.* FROM object_acls a WHERE a.app = ");
+  qb.push_bind(app).push(" ORDER BY a.id");
+  if let Some(limit) = limit {
+    qb.push(" LIMIT ").push_bind(limit as i64);
+  }
+  if let Some(offset) = offset {
+    qb.push(" OFFSET ").push_bind(offset as i64);
+  }
+  qb.build_query_as().fetch_all(pool).await
+}
+
+/// the grants that apply to the token of `sub` with `sub_type` in `app`
+pub async fn get_subject_object_acls(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  sub: i64,
+  sub_type: &str,
+) -> sqlx::Result<Vec<ObjectAclRow>> {
+  sqlx::query_as(
+    "SELECT a.* FROM object_acls a WHERE a.app = $1 AND (a.sub IS NULL OR a.sub = $2) AND (a.sub_type IS NULL OR a.sub_type = $3)",
+  )
+  .bind(app)
+  .bind(sub)
+  .bind(sub_type)
+  .fetch_all(pool)
+  .await
+}
+
+pub async fn create_object_acl(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  sub: Option<i64>,
+  sub_type: Option<String>,
+  prefix: String,
+  permission: &str,
+) -> sqlx::Result<ObjectAclRow> {
+  sqlx::query_as(
+    "INSERT INTO object_acls (app, sub, sub_type, prefix, permission) VALUES ($1, $2, $3, $4, $5) RETURNING *",
+  )
+  .bind(app)
+  .bind(sub)
+  .bind(sub_type)
+  .bind(prefix)
+  .bind(permission)
+  .fetch_one(pool)
+  .await
+}
+
+pub async fn delete_object_acl(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: i64,
+) -> sqlx::Result<Option<ObjectAclRow>> {
+  sqlx::query_as("DELETE FROM object_acls WHERE app = $1 AND id = $2 RETURNING *")
+    .bind(app)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
@@ -0,0 +1,103 @@
+pub const CREATED_AGE: &str = "created";
+pub const UPDATED_AGE: &str = "updated";
+
+/// deletes the objects of `app` under `prefix` once `days` have passed since they were created or
+/// last updated, depending on `age_from`
+#[derive(sqlx::FromRow)]
+pub struct LifecycleRuleRow {
+  pub id: i64,
+  pub app: i64,
+  pub prefix: String,
+  /// `CREATED_AGE` or `UPDATED_AGE`
+  pub age_from: String,
+  pub days: i64,
+  pub updated_at: i64,
+  pub created_at: i64,
+}
+
+pub async fn get_lifecycle_rules(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  limit: Option<usize>,
+  offset: Option<usize>,
+) -> sqlx::Result<Vec<LifecycleRuleRow>> {
+  let mut qb = sqlx::QueryBuilder::new("SELECT r.* FROM lifecycle_rules r WHERE r.app = ");
+  qb.push_bind(app).push(" ORDER BY r.id");
+  if let Some(limit) = limit {
+    qb.push(" LIMIT ").push_bind(limit as i64);
+  }
+  if let Some(offset) = offset {
+    qb.push(" OFFSET ").push_bind(offset as i64);
+  }
+  qb.build_query_as().fetch_all(pool).await
+}
+
+/// the rules of every app, for the scheduler
+pub async fn get_all_lifecycle_rules(pool: &sqlx::AnyPool) -> sqlx::Result<Vec<LifecycleRuleRow>> {
+  sqlx::query_as("SELECT r.* FROM lifecycle_rules r ORDER BY r.id")
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_lifecycle_rule(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: i64,
+) -> sqlx::Result<Option<LifecycleRuleRow>> {
+  sqlx::query_as("SELECT r.* FROM lifecycle_rules r WHERE r.app = $1 AND r.id = $2")
+    .bind(app)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn create_lifecycle_rule(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  prefix: String,
+  age_from: &str,
+  days: i64,
+) -> sqlx::Result<LifecycleRuleRow> {
+  sqlx::query_as(
+    "INSERT INTO lifecycle_rules (app, prefix, age_from, days) VALUES ($1, $2, $3, $4) RETURNING *",
+  )
+  .bind(app)
+  .bind(prefix)
+  .bind(age_from)
+  .bind(days)
+  .fetch_one(pool)
+  .await
+}
+
+pub async fn update_lifecycle_rule(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: i64,
+  prefix: String,
+  age_from: &str,
+  days: i64,
+) -> sqlx::Result<Option<LifecycleRuleRow>> {
+  sqlx::query_as(
+    "UPDATE lifecycle_rules SET prefix = $1, age_from = $2, days = $3, updated_at = $4 WHERE app = $5 AND id = $6 RETURNING *",
+  )
+  .bind(prefix)
+  .bind(age_from)
+  .bind(days)
+  .bind(chrono::Utc::now().timestamp())
+  .bind(app)
+  .bind(id)
+  .fetch_optional(pool)
+  .await
+}
+
+pub async fn delete_lifecycle_rule(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: i64,
+) -> sqlx::Result<Option<LifecycleRuleRow>> {
+  sqlx::query_as("DELETE FROM lifecycle_rules WHERE app = $1 AND id = $2 RETURNING *")
+    .bind(app)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
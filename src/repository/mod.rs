@@ -1 +1,8 @@
+pub mod acl;
+pub mod lifecycle;
+pub mod multipart;
 pub mod object;
+pub mod object_write;
+pub mod trash;
+pub mod tus;
+pub mod version;
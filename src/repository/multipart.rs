@@ -0,0 +1,123 @@
+#[derive(sqlx::FromRow)]
+pub struct MultipartUploadRow {
+  pub id: String,
+  pub app: i64,
+  pub path: String,
+  pub r#type: Option<String>,
+  pub updated_at: i64,
+  pub created_at: i64,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct MultipartPartRow {
+  pub upload_id: String,
+  pub part_number: i64,
+  pub etag: String,
+  pub size: i64,
+  pub updated_at: i64,
+}
+
+pub async fn get_multipart_upload(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: &str,
+) -> sqlx::Result<Option<MultipartUploadRow>> {
+  sqlx::query_as("SELECT u.* FROM multipart_uploads u WHERE u.app = $1 AND u.id = $2")
+    .bind(app)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// looks an upload up in every app, only for bookkeeping that is not made on behalf of a caller
+pub async fn get_any_multipart_upload(
+  pool: &sqlx::AnyPool,
+  id: &str,
+) -> sqlx::Result<Option<MultipartUploadRow>> {
+  sqlx::query_as("SELECT u.* FROM multipart_uploads u WHERE u.id = $1")
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// uploads in every app without a new part since `updated_before`
+pub async fn get_expired_multipart_uploads(
+  pool: &sqlx::AnyPool,
+  updated_before: i64,
+) -> sqlx::Result<Vec<MultipartUploadRow>> {
+  sqlx::query_as("SELECT u.* FROM multipart_uploads u WHERE u.updated_at < $1")
+    .bind(updated_before)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn create_multipart_upload(
+  pool: &sqlx::AnyPool,
+  id: &str,
+  app: i64,
+  path: String,
+  kind: Option<String>,
+) -> sqlx::Result<MultipartUploadRow> {
+  sqlx::query_as(
+    "INSERT INTO multipart_uploads (id, app, path, type) VALUES ($1, $2, $3, $4) RETURNING *",
+  )
+  .bind(id)
+  .bind(app)
+  .bind(path.trim_start_matches("/").trim_end_matches("/"))
+  .bind(kind)
+  .fetch_one(pool)
+  .await
+}
+
+pub async fn delete_multipart_upload(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: &str,
+) -> sqlx::Result<Option<MultipartUploadRow>> {
+  sqlx::query_as("DELETE FROM multipart_uploads WHERE app = $1 AND id = $2 RETURNING *")
+    .bind(app)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_multipart_parts(
+  pool: &sqlx::AnyPool,
+  upload_id: &str,
+) -> sqlx::Result<Vec<MultipartPartRow>> {
+  sqlx::query_as(
+    "SELECT p.* FROM multipart_parts p WHERE p.upload_id = $1 ORDER BY p.part_number ASC",
+  )
+  .bind(upload_id)
+  .fetch_all(pool)
+  .await
+}
+
+/// records a part, replacing an earlier upload of the same part number, and marks the upload as
+/// active
+pub async fn upsert_multipart_part(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  upload_id: &str,
+  part_number: i64,
+  etag: &str,
+  size: i64,
+) -> sqlx::Result<MultipartPartRow> {
+  let now = chrono::Utc::now().timestamp();
+  sqlx::query("UPDATE multipart_uploads SET updated_at = $1 WHERE id = $2")
+    .bind(now)
+    .bind(upload_id)
+    .execute(&mut **transaction)
+    .await?;
+  sqlx::query_as(
+    "INSERT INTO multipart_parts (upload_id, part_number, etag, size, updated_at) VALUES ($1, $2, $3, $4, $5)
+      ON CONFLICT (upload_id, part_number) DO UPDATE SET etag = excluded.etag, size = excluded.size, updated_at = excluded.updated_at
+      RETURNING *",
+  )
+  .bind(upload_id)
+  .bind(part_number)
+  .bind(etag)
+  .bind(size)
+  .bind(now)
+  .fetch_one(&mut **transaction)
+  .await
+}
@@ -1,32 +1,97 @@
-use std::collections::HashMap;
+use std::{
+  collections::HashMap,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-#[derive(Default, sqlx::FromRow)]
+use crate::core::checksum::Checksums;
+
+/// retained objects may only be modified or deleted early by admins bypassing governance
+pub const GOVERNANCE_MODE: &str = "governance";
+/// retained objects may not be modified or deleted by anyone and their retention only extended
+pub const COMPLIANCE_MODE: &str = "compliance";
+
+#[derive(Default, Clone, sqlx::FromRow)]
 pub struct ObjectRow {
   pub id: i64,
+  /// the application the object belongs to, objects are only visible to tokens of the same app
+  pub app: i64,
   pub path: String,
   pub r#type: Option<String>,
   pub size: i64,
   pub updated_at: i64,
   pub created_at: i64,
+  pub sha256: Option<String>,
+  pub md5: Option<String>,
+  pub crc32c: Option<String>,
+  /// `GOVERNANCE_MODE` or `COMPLIANCE_MODE` while the object has a retention
+  pub retention_mode: Option<String>,
+  pub retain_until: Option<i64>,
+  /// non zero while a legal hold keeps the object from being modified or deleted
+  pub legal_hold: i64,
 }
 
 impl ObjectRow {
   pub fn is_dir(&self) -> bool {
     self.id == 0
   }
+
+  /// a strong validator that changes whenever the bytes do, taken from the sha256 of the committed
+  /// contents along with the size so appends in progress are told apart, objects that were never
+  /// written since checksums were added fall back to their id and modification time
+  pub fn etag(&self) -> String {
+    match &self.sha256 {
+      Some(sha256) => format!("\"{}-{:x}\"", sha256, self.size),
+      None => format!("\"{:x}-{:x}-{:x}\"", self.id, self.size, self.updated_at),
+    }
+  }
+
+  pub fn modified(&self) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(self.updated_at.max(0) as u64)
+  }
+
+  /// whether the object's retention has not yet expired at `now`
+  pub fn is_retained(&self, now: i64) -> bool {
+    self
+      .retain_until
+      .is_some_and(|retain_until| retain_until > now)
+  }
+
+  /// `updated_at` formatted as an HTTP date for `Last-Modified`
+  pub fn last_modified(&self) -> String {
+    httpdate::fmt_http_date(self.modified())
+  }
 }
 
 async fn get_objects(
   pool: &sqlx::AnyPool,
+  app: i64,
   path: &str,
+  prefixes: Option<&[String]>,
   limit: Option<usize>,
   offset: Option<usize>,
 ) -> sqlx::Result<Vec<ObjectRow>> {
-  let mut qb = sqlx::QueryBuilder::new("SELECT f.* FROM objects f");
+  let mut qb = sqlx::QueryBuilder::new("SELECT f.* FROM objects f WHERE f.app = ");
+  qb.push_bind(app);
   if !path.is_empty() {
-    qb.push(" WHERE f.path LIKE ")
+    qb.push(" AND f.path LIKE ")
       .push_bind(format!("{}/%", path));
   }
+  if let Some(prefixes) = prefixes {
+    if prefixes.is_empty() {
+      return Ok(Vec::new());
+    }
+    qb.push(" AND (");
+    for (index, prefix) in prefixes.iter().enumerate() {
+      if index > 0 {
+        qb.push(" OR ");
+      }
+      qb.push("substr(f.path, 1, ")
+        .push_bind(prefix.chars().count() as i64)
+        .push(") = ")
+        .push_bind(prefix.clone());
+    }
+    qb.push(")");
+  }
   if let Some(limit) = limit {
     qb.push(" LIMIT ").push_bind(limit as i64);
   }
@@ -36,9 +101,13 @@ async fn get_objects(
   qb.build_query_as().fetch_all(pool).await
 }
 
+/// lists the objects and folders directly under `path`, only counting objects under one of
+/// `prefixes` when given
 pub async fn get_objects_and_folders(
   pool: &sqlx::AnyPool,
+  app: i64,
   path: Option<&str>,
+  prefixes: Option<&[String]>,
   limit: Option<usize>,
   offset: Option<usize>,
 ) -> sqlx::Result<Vec<ObjectRow>> {
@@ -47,7 +116,7 @@ pub async fn get_objects_and_folders(
     .trim_start_matches("/")
     .trim_end_matches("/");
 
-  let object_rows = get_objects(pool, path, limit, offset).await?;
+  let object_rows = get_objects(pool, app, path, prefixes, limit, offset).await?;
   let path_parts = if path.is_empty() {
     Vec::new()
   } else {
@@ -71,11 +140,11 @@ pub async fn get_objects_and_folders(
       .entry(object_folder.clone())
       .or_insert_with(|| ObjectRow {
         id: 0,
+        app,
         path: object_folder,
         r#type: Some("directory".to_owned()),
         size: 0,
-        updated_at: 0,
-        created_at: 0,
+        ..Default::default()
       });
     folder.size += object_row.size;
     if folder.updated_at > object_row.updated_at || folder.updated_at == 0 {
@@ -89,17 +158,104 @@ pub async fn get_objects_and_folders(
   Ok(folders.into_values().chain(objects).collect())
 }
 
+pub async fn get_objects_by_prefix(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  prefix: &str,
+  start_after: Option<&str>,
+  limit: usize,
+) -> sqlx::Result<Vec<ObjectRow>> {
+  let mut qb = sqlx::QueryBuilder::new("SELECT f.* FROM objects f WHERE f.app = ");
+  qb.push_bind(app)
+    .push(" AND substr(f.path, 1, ")
+    .push_bind(prefix.chars().count() as i64)
+    .push(") = ")
+    .push_bind(prefix.to_owned());
+  if let Some(start_after) = start_after {
+    qb.push(" AND f.path > ").push_bind(start_after.to_owned());
+  }
+  qb.push(" ORDER BY f.path LIMIT ").push_bind(limit as i64);
+  qb.build_query_as().fetch_all(pool).await
+}
+
+/// objects under `prefix` in id order after `after_id`
+pub async fn get_objects_by_prefix_after_id(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  prefix: &str,
+  after_id: i64,
+  limit: usize,
+) -> sqlx::Result<Vec<ObjectRow>> {
+  let mut qb = sqlx::QueryBuilder::new("SELECT f.* FROM objects f WHERE f.app = ");
+  qb.push_bind(app)
+    .push(" AND substr(f.path, 1, ")
+    .push_bind(prefix.chars().count() as i64)
+    .push(") = ")
+    .push_bind(prefix.to_owned())
+    .push(" AND f.id > ")
+    .push_bind(after_id);
+  qb.push(" ORDER BY f.id LIMIT ").push_bind(limit as i64);
+  qb.build_query_as().fetch_all(pool).await
+}
+
+/// objects under `prefix` created, or last updated when `updated`, before `before`, in id order
+/// after `after_id`
+pub async fn get_expired_objects(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  prefix: &str,
+  updated: bool,
+  before: i64,
+  after_id: i64,
+  limit: usize,
+) -> sqlx::Result<Vec<ObjectRow>> {
+  let mut qb = sqlx::QueryBuilder::new("SELECT f.* FROM objects f WHERE f.app = ");
+  qb.push_bind(app)
+    .push(" AND substr(f.path, 1, ")
+    .push_bind(prefix.chars().count() as i64)
+    .push(") = ")
+    .push_bind(prefix.to_owned())
+    .push(if updated {
+      " AND f.updated_at < "
+    } else {
+      " AND f.created_at < "
+    })
+    .push_bind(before)
+    .push(" AND f.id > ")
+    .push_bind(after_id);
+  qb.push(" ORDER BY f.id LIMIT ").push_bind(limit as i64);
+  qb.build_query_as().fetch_all(pool).await
+}
+
 pub async fn get_object_by_path(
   pool: &sqlx::AnyPool,
+  app: i64,
   path: &str,
 ) -> sqlx::Result<Option<ObjectRow>> {
-  sqlx::query_as("SELECT f.* FROM objects f WHERE f.path = $1")
+  sqlx::query_as("SELECT f.* FROM objects f WHERE f.app = $1 AND f.path = $2")
+    .bind(app)
     .bind(path.trim_start_matches("/").trim_end_matches("/"))
     .fetch_optional(pool)
     .await
 }
 
-pub async fn get_object_by_id(pool: &sqlx::AnyPool, id: i64) -> sqlx::Result<Option<ObjectRow>> {
+pub async fn get_object_by_id(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: i64,
+) -> sqlx::Result<Option<ObjectRow>> {
+  sqlx::query_as("SELECT f.* FROM objects f WHERE f.app = $1 AND f.id = $2")
+    .bind(app)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// looks an object up in every app, only for bookkeeping that is not made on behalf of a caller
+pub async fn get_any_object_by_id(
+  pool: &sqlx::AnyPool,
+  id: i64,
+) -> sqlx::Result<Option<ObjectRow>> {
   sqlx::query_as("SELECT f.* FROM objects f WHERE f.id = $1")
     .bind(id)
     .fetch_optional(pool)
@@ -108,11 +264,13 @@ pub async fn get_object_by_id(pool: &sqlx::AnyPool, id: i64) -> sqlx::Result<Opt
 
 pub async fn create_object(
   transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  app: i64,
   path: String,
   kind: Option<String>,
   size: i64,
 ) -> sqlx::Result<ObjectRow> {
-  sqlx::query_as("INSERT INTO objects (path, type, size) VALUES ($1, $2, $3) RETURNING *")
+  sqlx::query_as("INSERT INTO objects (app, path, type, size) VALUES ($1, $2, $3, $4) RETURNING *")
+    .bind(app)
     .bind(path.trim_start_matches("/").trim_end_matches("/"))
     .bind(kind)
     .bind(size)
@@ -120,34 +278,174 @@ pub async fn create_object(
     .await
 }
 
+/// inserts a copy of `source` at `path` with its size and checksums, keeping its type unless
+/// `kind` is set
+pub async fn create_object_copy(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  source: &ObjectRow,
+  path: String,
+  kind: Option<String>,
+) -> sqlx::Result<ObjectRow> {
+  sqlx::query_as(
+    "INSERT INTO objects (app, path, type, size, sha256, md5, crc32c) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *",
+  )
+  .bind(source.app)
+  .bind(path.trim_start_matches("/").trim_end_matches("/"))
+  .bind(kind.or_else(|| source.r#type.clone()))
+  .bind(source.size)
+  .bind(source.sha256.clone())
+  .bind(source.md5.clone())
+  .bind(source.crc32c.clone())
+  .fetch_one(&mut **transaction)
+  .await
+}
+
 pub async fn update_object_size(
   pool: &sqlx::AnyPool,
   id: i64,
   size: i64,
 ) -> sqlx::Result<Option<ObjectRow>> {
-  sqlx::query_as("UPDATE objects SET size = $1 WHERE id = $2 RETURNING *")
+  sqlx::query_as("UPDATE objects SET size = $1, updated_at = $2 WHERE id = $3 RETURNING *")
     .bind(size)
+    .bind(chrono::Utc::now().timestamp())
     .bind(id)
     .fetch_optional(pool)
     .await
 }
 
+/// adds `written` to the size in a single statement so concurrent updates are never lost
+pub async fn increment_object_size(
+  pool: &sqlx::AnyPool,
+  id: i64,
+  written: i64,
+) -> sqlx::Result<Option<ObjectRow>> {
+  sqlx::query_as("UPDATE objects SET size = size + $1, updated_at = $2 WHERE id = $3 RETURNING *")
+    .bind(written)
+    .bind(chrono::Utc::now().timestamp())
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// sets the type, size and checksums after the object's contents were written
+pub async fn update_object_contents(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  id: i64,
+  kind: Option<String>,
+  size: i64,
+  checksums: &Checksums,
+) -> sqlx::Result<Option<ObjectRow>> {
+  sqlx::query_as(
+    "UPDATE objects SET type = COALESCE($1, type), size = $2, sha256 = $3, md5 = $4, crc32c = $5, updated_at = $6 WHERE id = $7 RETURNING *",
+  )
+  .bind(kind)
+  .bind(size)
+  .bind(&checksums.sha256)
+  .bind(&checksums.md5)
+  .bind(&checksums.crc32c)
+  .bind(chrono::Utc::now().timestamp())
+  .bind(id)
+  .fetch_optional(&mut **transaction)
+  .await
+}
+
 pub async fn update_object_path(
   pool: &sqlx::AnyPool,
+  app: i64,
   id: i64,
   path: String,
   kind: Option<String>,
 ) -> sqlx::Result<Option<ObjectRow>> {
   sqlx::query_as(
-    "UPDATE objects SET path = $1, type = COALESCE($2, type) WHERE id = $3 RETURNING *",
+    "UPDATE objects SET path = $1, type = COALESCE($2, type) WHERE app = $3 AND id = $4 RETURNING *",
   )
   .bind(path)
   .bind(kind)
+  .bind(app)
+  .bind(id)
+  .fetch_optional(pool)
+  .await
+}
+
+/// the paths objects under `prefix` would take under `new_prefix` that are already taken, the
+/// prefixes must not be nested since objects that are moved themselves would be reported
+pub async fn get_prefix_conflicts(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  app: i64,
+  prefix: &str,
+  new_prefix: &str,
+  limit: usize,
+) -> sqlx::Result<Vec<String>> {
+  sqlx::query_scalar(
+    "SELECT f.path FROM objects s
+    JOIN objects f ON f.app = s.app AND f.path = $1 || substr(s.path, $2)
+    WHERE s.app = $3 AND substr(s.path, 1, $4) = $5
+    ORDER BY f.path
+    LIMIT $6",
+  )
+  .bind(new_prefix)
+  .bind(prefix.chars().count() as i64 + 1)
+  .bind(app)
+  .bind(prefix.chars().count() as i64)
+  .bind(prefix)
+  .bind(limit as i64)
+  .fetch_all(&mut **transaction)
+  .await
+}
+
+/// replaces `prefix` with `new_prefix` in the paths of every object under it, returning how many
+/// were moved
+pub async fn update_objects_prefix(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  app: i64,
+  prefix: &str,
+  new_prefix: &str,
+) -> sqlx::Result<u64> {
+  let result = sqlx::query(
+    "UPDATE objects SET path = $1 || substr(path, $2) WHERE app = $3 AND substr(path, 1, $4) = $5",
+  )
+  .bind(new_prefix)
+  .bind(prefix.chars().count() as i64 + 1)
+  .bind(app)
+  .bind(prefix.chars().count() as i64)
+  .bind(prefix)
+  .execute(&mut **transaction)
+  .await?;
+  Ok(result.rows_affected())
+}
+
+pub async fn update_object_retention(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: i64,
+  retention_mode: Option<&str>,
+  retain_until: Option<i64>,
+) -> sqlx::Result<Option<ObjectRow>> {
+  sqlx::query_as(
+    "UPDATE objects SET retention_mode = $1, retain_until = $2 WHERE app = $3 AND id = $4 RETURNING *",
+  )
+  .bind(retention_mode.map(str::to_owned))
+  .bind(retain_until)
+  .bind(app)
   .bind(id)
   .fetch_optional(pool)
   .await
 }
 
+pub async fn update_object_legal_hold(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: i64,
+  legal_hold: bool,
+) -> sqlx::Result<Option<ObjectRow>> {
+  sqlx::query_as("UPDATE objects SET legal_hold = $1 WHERE app = $2 AND id = $3 RETURNING *")
+    .bind(legal_hold as i64)
+    .bind(app)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
 pub async fn delete_object(
   transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
   id: i64,
@@ -160,10 +458,36 @@ pub async fn delete_object(
 
 pub async fn delete_object_by_path(
   transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  app: i64,
   path: &str,
 ) -> sqlx::Result<Option<ObjectRow>> {
-  sqlx::query_as("DELETE FROM objects WHERE path = $1 RETURNING *")
+  sqlx::query_as("DELETE FROM objects WHERE app = $1 AND path = $2 RETURNING *")
+    .bind(app)
     .bind(path)
     .fetch_optional(&mut **transaction)
     .await
 }
+
+#[derive(sqlx::FromRow)]
+pub struct ObjectUsageRow {
+  pub bytes: i64,
+  pub objects: i64,
+}
+
+/// the total size and number of the objects of `app` whose path starts with `prefix`
+pub async fn get_object_usage(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  prefix: &str,
+) -> sqlx::Result<ObjectUsageRow> {
+  sqlx::query_as(
+    "SELECT CAST(COALESCE(SUM(size), 0) AS BIGINT) AS bytes, COUNT(*) AS objects
+    FROM objects
+    WHERE app = $1 AND substr(path, 1, $2) = $3",
+  )
+  .bind(app)
+  .bind(prefix.chars().count() as i64)
+  .bind(prefix)
+  .fetch_one(pool)
+  .await
+}
@@ -0,0 +1,54 @@
+/// the object row was inserted and its storage has not been created yet
+pub const CREATE_OPERATION: &str = "create";
+/// new contents are being written to a staging area
+pub const REPLACE_OPERATION: &str = "replace";
+/// bytes are being appended past `size`
+pub const APPEND_OPERATION: &str = "append";
+/// the object row was inserted as a copy and its bytes have not all been copied yet
+pub const COPY_OPERATION: &str = "copy";
+/// the object row was deleted and its storage has not been removed yet
+pub const DELETE_OPERATION: &str = "delete";
+
+/// a journal entry for a write that has started and not yet been committed, entries left behind
+/// by a crash are reconciled at startup
+#[derive(sqlx::FromRow)]
+pub struct ObjectWriteRow {
+  pub id: i64,
+  pub object_id: i64,
+  pub operation: String,
+  /// the committed size of the object when the write started
+  pub size: i64,
+  pub created_at: i64,
+}
+
+pub async fn get_object_writes(pool: &sqlx::AnyPool) -> sqlx::Result<Vec<ObjectWriteRow>> {
+  sqlx::query_as("SELECT w.* FROM object_writes w ORDER BY w.id ASC")
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn create_object_write(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  object_id: i64,
+  operation: &str,
+  size: i64,
+) -> sqlx::Result<ObjectWriteRow> {
+  sqlx::query_as(
+    "INSERT INTO object_writes (object_id, operation, size) VALUES ($1, $2, $3) RETURNING *",
+  )
+  .bind(object_id)
+  .bind(operation)
+  .bind(size)
+  .fetch_one(&mut **transaction)
+  .await
+}
+
+pub async fn delete_object_write(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  id: i64,
+) -> sqlx::Result<Option<ObjectWriteRow>> {
+  sqlx::query_as("DELETE FROM object_writes WHERE id = $1 RETURNING *")
+    .bind(id)
+    .fetch_optional(&mut **transaction)
+    .await
+}
@@ -0,0 +1,180 @@
+use super::object::ObjectRow;
+
+/// a deleted object kept until it is restored or purged, its bytes stay stored under its id
+#[derive(sqlx::FromRow)]
+pub struct TrashedObjectRow {
+  pub id: i64,
+  pub app: i64,
+  pub path: String,
+  pub r#type: Option<String>,
+  pub size: i64,
+  pub sha256: Option<String>,
+  pub md5: Option<String>,
+  pub crc32c: Option<String>,
+  pub retention_mode: Option<String>,
+  pub retain_until: Option<i64>,
+  pub legal_hold: i64,
+  pub updated_at: i64,
+  pub created_at: i64,
+  pub trashed_at: i64,
+}
+
+impl TrashedObjectRow {
+  pub fn object_row(&self) -> ObjectRow {
+    ObjectRow {
+      id: self.id,
+      app: self.app,
+      path: self.path.clone(),
+      r#type: self.r#type.clone(),
+      size: self.size,
+      updated_at: self.updated_at,
+      created_at: self.created_at,
+      sha256: self.sha256.clone(),
+      md5: self.md5.clone(),
+      crc32c: self.crc32c.clone(),
+      retention_mode: self.retention_mode.clone(),
+      retain_until: self.retain_until,
+      legal_hold: self.legal_hold,
+    }
+  }
+}
+
+pub async fn get_trashed_objects(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  path: &str,
+  prefixes: Option<&[String]>,
+  limit: Option<usize>,
+  offset: Option<usize>,
+) -> sqlx::Result<Vec<TrashedObjectRow>> {
+  let mut qb = sqlx::QueryBuilder::new("SELECT t.* FROM trashed_objects t WHERE t.app = ");
+  qb.push_bind(app);
+  if !path.is_empty() {
+    qb.push(" AND t.path LIKE ")
+      .push_bind(format!("{}/%", path));
+  }
+  if let Some(prefixes) = prefixes {
+    if prefixes.is_empty() {
+      return Ok(Vec::new());
+    }
+    qb.push(" AND (");
+    for (index, prefix) in prefixes.iter().enumerate() {
+      if index > 0 {
+        qb.push(" OR ");
+      }
+      qb.push("substr(t.path, 1, ")
+        .push_bind(prefix.chars().count() as i64)
+        .push(") = ")
+        .push_bind(prefix.clone());
+    }
+    qb.push(")");
+  }
+  qb.push(" ORDER BY t.trashed_at DESC, t.id DESC");
+  if let Some(limit) = limit {
+    qb.push(" LIMIT ").push_bind(limit as i64);
+  }
+  if let Some(offset) = offset {
+    qb.push(" OFFSET ").push_bind(offset as i64);
+  }
+  qb.build_query_as().fetch_all(pool).await
+}
+
+pub async fn get_trashed_object(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: i64,
+) -> sqlx::Result<Option<TrashedObjectRow>> {
+  sqlx::query_as("SELECT t.* FROM trashed_objects t WHERE t.app = $1 AND t.id = $2")
+    .bind(app)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// trashed objects trashed before `trashed_before`, oldest first
+pub async fn get_expired_trashed_objects(
+  pool: &sqlx::AnyPool,
+  trashed_before: i64,
+) -> sqlx::Result<Vec<TrashedObjectRow>> {
+  sqlx::query_as(
+    "SELECT t.* FROM trashed_objects t WHERE t.trashed_at < $1 ORDER BY t.trashed_at ASC",
+  )
+  .bind(trashed_before)
+  .fetch_all(pool)
+  .await
+}
+
+/// moves the object's row into the trash
+pub async fn trash_object(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  id: i64,
+) -> sqlx::Result<Option<TrashedObjectRow>> {
+  let object_row = match super::object::delete_object(transaction, id).await? {
+    Some(object_row) => object_row,
+    None => return Ok(None),
+  };
+  sqlx::query_as(
+    "INSERT INTO trashed_objects (id, app, path, type, size, sha256, md5, crc32c, retention_mode, retain_until, legal_hold, updated_at, created_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+    RETURNING *",
+  )
+  .bind(object_row.id)
+  .bind(object_row.app)
+  .bind(object_row.path)
+  .bind(object_row.r#type)
+  .bind(object_row.size)
+  .bind(object_row.sha256)
+  .bind(object_row.md5)
+  .bind(object_row.crc32c)
+  .bind(object_row.retention_mode)
+  .bind(object_row.retain_until)
+  .bind(object_row.legal_hold)
+  .bind(object_row.updated_at)
+  .bind(object_row.created_at)
+  .fetch_optional(&mut **transaction)
+  .await
+}
+
+/// moves the trashed object's row back into the objects under its old id and path
+pub async fn restore_trashed_object(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  app: i64,
+  id: i64,
+) -> sqlx::Result<Option<ObjectRow>> {
+  let trashed_row = match delete_trashed_object(transaction, app, id).await? {
+    Some(trashed_row) => trashed_row,
+    None => return Ok(None),
+  };
+  sqlx::query_as(
+    "INSERT INTO objects (id, app, path, type, size, sha256, md5, crc32c, retention_mode, retain_until, legal_hold, updated_at, created_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+    RETURNING *",
+  )
+  .bind(trashed_row.id)
+  .bind(trashed_row.app)
+  .bind(trashed_row.path)
+  .bind(trashed_row.r#type)
+  .bind(trashed_row.size)
+  .bind(trashed_row.sha256)
+  .bind(trashed_row.md5)
+  .bind(trashed_row.crc32c)
+  .bind(trashed_row.retention_mode)
+  .bind(trashed_row.retain_until)
+  .bind(trashed_row.legal_hold)
+  .bind(trashed_row.updated_at)
+  .bind(trashed_row.created_at)
+  .fetch_optional(&mut **transaction)
+  .await
+}
+
+pub async fn delete_trashed_object(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  app: i64,
+  id: i64,
+) -> sqlx::Result<Option<TrashedObjectRow>> {
+  sqlx::query_as("DELETE FROM trashed_objects WHERE app = $1 AND id = $2 RETURNING *")
+    .bind(app)
+    .bind(id)
+    .fetch_optional(&mut **transaction)
+    .await
+}
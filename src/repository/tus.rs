@@ -0,0 +1,43 @@
+#[derive(sqlx::FromRow)]
+pub struct TusUploadRow {
+  pub object_id: i64,
+  pub length: i64,
+  pub metadata: Option<String>,
+  pub created_at: i64,
+}
+
+pub async fn get_tus_upload(
+  pool: &sqlx::AnyPool,
+  object_id: i64,
+) -> sqlx::Result<Option<TusUploadRow>> {
+  sqlx::query_as("SELECT u.* FROM tus_uploads u WHERE u.object_id = $1")
+    .bind(object_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn create_tus_upload(
+  pool: &sqlx::AnyPool,
+  object_id: i64,
+  length: i64,
+  metadata: Option<String>,
+) -> sqlx::Result<TusUploadRow> {
+  sqlx::query_as(
+    "INSERT INTO tus_uploads (object_id, length, metadata) VALUES ($1, $2, $3) RETURNING *",
+  )
+  .bind(object_id)
+  .bind(length)
+  .bind(metadata)
+  .fetch_one(pool)
+  .await
+}
+
+pub async fn delete_tus_upload(
+  pool: &sqlx::AnyPool,
+  object_id: i64,
+) -> sqlx::Result<Option<TusUploadRow>> {
+  sqlx::query_as("DELETE FROM tus_uploads WHERE object_id = $1 RETURNING *")
+    .bind(object_id)
+    .fetch_optional(pool)
+    .await
+}
@@ -0,0 +1,126 @@
+use super::object::ObjectRow;
+
+/// a previous state of the object at `path`, numbered from 1 per path, its bytes are stored under
+/// `storage_id` next to the objects
+#[derive(sqlx::FromRow)]
+pub struct ObjectVersionRow {
+  pub id: i64,
+  pub app: i64,
+  /// the object the version was taken from, which may have been deleted since
+  pub object_id: i64,
+  pub path: String,
+  pub version: i64,
+  pub r#type: Option<String>,
+  pub size: i64,
+  pub sha256: Option<String>,
+  pub md5: Option<String>,
+  pub crc32c: Option<String>,
+  /// when the object was last written before the version was taken
+  pub updated_at: i64,
+  pub created_at: i64,
+}
+
+impl ObjectVersionRow {
+  /// versions are stored under negative ids so they never collide with objects
+  pub fn storage_id(&self) -> i64 {
+    -self.id
+  }
+
+  /// the version as an object stored under `storage_id`, to copy it back over an object
+  pub fn source_object_row(&self) -> ObjectRow {
+    ObjectRow {
+      id: self.storage_id(),
+      app: self.app,
+      path: self.path.clone(),
+      r#type: self.r#type.clone(),
+      size: self.size,
+      updated_at: self.updated_at,
+      created_at: self.created_at,
+      sha256: self.sha256.clone(),
+      md5: self.md5.clone(),
+      crc32c: self.crc32c.clone(),
+      ..Default::default()
+    }
+  }
+}
+
+pub async fn get_object_versions(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  path: &str,
+  limit: Option<usize>,
+  offset: Option<usize>,
+) -> sqlx::Result<Vec<ObjectVersionRow>> {
+  let mut qb = sqlx::QueryBuilder::new("SELECT v.* FROM object_versions v WHERE v.app = ");
+  qb.push_bind(app)
+    .push(" AND v.path = ")
+    .push_bind(path.to_owned())
+    .push(" ORDER BY v.version DESC");
+  if let Some(limit) = limit {
+    qb.push(" LIMIT ").push_bind(limit as i64);
+  }
+  if let Some(offset) = offset {
+    qb.push(" OFFSET ").push_bind(offset as i64);
+  }
+  qb.build_query_as().fetch_all(pool).await
+}
+
+pub async fn get_object_version(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: i64,
+) -> sqlx::Result<Option<ObjectVersionRow>> {
+  sqlx::query_as("SELECT v.* FROM object_versions v WHERE v.app = $1 AND v.id = $2")
+    .bind(app)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// records the current state of `object_row` as the next version of its path
+pub async fn create_object_version(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Any>,
+  object_row: &ObjectRow,
+) -> sqlx::Result<ObjectVersionRow> {
+  sqlx::query_as(
+    "INSERT INTO object_versions (app, object_id, path, version, type, size, sha256, md5, crc32c, updated_at)
+    SELECT $1, $2, $3, COALESCE(MAX(v.version), 0) + 1, $4, $5, $6, $7, $8, $9
+    FROM object_versions v WHERE v.app = $1 AND v.path = $3
+    RETURNING *",
+  )
+  .bind(object_row.app)
+  .bind(object_row.id)
+  .bind(object_row.path.clone())
+  .bind(object_row.r#type.clone())
+  .bind(object_row.size)
+  .bind(object_row.sha256.clone())
+  .bind(object_row.md5.clone())
+  .bind(object_row.crc32c.clone())
+  .bind(object_row.updated_at)
+  .fetch_one(&mut **transaction)
+  .await
+}
+
+pub async fn delete_object_version(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  id: i64,
+) -> sqlx::Result<Option<ObjectVersionRow>> {
+  sqlx::query_as("DELETE FROM object_versions WHERE app = $1 AND id = $2 RETURNING *")
+    .bind(app)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn delete_object_versions(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  path: &str,
+) -> sqlx::Result<Vec<ObjectVersionRow>> {
+  sqlx::query_as("DELETE FROM object_versions WHERE app = $1 AND path = $2 RETURNING *")
+    .bind(app)
+    .bind(path)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,171 @@
+use crate::{
+  core::error::{Errors, InternalError, INTERNAL_ERROR, NOT_FOUND_ERROR, REQUIRED_ERROR},
+  middleware::{
+    authorization::{Authorization, OBJECTS_ADMIN_SCOPE},
+    json::Json,
+  },
+  model::{
+    acl::{CreateObjectAclRequest, ObjectAcl, ObjectAclPagination},
+    util::{OffsetAndLimit, Pagination},
+  },
+  repository,
+};
+
+use axum::{
+  extract::{Path, Query, State},
+  http::StatusCode,
+  response::IntoResponse,
+};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use super::RouterState;
+
+pub const ACL_TAG: &str = "acl";
+
+#[utoipa::path(
+  get,
+  path = "/acls",
+  tags = [ACL_TAG],
+  params(
+    OffsetAndLimit,
+  ),
+  responses(
+    (status = 200, content_type = "application/json", body = ObjectAclPagination),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:admin"])
+  )
+)]
+pub async fn get_acls(
+  State(state): State<RouterState>,
+  Authorization { claims, .. }: Authorization,
+  Query(offset_and_limit_query): Query<OffsetAndLimit>,
+) -> impl IntoResponse {
+  if let Err(err) = claims.require_scope(OBJECTS_ADMIN_SCOPE) {
+    return err.into_response();
+  }
+  let acls = match repository::acl::get_object_acls(
+    &state.pool,
+    claims.app,
+    offset_and_limit_query.limit,
+    offset_and_limit_query.offset,
+  )
+  .await
+  {
+    Ok(acls) => acls,
+    Err(err) => {
+      log::error!("Error getting acls from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+
+  axum::Json(Pagination {
+    has_more: acls.len() == offset_and_limit_query.limit.unwrap_or(usize::MAX),
+    items: acls.into_iter().map(ObjectAcl::from).collect(),
+  })
+  .into_response()
+}
+
+#[utoipa::path(
+  post,
+  path = "/acls",
+  tags = [ACL_TAG],
+  request_body = CreateObjectAclRequest,
+  responses(
+    (status = 201, content_type = "application/json", body = ObjectAcl),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:admin"])
+  )
+)]
+pub async fn create_acl(
+  State(state): State<RouterState>,
+  Authorization { claims, .. }: Authorization,
+  Json(body): Json<CreateObjectAclRequest>,
+) -> impl IntoResponse {
+  if let Err(err) = claims.require_scope(OBJECTS_ADMIN_SCOPE) {
+    return err.into_response();
+  }
+  if body.sub.is_none() && body.sub_type.is_none() {
+    return InternalError::bad_request()
+      .with_error("sub", REQUIRED_ERROR)
+      .into_response();
+  }
+  let acl_row = match repository::acl::create_object_acl(
+    &state.pool,
+    claims.app,
+    body.sub,
+    body.sub_type,
+    body.prefix.trim_start_matches('/').to_owned(),
+    body.permission.as_str(),
+  )
+  .await
+  {
+    Ok(acl_row) => acl_row,
+    Err(err) => {
+      log::error!("Error creating acl in database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+
+  (StatusCode::CREATED, axum::Json(ObjectAcl::from(acl_row))).into_response()
+}
+
+#[utoipa::path(
+  delete,
+  path = "/acls/{acl_id}",
+  tags = [ACL_TAG],
+  responses(
+    (status = 204),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:admin"])
+  )
+)]
+pub async fn delete_acl(
+  State(state): State<RouterState>,
+  Authorization { claims, .. }: Authorization,
+  Path(acl_id): Path<i64>,
+) -> impl IntoResponse {
+  if let Err(err) = claims.require_scope(OBJECTS_ADMIN_SCOPE) {
+    return err.into_response();
+  }
+  match repository::acl::delete_object_acl(&state.pool, claims.app, acl_id).await {
+    Ok(Some(_)) => (StatusCode::NO_CONTENT, ()).into_response(),
+    Ok(None) => {
+      log::error!("Acl not found: {}", acl_id);
+      InternalError::not_found()
+        .with_error("acl_id", NOT_FOUND_ERROR)
+        .into_response()
+    }
+    Err(err) => {
+      log::error!("Error deleting acl from database: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+pub fn create_router(state: RouterState) -> OpenApiRouter {
+  OpenApiRouter::new()
+    .routes(routes!(get_acls))
+    .routes(routes!(create_acl))
+    .routes(routes!(delete_acl))
+    .with_state(state)
+}
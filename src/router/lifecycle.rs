@@ -0,0 +1,276 @@
+use crate::{
+  core::error::{Errors, InternalError, INTERNAL_ERROR, INVALID_ERROR, NOT_FOUND_ERROR},
+  middleware::{
+    authorization::{Authorization, OBJECTS_ADMIN_SCOPE},
+    json::Json,
+  },
+  model::{
+    lifecycle::{LifecycleRule, LifecycleRulePagination, LifecycleRuleRequest},
+    util::{OffsetAndLimit, Pagination},
+  },
+  repository::{self, lifecycle::LifecycleRuleRow},
+};
+
+use axum::{
+  extract::{Path, Query, State},
+  http::StatusCode,
+  response::{IntoResponse, Response},
+};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use super::RouterState;
+
+pub const LIFECYCLE_TAG: &str = "lifecycle";
+
+#[utoipa::path(
+  get,
+  path = "/lifecycle-rules",
+  tags = [LIFECYCLE_TAG],
+  params(
+    OffsetAndLimit,
+  ),
+  responses(
+    (status = 200, content_type = "application/json", body = LifecycleRulePagination),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:admin"])
+  )
+)]
+pub async fn get_lifecycle_rules(
+  State(state): State<RouterState>,
+  Authorization { claims, .. }: Authorization,
+  Query(offset_and_limit_query): Query<OffsetAndLimit>,
+) -> impl IntoResponse {
+  if let Err(err) = claims.require_scope(OBJECTS_ADMIN_SCOPE) {
+    return err.into_response();
+  }
+  let rules = match repository::lifecycle::get_lifecycle_rules(
+    &state.pool,
+    claims.app,
+    offset_and_limit_query.limit,
+    offset_and_limit_query.offset,
+  )
+  .await
+  {
+    Ok(rules) => rules,
+    Err(err) => {
+      log::error!("Error getting lifecycle rules from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+
+  axum::Json(Pagination {
+    has_more: rules.len() == offset_and_limit_query.limit.unwrap_or(usize::MAX),
+    items: rules.into_iter().map(LifecycleRule::from).collect(),
+  })
+  .into_response()
+}
+
+#[utoipa::path(
+  get,
+  path = "/lifecycle-rules/{rule_id}",
+  tags = [LIFECYCLE_TAG],
+  responses(
+    (status = 200, content_type = "application/json", body = LifecycleRule),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:admin"])
+  )
+)]
+pub async fn get_lifecycle_rule(
+  State(state): State<RouterState>,
+  Authorization { claims, .. }: Authorization,
+  Path(rule_id): Path<i64>,
+) -> impl IntoResponse {
+  if let Err(err) = claims.require_scope(OBJECTS_ADMIN_SCOPE) {
+    return err.into_response();
+  }
+  lifecycle_rule_response(
+    rule_id,
+    repository::lifecycle::get_lifecycle_rule(&state.pool, claims.app, rule_id).await,
+  )
+}
+
+#[utoipa::path(
+  post,
+  path = "/lifecycle-rules",
+  tags = [LIFECYCLE_TAG],
+  request_body = LifecycleRuleRequest,
+  responses(
+    (status = 201, content_type = "application/json", body = LifecycleRule),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:admin"])
+  )
+)]
+pub async fn create_lifecycle_rule(
+  State(state): State<RouterState>,
+  Authorization { claims, .. }: Authorization,
+  Json(body): Json<LifecycleRuleRequest>,
+) -> impl IntoResponse {
+  if let Err(err) = claims.require_scope(OBJECTS_ADMIN_SCOPE) {
+    return err.into_response();
+  }
+  if let Err(err) = validate_lifecycle_rule(&body) {
+    return err.into_response();
+  }
+  let rule_row = match repository::lifecycle::create_lifecycle_rule(
+    &state.pool,
+    claims.app,
+    body.prefix.trim_start_matches('/').to_owned(),
+    body.age_from.as_str(),
+    body.days as i64,
+  )
+  .await
+  {
+    Ok(rule_row) => rule_row,
+    Err(err) => {
+      log::error!("Error creating lifecycle rule in database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+
+  (
+    StatusCode::CREATED,
+    axum::Json(LifecycleRule::from(rule_row)),
+  )
+    .into_response()
+}
+
+#[utoipa::path(
+  put,
+  path = "/lifecycle-rules/{rule_id}",
+  tags = [LIFECYCLE_TAG],
+  request_body = LifecycleRuleRequest,
+  responses(
+    (status = 200, content_type = "application/json", body = LifecycleRule),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:admin"])
+  )
+)]
+pub async fn update_lifecycle_rule(
+  State(state): State<RouterState>,
+  Authorization { claims, .. }: Authorization,
+  Path(rule_id): Path<i64>,
+  Json(body): Json<LifecycleRuleRequest>,
+) -> impl IntoResponse {
+  if let Err(err) = claims.require_scope(OBJECTS_ADMIN_SCOPE) {
+    return err.into_response();
+  }
+  if let Err(err) = validate_lifecycle_rule(&body) {
+    return err.into_response();
+  }
+  lifecycle_rule_response(
+    rule_id,
+    repository::lifecycle::update_lifecycle_rule(
+      &state.pool,
+      claims.app,
+      rule_id,
+      body.prefix.trim_start_matches('/').to_owned(),
+      body.age_from.as_str(),
+      body.days as i64,
+    )
+    .await,
+  )
+}
+
+#[utoipa::path(
+  delete,
+  path = "/lifecycle-rules/{rule_id}",
+  tags = [LIFECYCLE_TAG],
+  responses(
+    (status = 204),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:admin"])
+  )
+)]
+pub async fn delete_lifecycle_rule(
+  State(state): State<RouterState>,
+  Authorization { claims, .. }: Authorization,
+  Path(rule_id): Path<i64>,
+) -> impl IntoResponse {
+  if let Err(err) = claims.require_scope(OBJECTS_ADMIN_SCOPE) {
+    return err.into_response();
+  }
+  match repository::lifecycle::delete_lifecycle_rule(&state.pool, claims.app, rule_id).await {
+    Ok(Some(_)) => (StatusCode::NO_CONTENT, ()).into_response(),
+    Ok(None) => {
+      log::error!("LifecycleRule not found: {}", rule_id);
+      InternalError::not_found()
+        .with_error("rule_id", NOT_FOUND_ERROR)
+        .into_response()
+    }
+    Err(err) => {
+      log::error!("Error deleting lifecycle rule from database: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+fn validate_lifecycle_rule(body: &LifecycleRuleRequest) -> Result<(), InternalError> {
+  if body.days == 0 {
+    log::error!("Lifecycle rule must expire objects after at least a day");
+    return Err(InternalError::bad_request().with_error("days", INVALID_ERROR));
+  }
+  Ok(())
+}
+
+fn lifecycle_rule_response(
+  rule_id: i64,
+  result: sqlx::Result<Option<LifecycleRuleRow>>,
+) -> Response {
+  match result {
+    Ok(Some(rule_row)) => axum::Json(LifecycleRule::from(rule_row)).into_response(),
+    Ok(None) => {
+      log::error!("LifecycleRule not found: {}", rule_id);
+      InternalError::not_found()
+        .with_error("rule_id", NOT_FOUND_ERROR)
+        .into_response()
+    }
+    Err(err) => {
+      log::error!("Error getting lifecycle rule from database: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+pub fn create_router(state: RouterState) -> OpenApiRouter {
+  OpenApiRouter::new()
+    .routes(routes!(get_lifecycle_rules, create_lifecycle_rule))
+    .routes(routes!(
+      get_lifecycle_rule,
+      update_lifecycle_rule,
+      delete_lifecycle_rule
+    ))
+    .with_state(state)
+}
@@ -1,27 +1,44 @@
+pub mod acl;
+pub mod lifecycle;
 pub mod object;
 pub mod openapi;
+pub mod quota;
+pub mod s3;
+pub mod trash;
+pub mod tus;
 pub mod util;
 
 use std::sync::Arc;
 
+use acl::ACL_TAG;
 use axum::Router;
+use lifecycle::LIFECYCLE_TAG;
 use object::OBJECT_TAG;
 use openapi::OPENAPI_TAG;
+use quota::QUOTA_TAG;
 use sqlx::AnyPool;
 use tower_http::{compression::CompressionLayer, cors::CorsLayer, trace::TraceLayer};
+use trash::TRASH_TAG;
+use tus::TUS_TAG;
 use util::UTIL_TAG;
 use utoipa::{Modify, OpenApi};
 use utoipa_axum::router::OpenApiRouter;
 
-use crate::core::{
-  config::Config,
-  openapi::{SecurityAddon, ServersAddon},
+use crate::{
+  auth::AuthProvider,
+  core::{
+    config::Config,
+    openapi::{SecurityAddon, ServersAddon},
+  },
+  storage::StorageBackend,
 };
 
 #[derive(Clone)]
 pub struct RouterState {
   pub pool: AnyPool,
   pub config: Arc<Config>,
+  pub storage: Arc<dyn StorageBackend>,
+  pub auth: Arc<dyn AuthProvider>,
 }
 
 unsafe impl Send for RouterState {}
@@ -32,6 +49,11 @@ unsafe impl Sync for RouterState {}
   info(license(name = "MIT OR Apache-2.0", identifier = "https://spdx.org/licenses/MIT.html")),
   tags(
     (name = OBJECT_TAG, description = "Object endpoints"),
+    (name = TUS_TAG, description = "Resumable upload endpoints"),
+    (name = ACL_TAG, description = "Access control endpoints"),
+    (name = QUOTA_TAG, description = "Quota endpoints"),
+    (name = LIFECYCLE_TAG, description = "Lifecycle rule endpoints"),
+    (name = TRASH_TAG, description = "Trash endpoints"),
     (name = UTIL_TAG, description = "Utility endpoints"),
     (name = OPENAPI_TAG, description = "OpenApi endpoints"),
   ),
@@ -46,13 +68,22 @@ pub fn create_router(state: RouterState) -> Router {
 
   let open_api_router = OpenApiRouter::with_openapi(ApiDoc::openapi())
     .merge(object::create_router(state.clone()))
+    .merge(tus::create_router(state.clone()))
+    .merge(acl::create_router(state.clone()))
+    .merge(quota::create_router(state.clone()))
+    .merge(lifecycle::create_router(state.clone()))
+    .merge(trash::create_router(state.clone()))
     .merge(util::create_router(state.clone()));
 
   let openapi = open_api_router.get_openapi().clone();
-  open_api_router
+  let mut router: Router = open_api_router
     .merge(openapi::create_router(openapi))
+    .into();
+  if state.config.s3.enabled {
+    router = router.merge(s3::create_router(state.clone()));
+  }
+  router
     .layer(CorsLayer::very_permissive())
     .layer(TraceLayer::new_for_http())
     .layer(CompressionLayer::new().gzip(true))
-    .into()
 }
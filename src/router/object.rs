@@ -1,27 +1,63 @@
-use std::usize;
+use std::{
+  collections::HashMap,
+  io,
+  ops::Range,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+};
 
 use crate::{
-  core::error::{
-    Errors, InternalError, INTERNAL_ERROR, INVALID_ERROR, NOT_FOUND_ERROR, REQUEST_BODY,
+  core::{
+    checksum::{ExpectedDigests, Hasher},
+    conditional::{evaluate_preconditions, Precondition},
+    error::{
+      Errors, InternalError, ALREADY_EXISTS_ERROR, INTERNAL_ERROR, INVALID_ERROR,
+      NOT_ALLOWED_ERROR, NOT_FOUND_ERROR, PRECONDITION_FAILED_ERROR, REQUEST_BODY, REQUIRED_ERROR,
+      TOO_LARGE_ERROR,
+    },
+    presign::Presigned,
+    range::{content_range, parse_range, unsatisfied_content_range, RangeError, BYTES_UNIT},
+  },
+  middleware::{
+    authorization::{
+      Authorization, OBJECTS_ADMIN_SCOPE, OBJECTS_DELETE_SCOPE, OBJECTS_READ_SCOPE,
+      OBJECTS_WRITE_SCOPE,
+    },
+    json::Json,
   },
-  middleware::{authorization::Authorization, json::Json},
   model::{
+    multipart::{
+      CompleteMultipartUploadRequest, CreateMultipartUploadRequest, MultipartPart, MultipartUpload,
+    },
     object::{
-      CreateObjectRequest, MoveObjectRequest, ObjectInstance, ObjectInstancePagination,
-      ObjectQuery, ObjectsQuery, UploadPartRequest, UploadResponse,
+      CopyObjectRequest, CreateObjectRequest, DeleteFolderQuery, DeleteFolderResponse,
+      MoveFolderRequest, MoveFolderResponse, MoveObjectRequest, ObjectInstance,
+      ObjectInstancePagination, ObjectLegalHoldRequest, ObjectQuery, ObjectRetentionRequest,
+      ObjectVersion, ObjectVersionPagination, ObjectsQuery, PresignMethod, PresignObjectRequest,
+      PresignedObjectUrl, PresignedQuery, UploadPartRequest, UploadResponse,
     },
     util::{OffsetAndLimit, Pagination},
   },
-  repository, service,
+  repository::{self, multipart::MultipartUploadRow, object::ObjectRow, version::ObjectVersionRow},
+  service::{
+    self,
+    multipart::MultipartError,
+    object::{FolderMove, ObjectCopy},
+    quota::QuotaAllowance,
+  },
+  storage::{ObjectReader, StorageBackend},
 };
 
 use axum::{
-  body::Body,
+  body::{Body, Bytes},
   extract::{Multipart, Path, Query, State},
-  http::{header, StatusCode},
-  response::IntoResponse,
+  http::{header, HeaderMap, HeaderName, Method, StatusCode},
+  response::{IntoResponse, Response},
 };
-use tokio::fs;
+use chrono::{TimeDelta, Utc};
+use futures_util::{Stream, StreamExt};
 use tokio_util::io::ReaderStream;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
@@ -29,6 +65,11 @@ use super::RouterState;
 
 pub const OBJECT_TAG: &str = "object";
 
+const DEFAULT_PRESIGN_EXPIRES_IN: u64 = 15 * 60;
+
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+const MAX_RANGES: usize = 16;
+
 #[utoipa::path(
   get,
   path = "/objects",
@@ -40,21 +81,25 @@ pub const OBJECT_TAG: &str = "object";
   responses(
     (status = 200, content_type = "application/json", body = ObjectInstancePagination),
     (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
     (status = 500, content_type = "application/json", body = Errors),
   ),
   security(
-    ("Authorization" = [])
+    ("Authorization" = ["objects:list"])
   )
 )]
 pub async fn get_objects(
   State(state): State<RouterState>,
-  Authorization { .. }: Authorization,
+  authorization: Authorization,
   Query(offset_and_limit_query): Query<OffsetAndLimit>,
   Query(objects_query): Query<ObjectsQuery>,
 ) -> impl IntoResponse {
+  let prefixes = authorization.list_prefixes();
   let objects = match repository::object::get_objects_and_folders(
     &state.pool,
-    objects_query.path.as_ref().map(String::as_str),
+    authorization.claims.app,
+    objects_query.path.as_deref(),
+    prefixes.as_deref(),
     offset_and_limit_query.limit,
     offset_and_limit_query.offset,
   )
@@ -85,37 +130,56 @@ pub async fn get_objects(
   ),
   responses(
     (status = 200, content_type = "application/json", body = ObjectInstance),
+    (status = 304),
     (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
     (status = 404, content_type = "application/json", body = Errors),
     (status = 500, content_type = "application/json", body = Errors),
   ),
   security(
-    ("Authorization" = [])
+    ("Authorization" = ["objects:read"])
   )
 )]
 pub async fn get_object_by_path(
   State(state): State<RouterState>,
-  Authorization { .. }: Authorization,
+  authorization: Authorization,
   Query(object_query): Query<ObjectQuery>,
+  headers: HeaderMap,
 ) -> impl IntoResponse {
-  let object_row =
-    match repository::object::get_object_by_path(&state.pool, &object_query.path).await {
-      Ok(Some(object_row)) => object_row,
-      Ok(None) => {
-        log::error!("ObjectInstance not found: {}", object_query.path);
-        return InternalError::not_found()
-          .with_error("path", NOT_FOUND_ERROR)
-          .into_response();
-      }
-      Err(err) => {
-        log::error!("Error getting objects from database: {}", err);
-        return InternalError::internal_error()
-          .with_application_error(INTERNAL_ERROR)
-          .into_response();
-      }
-    };
+  if let Err(err) = authorization.require(OBJECTS_READ_SCOPE, &object_query.path) {
+    return err.into_response();
+  }
+  let object_row = match repository::object::get_object_by_path(
+    &state.pool,
+    authorization.claims.app,
+    &object_query.path,
+  )
+  .await
+  {
+    Ok(Some(object_row)) => object_row,
+    Ok(None) => {
+      log::error!("ObjectInstance not found: {}", object_query.path);
+      return InternalError::not_found()
+        .with_error("path", NOT_FOUND_ERROR)
+        .into_response();
+    }
+    Err(err) => {
+      log::error!("Error getting objects from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
 
-  axum::Json(ObjectInstance::from(object_row)).into_response()
+  if let Some(response) = precondition_response(&headers, true, &object_row) {
+    return response;
+  }
+
+  (
+    validator_headers(&object_row),
+    axum::Json(ObjectInstance::from(object_row)),
+  )
+    .into_response()
 }
 
 #[utoipa::path(
@@ -124,101 +188,106 @@ pub async fn get_object_by_path(
   tags = [OBJECT_TAG],
   responses(
     (status = 200, content_type = "application/json", body = ObjectInstance),
+    (status = 304),
     (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
     (status = 404, content_type = "application/json", body = Errors),
     (status = 500, content_type = "application/json", body = Errors),
   ),
   security(
-    ("Authorization" = [])
+    ("Authorization" = ["objects:read"])
   )
 )]
 pub async fn get_object_by_id(
   State(state): State<RouterState>,
-  Authorization { .. }: Authorization,
+  authorization: Authorization,
   Path(object_id): Path<i64>,
+  headers: HeaderMap,
 ) -> impl IntoResponse {
-  let object_row = match repository::object::get_object_by_id(&state.pool, object_id).await {
-    Ok(Some(object_row)) => object_row,
-    Ok(None) => {
-      log::error!("ObjectInstance not found: {}", object_id);
-      return InternalError::not_found()
-        .with_error("object_id", NOT_FOUND_ERROR)
-        .into_response();
-    }
-    Err(err) => {
-      log::error!("Error getting objects from database: {}", err);
-      return InternalError::internal_error()
-        .with_application_error(INTERNAL_ERROR)
-        .into_response();
-    }
-  };
+  let object_row =
+    match repository::object::get_object_by_id(&state.pool, authorization.claims.app, object_id)
+      .await
+    {
+      Ok(Some(object_row)) => object_row,
+      Ok(None) => {
+        log::error!("ObjectInstance not found: {}", object_id);
+        return InternalError::not_found()
+          .with_error("object_id", NOT_FOUND_ERROR)
+          .into_response();
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    };
+  if let Err(err) = authorization.require(OBJECTS_READ_SCOPE, &object_row.path) {
+    return err.into_response();
+  }
 
-  axum::Json(ObjectInstance::from(object_row)).into_response()
+  if let Some(response) = precondition_response(&headers, true, &object_row) {
+    return response;
+  }
+
+  (
+    validator_headers(&object_row),
+    axum::Json(ObjectInstance::from(object_row)),
+  )
+    .into_response()
 }
 
 #[utoipa::path(
   get,
   path = "/objects/{object_id}/read",
   tags = [OBJECT_TAG],
+  params(
+    PresignedQuery,
+  ),
   responses(
     (status = 200, content_type = "*/*"),
+    (status = 206, content_type = "*/*"),
+    (status = 304),
     (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
     (status = 404, content_type = "application/json", body = Errors),
+    (status = 416, content_type = "application/json", body = Errors),
     (status = 500, content_type = "application/json", body = Errors),
   ),
   security(
-    ("Authorization" = [])
+    ("Authorization" = ["objects:read"]),
+    ()
   )
 )]
 pub async fn read_object_by_id(
   State(state): State<RouterState>,
-  Authorization { .. }: Authorization,
+  authorization: Authorization,
   Path(object_id): Path<i64>,
+  headers: HeaderMap,
 ) -> impl IntoResponse {
-  let object_row = match repository::object::get_object_by_id(&state.pool, object_id).await {
-    Ok(Some(object)) => object,
-    Ok(None) => {
-      log::error!("ObjectInstance not found: {}", object_id);
-      return InternalError::not_found()
-        .with_error("object_id", NOT_FOUND_ERROR)
-        .into_response();
-    }
-    Err(err) => {
-      log::error!("Error getting objects from database: {}", err);
-      return InternalError::internal_error()
-        .with_application_error(INTERNAL_ERROR)
-        .into_response();
-    }
-  };
+  let object_row =
+    match repository::object::get_object_by_id(&state.pool, authorization.claims.app, object_id)
+      .await
+    {
+      Ok(Some(object)) => object,
+      Ok(None) => {
+        log::error!("ObjectInstance not found: {}", object_id);
+        return InternalError::not_found()
+          .with_error("object_id", NOT_FOUND_ERROR)
+          .into_response();
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    };
+  if let Err(err) = authorization.require(OBJECTS_READ_SCOPE, &object_row.path) {
+    return err.into_response();
+  }
 
-  let objects_path = std::path::Path::new(&state.config.objects_dir);
-  let object_path = objects_path.join(object_row.id.to_string());
-  let object = match fs::OpenOptions::new()
-    .create(false)
-    .read(true)
-    .open(object_path)
-    .await
-  {
-    Ok(object) => object,
-    Err(err) => {
-      log::error!("Error opening object: {}", err);
-      return InternalError::internal_error()
-        .with_application_error(INTERNAL_ERROR)
-        .into_response();
-    }
-  };
-  let content_type = object_row
-    .r#type
-    .unwrap_or_else(|| "application/octet-stream".to_owned());
-  let content_disposition = format!("attachment; objectname={:?}", object_row.path);
-  (
-    [
-      (header::CONTENT_TYPE, content_type),
-      (header::CONTENT_DISPOSITION, content_disposition),
-    ],
-    Body::from_stream(ReaderStream::new(object)),
-  )
-    .into_response()
+  read_object(&state, object_row, &headers).await
 }
 
 #[utoipa::path(
@@ -227,23 +296,39 @@ pub async fn read_object_by_id(
   tags = [OBJECT_TAG],
   params(
     ObjectQuery,
+    PresignedQuery,
   ),
   responses(
     (status = 200, content_type = "*/*"),
+    (status = 206, content_type = "*/*"),
+    (status = 304),
     (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
     (status = 404, content_type = "application/json", body = Errors),
+    (status = 416, content_type = "application/json", body = Errors),
     (status = 500, content_type = "application/json", body = Errors),
   ),
   security(
-    ("Authorization" = [])
+    ("Authorization" = ["objects:read"]),
+    ()
   )
 )]
 pub async fn read_object_by_path(
   State(state): State<RouterState>,
-  Authorization { .. }: Authorization,
+  authorization: Authorization,
   Query(query): Query<ObjectQuery>,
+  headers: HeaderMap,
 ) -> impl IntoResponse {
-  let object_row = match repository::object::get_object_by_path(&state.pool, &query.path).await {
+  if let Err(err) = authorization.require(OBJECTS_READ_SCOPE, &query.path) {
+    return err.into_response();
+  }
+  let object_row = match repository::object::get_object_by_path(
+    &state.pool,
+    authorization.claims.app,
+    &query.path,
+  )
+  .await
+  {
     Ok(Some(object)) => object,
     Ok(None) => {
       log::error!("ObjectInstance not found: {}", query.path);
@@ -259,34 +344,7 @@ pub async fn read_object_by_path(
     }
   };
 
-  let objects_path = std::path::Path::new(&state.config.objects_dir);
-  let object_path = objects_path.join(object_row.id.to_string());
-  let object = match fs::OpenOptions::new()
-    .create(false)
-    .read(true)
-    .open(object_path)
-    .await
-  {
-    Ok(object) => object,
-    Err(err) => {
-      log::error!("Error opening object: {}", err);
-      return InternalError::internal_error()
-        .with_application_error(INTERNAL_ERROR)
-        .into_response();
-    }
-  };
-  let content_type = object_row
-    .r#type
-    .unwrap_or_else(|| "application/octet-stream".to_owned());
-  let content_disposition = format!("attachment; objectname={:?}", object_row.path);
-  (
-    [
-      (header::CONTENT_TYPE, content_type),
-      (header::CONTENT_DISPOSITION, content_disposition),
-    ],
-    Body::from_stream(ReaderStream::new(object)),
-  )
-    .into_response()
+  read_object(&state, object_row, &headers).await
 }
 
 #[utoipa::path(
@@ -298,29 +356,45 @@ pub async fn read_object_by_path(
     (status = 201, content_type = "application/json", body = ObjectInstance),
     (status = 400, content_type = "application/json", body = Errors),
     (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
     (status = 500, content_type = "application/json", body = Errors),
   ),
   security(
-    ("Authorization" = [])
+    ("Authorization" = ["objects:write"])
   )
 )]
 pub async fn create_object(
   State(state): State<RouterState>,
-  Authorization { .. }: Authorization,
+  authorization: Authorization,
   Json(body): Json<CreateObjectRequest>,
 ) -> impl IntoResponse {
-  let object_row =
-    match service::object::create_object(&state.pool, state.config.clone(), body.path, body.r#type)
-      .await
-    {
-      Ok(object_row) => object_row,
-      Err(err) => {
-        log::error!("Error creating object in database: {}", err);
-        return InternalError::internal_error()
-          .with_application_error(INTERNAL_ERROR)
-          .into_response();
-      }
-    };
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &body.path) {
+    return err.into_response();
+  }
+  let allowance = match quota_allowance(&state, authorization.claims.app, &body.path).await {
+    Ok(allowance) => allowance,
+    Err(response) => return response,
+  };
+  if let Err(err) = allowance.check(1, 0) {
+    return err.into_response();
+  }
+  let object_row = match service::object::create_object(
+    &state.pool,
+    state.storage.clone(),
+    authorization.claims.app,
+    body.path,
+    body.r#type,
+  )
+  .await
+  {
+    Ok(object_row) => object_row,
+    Err(err) => {
+      log::error!("Error creating object in database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
 
   (
     StatusCode::CREATED,
@@ -338,45 +412,75 @@ pub async fn create_object(
     (status = 200, content_type = "application/json", body = UploadResponse),
     (status = 400, content_type = "application/json", body = Errors),
     (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
     (status = 404, content_type = "application/json", body = Errors),
+    (status = 412, content_type = "application/json", body = Errors),
     (status = 500, content_type = "application/json", body = Errors),
   ),
   security(
-    ("Authorization" = [])
+    ("Authorization" = ["objects:write"])
   )
 )]
 pub async fn append_object(
   State(state): State<RouterState>,
-  Authorization { .. }: Authorization,
+  authorization: Authorization,
   Path(object_id): Path<i64>,
+  headers: HeaderMap,
   mut multipart: Multipart,
 ) -> impl IntoResponse {
-  let object_row = match repository::object::get_object_by_id(&state.pool, object_id).await {
-    Ok(Some(object)) => object,
-    Ok(None) => {
-      log::error!("ObjectInstance not found: {}", object_id);
-      return InternalError::not_found()
-        .with_error("object_id", NOT_FOUND_ERROR)
-        .into_response();
-    }
-    Err(err) => {
-      log::error!("Error getting objects from database: {}", err);
-      return InternalError::internal_error()
-        .with_application_error(INTERNAL_ERROR)
+  let object_row =
+    match repository::object::get_object_by_id(&state.pool, authorization.claims.app, object_id)
+      .await
+    {
+      Ok(Some(object)) => object,
+      Ok(None) => {
+        log::error!("ObjectInstance not found: {}", object_id);
+        return InternalError::not_found()
+          .with_error("object_id", NOT_FOUND_ERROR)
+          .into_response();
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    };
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &object_row.path) {
+    return err.into_response();
+  }
+  if let Some(response) = precondition_response(&headers, false, &object_row) {
+    return response;
+  }
+  if let Err(err) =
+    service::lock::check_unlocked(&object_row, authorization.bypass_governance(&headers))
+  {
+    return err.into_response();
+  }
+  let expected_digests = match ExpectedDigests::from_headers(&headers) {
+    Ok(expected_digests) => expected_digests,
+    Err(name) => {
+      log::error!("Invalid {} header", name);
+      return InternalError::bad_request()
+        .with_error(name, INVALID_ERROR)
         .into_response();
     }
   };
-  let objects_path = std::path::Path::new(&state.config.objects_dir);
-  let object_path = objects_path.join(object_row.id.to_string());
-  let mut object = match fs::OpenOptions::new()
-    .create(false)
-    .read(false)
-    .append(true)
-    .open(object_path)
-    .await
+  let mut content_hasher = Hasher::new();
+  let mut append = match service::object::open_append(
+    &state.pool,
+    state.storage.as_ref(),
+    &state.config,
+    object_row.id,
+    authorization.bypass_governance(&headers),
+  )
+  .await
   {
-    Ok(object) => object,
+    Ok(append) => append,
     Err(err) => {
+      if let Some(object_locked) = service::lock::object_locked(&err) {
+        return InternalError::from(object_locked).into_response();
+      }
       log::error!("Error opening object: {}", err);
       return InternalError::internal_error()
         .with_application_error(INTERNAL_ERROR)
@@ -385,27 +489,41 @@ pub async fn append_object(
   };
 
   let mut written = 0;
+  let mut failed = None;
   loop {
     match multipart.next_field().await {
       Ok(Some(field)) => match field.bytes().await {
         Ok(bytes) => {
-          match service::object::append_object(&state.pool, object_id, &mut object, bytes).await {
+          if !expected_digests.is_empty() {
+            content_hasher.update(&bytes);
+          }
+          match service::object::append_object(&state.pool, &mut append, bytes).await {
             Ok(w) => {
               written += w;
             }
             Err(err) => {
+              if let Some(quota_exceeded) = service::quota::quota_exceeded(&err) {
+                failed = Some(InternalError::from(quota_exceeded).into_response());
+                break;
+              }
               log::error!("Error appending object: {}", err);
-              return InternalError::internal_error()
-                .with_application_error(INTERNAL_ERROR)
-                .into_response();
+              failed = Some(
+                InternalError::internal_error()
+                  .with_application_error(INTERNAL_ERROR)
+                  .into_response(),
+              );
+              break;
             }
           }
         }
         Err(err) => {
           log::error!("Error reading field: {}", err);
-          return InternalError::bad_request()
-            .with_error(REQUEST_BODY, INVALID_ERROR)
-            .into_response();
+          failed = Some(
+            InternalError::bad_request()
+              .with_error(REQUEST_BODY, INVALID_ERROR)
+              .into_response(),
+          );
+          break;
         }
       },
       Ok(None) => {
@@ -413,80 +531,550 @@ pub async fn append_object(
       }
       Err(err) => {
         log::error!("Error getting next field: {}", err);
-        return InternalError::bad_request()
-          .with_error(REQUEST_BODY, INVALID_ERROR)
-          .into_response();
+        failed = Some(
+          InternalError::bad_request()
+            .with_error(REQUEST_BODY, INVALID_ERROR)
+            .into_response(),
+        );
+        break;
       }
     }
   }
+  if let Some(response) = failed {
+    if let Err(err) =
+      service::object::abort_append(&state.pool, state.storage.as_ref(), append).await
+    {
+      log::error!("Error aborting append: {}", err);
+    }
+    return response;
+  }
+  if let Err(name) = expected_digests.verify(&content_hasher.checksums()) {
+    log::error!("Appended bytes do not match {} header", name);
+    if let Err(err) =
+      service::object::abort_append(&state.pool, state.storage.as_ref(), append).await
+    {
+      log::error!("Error aborting append: {}", err);
+    }
+    return InternalError::bad_request()
+      .with_error(name, INVALID_ERROR)
+      .into_response();
+  }
+  if let Err(err) = service::object::finish_append(&state.pool, append).await {
+    log::error!("Error finishing object: {}", err);
+    return InternalError::internal_error()
+      .with_application_error(INTERNAL_ERROR)
+      .into_response();
+  }
   axum::Json(UploadResponse { written }).into_response()
 }
 
 #[utoipa::path(
   put,
-  path = "/objects/{object_id}/move",
+  path = "/objects/by-path/write",
   tags = [OBJECT_TAG],
-  request_body = MoveObjectRequest,
+  params(
+    ObjectQuery,
+    PresignedQuery,
+  ),
+  request_body(content = Vec<u8>, content_type = "application/octet-stream"),
   responses(
     (status = 200, content_type = "application/json", body = ObjectInstance),
     (status = 400, content_type = "application/json", body = Errors),
     (status = 401, content_type = "application/json", body = Errors),
-    (status = 404, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 412, content_type = "application/json", body = Errors),
+    (status = 413, content_type = "application/json", body = Errors),
     (status = 500, content_type = "application/json", body = Errors),
   ),
   security(
-    ("Authorization" = [])
+    ("Authorization" = ["objects:write"]),
+    ()
   )
 )]
-pub async fn move_object(
+pub async fn write_object_by_path(
   State(state): State<RouterState>,
-  Authorization { .. }: Authorization,
-  Path(object_id): Path<i64>,
-  Json(body): Json<MoveObjectRequest>,
+  authorization: Authorization,
+  Query(object_query): Query<ObjectQuery>,
+  headers: HeaderMap,
+  body: Body,
 ) -> impl IntoResponse {
-  let object_row =
-    match repository::object::update_object_path(&state.pool, object_id, body.path, body.r#type)
-      .await
-    {
-      Ok(Some(object)) => object,
-      Ok(None) => {
-        log::error!("ObjectInstance not found: {}", object_id);
-        return InternalError::not_found()
-          .with_error("object_id", NOT_FOUND_ERROR)
-          .into_response();
-      }
-      Err(err) => {
-        log::error!("Error getting objects from database: {}", err);
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &object_query.path) {
+    return err.into_response();
+  }
+  let size = match repository::object::get_object_by_path(
+    &state.pool,
+    authorization.claims.app,
+    &object_query.path,
+  )
+  .await
+  {
+    Ok(Some(object_row)) => {
+      if let Some(response) = precondition_response(&headers, false, &object_row) {
+        return response;
+      }
+      if let Err(err) =
+        service::lock::check_unlocked(&object_row, authorization.bypass_governance(&headers))
+      {
+        return err.into_response();
+      }
+      Some(object_row.size.max(0) as u64)
+    }
+    Ok(None) => {
+      if headers.contains_key(header::IF_MATCH) {
+        return InternalError::precondition_failed()
+          .with_error(header::IF_MATCH.as_str(), PRECONDITION_FAILED_ERROR)
+          .into_response();
+      }
+      None
+    }
+    Err(err) => {
+      log::error!("Error getting objects from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+  let max_body_size = authorization.max_body_size(state.config.max_body_size);
+  if let Some(response) = content_length_response(&headers, max_body_size) {
+    return response;
+  }
+  if let Some(response) = quota_content_length_response(
+    &state,
+    authorization.claims.app,
+    &object_query.path,
+    &headers,
+    size,
+  )
+  .await
+  {
+    return response;
+  }
+
+  let exceeded = Arc::new(AtomicBool::new(false));
+  let stream = limited_body_stream(body, max_body_size, exceeded.clone());
+  let result = service::object::put_object(
+    &state.pool,
+    state.storage.clone(),
+    &state.config,
+    authorization.claims.app,
+    object_query.path,
+    content_type(&headers),
+    authorization.bypass_governance(&headers),
+    stream,
+  )
+  .await;
+  write_response(result, &exceeded)
+}
+
+#[utoipa::path(
+  put,
+  path = "/objects/{object_id}/write",
+  tags = [OBJECT_TAG],
+  params(
+    PresignedQuery,
+  ),
+  request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+  responses(
+    (status = 200, content_type = "application/json", body = ObjectInstance),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 412, content_type = "application/json", body = Errors),
+    (status = 413, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"]),
+    ()
+  )
+)]
+pub async fn write_object_by_id(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+  headers: HeaderMap,
+  body: Body,
+) -> impl IntoResponse {
+  let object_row =
+    match repository::object::get_object_by_id(&state.pool, authorization.claims.app, object_id)
+      .await
+    {
+      Ok(Some(object)) => object,
+      Ok(None) => {
+        log::error!("ObjectInstance not found: {}", object_id);
+        return InternalError::not_found()
+          .with_error("object_id", NOT_FOUND_ERROR)
+          .into_response();
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
         return InternalError::internal_error()
           .with_application_error(INTERNAL_ERROR)
           .into_response();
       }
     };
-  axum::Json(ObjectInstance::from(object_row)).into_response()
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &object_row.path) {
+    return err.into_response();
+  }
+  if let Some(response) = precondition_response(&headers, false, &object_row) {
+    return response;
+  }
+  if let Err(err) =
+    service::lock::check_unlocked(&object_row, authorization.bypass_governance(&headers))
+  {
+    return err.into_response();
+  }
+  let max_body_size = authorization.max_body_size(state.config.max_body_size);
+  if let Some(response) = content_length_response(&headers, max_body_size) {
+    return response;
+  }
+  if let Some(response) = quota_content_length_response(
+    &state,
+    authorization.claims.app,
+    &object_row.path,
+    &headers,
+    Some(object_row.size.max(0) as u64),
+  )
+  .await
+  {
+    return response;
+  }
+
+  let exceeded = Arc::new(AtomicBool::new(false));
+  let stream = limited_body_stream(body, max_body_size, exceeded.clone());
+  let result = service::object::replace_object(
+    &state.pool,
+    state.storage.clone(),
+    &state.config,
+    object_row.id,
+    content_type(&headers).or(object_row.r#type),
+    authorization.bypass_governance(&headers),
+    stream,
+  )
+  .await;
+  write_response(result, &exceeded)
+}
+
+#[utoipa::path(
+  post,
+  path = "/objects/multipart",
+  tags = [OBJECT_TAG],
+  request_body = CreateMultipartUploadRequest,
+  responses(
+    (status = 201, content_type = "application/json", body = MultipartUpload),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn create_multipart_upload(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Json(body): Json<CreateMultipartUploadRequest>,
+) -> impl IntoResponse {
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &body.path) {
+    return err.into_response();
+  }
+  match service::multipart::create_upload(
+    &state.pool,
+    authorization.claims.app,
+    body.path,
+    body.r#type,
+  )
+  .await
+  {
+    Ok(upload_row) => (
+      StatusCode::CREATED,
+      axum::Json(MultipartUpload::from(upload_row)),
+    )
+      .into_response(),
+    Err(err) => {
+      log::error!("Error creating multipart upload in database: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+#[utoipa::path(
+  put,
+  path = "/objects/multipart/{upload_id}/parts/{part_number}",
+  tags = [OBJECT_TAG],
+  request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+  responses(
+    (status = 200, content_type = "application/json", body = MultipartPart),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 413, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn upload_multipart_part(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path((upload_id, part_number)): Path<(String, u32)>,
+  headers: HeaderMap,
+  body: Body,
+) -> impl IntoResponse {
+  if let Err(response) = authorize_multipart_upload(&state, &authorization, &upload_id).await {
+    return response;
+  }
+  let max_body_size = authorization.max_body_size(state.config.max_body_size);
+  if let Some(response) = content_length_response(&headers, max_body_size) {
+    return response;
+  }
+
+  let exceeded = Arc::new(AtomicBool::new(false));
+  let stream = limited_body_stream(body, max_body_size, exceeded.clone());
+  match service::multipart::upload_part(
+    &state.pool,
+    state.storage.as_ref(),
+    authorization.claims.app,
+    &upload_id,
+    part_number,
+    stream,
+  )
+  .await
+  {
+    Ok(part_row) => axum::Json(MultipartPart::from(part_row)).into_response(),
+    Err(_) if exceeded.load(Ordering::Relaxed) => {
+      log::error!("Multipart upload part is larger than {}", max_body_size);
+      InternalError::payload_too_large()
+        .with_error(REQUEST_BODY, TOO_LARGE_ERROR)
+        .into_response()
+    }
+    Err(err) => multipart_error_response(err),
+  }
+}
+
+#[utoipa::path(
+  get,
+  path = "/objects/multipart/{upload_id}/parts",
+  tags = [OBJECT_TAG],
+  responses(
+    (status = 200, content_type = "application/json", body = Vec<MultipartPart>),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn get_multipart_parts(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(upload_id): Path<String>,
+) -> impl IntoResponse {
+  if let Err(response) = authorize_multipart_upload(&state, &authorization, &upload_id).await {
+    return response;
+  }
+  match repository::multipart::get_multipart_parts(&state.pool, &upload_id).await {
+    Ok(part_rows) => axum::Json(
+      part_rows
+        .into_iter()
+        .map(MultipartPart::from)
+        .collect::<Vec<_>>(),
+    )
+    .into_response(),
+    Err(err) => multipart_error_response(err.into()),
+  }
+}
+
+#[utoipa::path(
+  post,
+  path = "/objects/multipart/{upload_id}/complete",
+  tags = [OBJECT_TAG],
+  request_body = CompleteMultipartUploadRequest,
+  responses(
+    (status = 200, content_type = "application/json", body = ObjectInstance),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn complete_multipart_upload(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(upload_id): Path<String>,
+  headers: HeaderMap,
+  Json(body): Json<CompleteMultipartUploadRequest>,
+) -> impl IntoResponse {
+  let upload_row = match authorize_multipart_upload(&state, &authorization, &upload_id).await {
+    Ok(upload_row) => upload_row,
+    Err(response) => return response,
+  };
+  match repository::object::get_object_by_path(
+    &state.pool,
+    authorization.claims.app,
+    &upload_row.path,
+  )
+  .await
+  {
+    Ok(Some(object_row)) => {
+      if let Err(err) =
+        service::lock::check_unlocked(&object_row, authorization.bypass_governance(&headers))
+      {
+        return err.into_response();
+      }
+    }
+    Ok(None) => {}
+    Err(err) => {
+      log::error!("Error getting objects from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  }
+  let parts = body.parts.map(|parts| {
+    parts
+      .into_iter()
+      .map(|part| (part.part_number, part.etag))
+      .collect::<Vec<_>>()
+  });
+  match service::multipart::complete_upload(
+    &state.pool,
+    state.storage.clone(),
+    &state.config,
+    authorization.claims.app,
+    &upload_id,
+    parts.as_deref(),
+    authorization.bypass_governance(&headers),
+  )
+  .await
+  {
+    Ok(object_row) => (
+      validator_headers(&object_row),
+      axum::Json(ObjectInstance::from(object_row)),
+    )
+      .into_response(),
+    Err(err) => multipart_error_response(err),
+  }
 }
 
 #[utoipa::path(
   delete,
-  path = "/objects/{object_id}",
+  path = "/objects/multipart/{upload_id}",
   tags = [OBJECT_TAG],
   responses(
     (status = 204),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn abort_multipart_upload(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(upload_id): Path<String>,
+) -> impl IntoResponse {
+  if let Err(response) = authorize_multipart_upload(&state, &authorization, &upload_id).await {
+    return response;
+  }
+  match service::multipart::abort_upload(
+    &state.pool,
+    state.storage.as_ref(),
+    authorization.claims.app,
+    &upload_id,
+  )
+  .await
+  {
+    Ok(_) => (StatusCode::NO_CONTENT, ()).into_response(),
+    Err(err) => multipart_error_response(err),
+  }
+}
+
+#[utoipa::path(
+  put,
+  path = "/objects/{object_id}/move",
+  tags = [OBJECT_TAG],
+  request_body = MoveObjectRequest,
+  responses(
+    (status = 200, content_type = "application/json", body = ObjectInstance),
     (status = 400, content_type = "application/json", body = Errors),
     (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
     (status = 404, content_type = "application/json", body = Errors),
+    (status = 412, content_type = "application/json", body = Errors),
     (status = 500, content_type = "application/json", body = Errors),
   ),
   security(
-    ("Authorization" = [])
+    ("Authorization" = ["objects:write"])
   )
 )]
-pub async fn delete_object(
+pub async fn move_object(
   State(state): State<RouterState>,
-  Authorization { .. }: Authorization,
+  authorization: Authorization,
   Path(object_id): Path<i64>,
+  headers: HeaderMap,
+  Json(body): Json<MoveObjectRequest>,
 ) -> impl IntoResponse {
-  match service::object::delete_object(&state.pool, state.config.clone(), object_id).await {
-    Ok(Some(_)) => {}
+  let object_row =
+    match repository::object::get_object_by_id(&state.pool, authorization.claims.app, object_id)
+      .await
+    {
+      Ok(Some(object)) => object,
+      Ok(None) => {
+        log::error!("ObjectInstance not found: {}", object_id);
+        return InternalError::not_found()
+          .with_error("object_id", NOT_FOUND_ERROR)
+          .into_response();
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    };
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &object_row.path) {
+    return err.into_response();
+  }
+  if let Some(response) = precondition_response(&headers, false, &object_row) {
+    return response;
+  }
+  if let Err(err) =
+    service::lock::check_unlocked(&object_row, authorization.bypass_governance(&headers))
+  {
+    return err.into_response();
+  }
+
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &body.path) {
+    return err.into_response();
+  }
+
+  let object_row = match service::object::move_object(
+    &state.pool,
+    &state.config,
+    authorization.claims.app,
+    object_id,
+    body.path,
+    body.r#type,
+  )
+  .await
+  {
+    Ok(Some(object)) => object,
     Ok(None) => {
       log::error!("ObjectInstance not found: {}", object_id);
       return InternalError::not_found()
@@ -494,13 +1082,1637 @@ pub async fn delete_object(
         .into_response();
     }
     Err(err) => {
-      log::error!("Error deleting object: {}", err);
+      if let Some(quota_exceeded) = service::quota::quota_exceeded(&err) {
+        return InternalError::from(quota_exceeded).into_response();
+      }
+      log::error!("Error moving object in database: {}", err);
       return InternalError::internal_error()
         .with_application_error(INTERNAL_ERROR)
         .into_response();
     }
-  }
-  (StatusCode::NO_CONTENT, ()).into_response()
+  };
+  (
+    validator_headers(&object_row),
+    axum::Json(ObjectInstance::from(object_row)),
+  )
+    .into_response()
+}
+
+#[utoipa::path(
+  put,
+  path = "/objects/by-path/move",
+  tags = [OBJECT_TAG],
+  params(
+    ObjectQuery,
+  ),
+  request_body = MoveFolderRequest,
+  responses(
+    (status = 200, content_type = "application/json", body = MoveFolderResponse),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 409, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn move_folder(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Query(object_query): Query<ObjectQuery>,
+  headers: HeaderMap,
+  Json(body): Json<MoveFolderRequest>,
+) -> impl IntoResponse {
+  let from = object_query.path.trim_matches('/').to_owned();
+  let to = body.path.trim_matches('/').to_owned();
+  if from.is_empty() || to.is_empty() {
+    log::error!("Moving a folder requires a source and a target path");
+    return InternalError::bad_request()
+      .with_error("path", REQUIRED_ERROR)
+      .into_response();
+  }
+  let prefix = format!("{}/", from);
+  let new_prefix = format!("{}/", to);
+  let object_rows = match get_all_objects_by_prefix(&state, authorization.claims.app, &prefix).await
+  {
+    Ok(object_rows) => object_rows,
+    Err(response) => return response,
+  };
+  if object_rows.is_empty() {
+    log::error!("Folder not found: {}", from);
+    return InternalError::not_found()
+      .with_error("path", NOT_FOUND_ERROR)
+      .into_response();
+  }
+  let bypass_governance = authorization.bypass_governance(&headers);
+  for object_row in &object_rows {
+    if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &object_row.path) {
+      return err.into_response();
+    }
+    let path = format!("{}{}", new_prefix, &object_row.path[prefix.len()..]);
+    if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &path) {
+      return err.into_response();
+    }
+    if let Err(err) = service::lock::check_unlocked(object_row, bypass_governance) {
+      return err.into_response();
+    }
+  }
+
+  match service::object::move_folder(
+    &state.pool,
+    &state.config,
+    authorization.claims.app,
+    prefix,
+    new_prefix,
+  )
+  .await
+  {
+    Ok(FolderMove::Moved(moved)) => {
+      axum::Json(MoveFolderResponse { from, to, moved }).into_response()
+    }
+    Ok(FolderMove::Nested) => {
+      log::error!("Folder {} can not be moved into {}", from, to);
+      InternalError::bad_request()
+        .with_error("path", INVALID_ERROR)
+        .into_response()
+    }
+    Ok(FolderMove::Conflicts(paths)) => {
+      log::error!(
+        "Moving folder {} to {} conflicts with {:?}",
+        from,
+        to,
+        paths
+      );
+      let parameters = HashMap::from([("paths".to_owned(), paths.into())]);
+      InternalError::from(StatusCode::CONFLICT)
+        .with_error("path", (ALREADY_EXISTS_ERROR, parameters))
+        .into_response()
+    }
+    Err(err) => {
+      if let Some(quota_exceeded) = service::quota::quota_exceeded(&err) {
+        return InternalError::from(quota_exceeded).into_response();
+      }
+      log::error!("Error moving folder in database: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+#[utoipa::path(
+  post,
+  path = "/objects/{object_id}/copy",
+  tags = [OBJECT_TAG],
+  request_body = CopyObjectRequest,
+  responses(
+    (status = 201, content_type = "application/json", body = ObjectInstance),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 409, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:read", "objects:write"])
+  )
+)]
+pub async fn copy_object(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+  Json(body): Json<CopyObjectRequest>,
+) -> impl IntoResponse {
+  let object_row =
+    match repository::object::get_object_by_id(&state.pool, authorization.claims.app, object_id)
+      .await
+    {
+      Ok(Some(object)) => object,
+      Ok(None) => {
+        log::error!("ObjectInstance not found: {}", object_id);
+        return InternalError::not_found()
+          .with_error("object_id", NOT_FOUND_ERROR)
+          .into_response();
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    };
+  let copies = vec![ObjectCopy {
+    source: object_row,
+    path: body.path.trim_matches('/').to_owned(),
+    kind: body.r#type,
+  }];
+  match copy_objects(&state, &authorization, copies).await {
+    Ok(mut object_rows) => (
+      StatusCode::CREATED,
+      axum::Json(ObjectInstance::from(object_rows.remove(0))),
+    )
+      .into_response(),
+    Err(response) => response,
+  }
+}
+
+#[utoipa::path(
+  post,
+  path = "/objects/by-path/copy",
+  tags = [OBJECT_TAG],
+  params(
+    ObjectQuery,
+  ),
+  request_body = CopyObjectRequest,
+  responses(
+    (status = 201, content_type = "application/json", body = Vec<ObjectInstance>),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 409, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:read", "objects:write"])
+  )
+)]
+pub async fn copy_object_by_path(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Query(object_query): Query<ObjectQuery>,
+  Json(body): Json<CopyObjectRequest>,
+) -> impl IntoResponse {
+  let path = object_query.path.trim_matches('/');
+  if path.is_empty() {
+    log::error!("Copying requires a source path");
+    return InternalError::bad_request()
+      .with_error("path", REQUIRED_ERROR)
+      .into_response();
+  }
+  let target = body.path.trim_matches('/');
+  let copies =
+    match repository::object::get_object_by_path(&state.pool, authorization.claims.app, path).await
+    {
+      Ok(Some(object_row)) => vec![ObjectCopy {
+        source: object_row,
+        path: target.to_owned(),
+        kind: body.r#type,
+      }],
+      Ok(None) => {
+        let prefix = format!("{}/", path);
+        let object_rows =
+          match get_all_objects_by_prefix(&state, authorization.claims.app, &prefix).await {
+            Ok(object_rows) => object_rows,
+            Err(response) => return response,
+          };
+        if object_rows.is_empty() {
+          log::error!("ObjectInstance not found: {}", path);
+          return InternalError::not_found()
+            .with_error("path", NOT_FOUND_ERROR)
+            .into_response();
+        }
+        object_rows
+          .into_iter()
+          .map(|object_row| ObjectCopy {
+            path: format!("{}/{}", target, &object_row.path[prefix.len()..]),
+            source: object_row,
+            kind: body.r#type.clone(),
+          })
+          .collect()
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    };
+  match copy_objects(&state, &authorization, copies).await {
+    Ok(object_rows) => (
+      StatusCode::CREATED,
+      axum::Json(
+        object_rows
+          .into_iter()
+          .map(ObjectInstance::from)
+          .collect::<Vec<_>>(),
+      ),
+    )
+      .into_response(),
+    Err(response) => response,
+  }
+}
+
+#[utoipa::path(
+  put,
+  path = "/objects/{object_id}/retention",
+  tags = [OBJECT_TAG],
+  request_body = ObjectRetentionRequest,
+  responses(
+    (status = 200, content_type = "application/json", body = ObjectInstance),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn set_object_retention(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+  headers: HeaderMap,
+  Json(body): Json<ObjectRetentionRequest>,
+) -> impl IntoResponse {
+  let retain_until = body.retain_until.timestamp();
+  if retain_until <= Utc::now().timestamp() {
+    log::error!("Retention must end in the future: {}", body.retain_until);
+    return InternalError::bad_request()
+      .with_error("retain_until", INVALID_ERROR)
+      .into_response();
+  }
+  update_object_retention(
+    &state,
+    &authorization,
+    object_id,
+    &headers,
+    Some((body.mode.as_str(), retain_until)),
+  )
+  .await
+}
+
+#[utoipa::path(
+  delete,
+  path = "/objects/{object_id}/retention",
+  tags = [OBJECT_TAG],
+  responses(
+    (status = 200, content_type = "application/json", body = ObjectInstance),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn delete_object_retention(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+  headers: HeaderMap,
+) -> impl IntoResponse {
+  update_object_retention(&state, &authorization, object_id, &headers, None).await
+}
+
+#[utoipa::path(
+  put,
+  path = "/objects/{object_id}/legal-hold",
+  tags = [OBJECT_TAG],
+  request_body = ObjectLegalHoldRequest,
+  responses(
+    (status = 200, content_type = "application/json", body = ObjectInstance),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:admin"])
+  )
+)]
+pub async fn set_object_legal_hold(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+  Json(body): Json<ObjectLegalHoldRequest>,
+) -> impl IntoResponse {
+  if let Err(err) = authorization.claims.require_scope(OBJECTS_ADMIN_SCOPE) {
+    return err.into_response();
+  }
+
+  // waits for writes and deletes of the object in progress, which checked it before starting
+  let _lock = service::object::lock_object(object_id).await;
+  match repository::object::update_object_legal_hold(
+    &state.pool,
+    authorization.claims.app,
+    object_id,
+    body.legal_hold,
+  )
+  .await
+  {
+    Ok(Some(object_row)) => axum::Json(ObjectInstance::from(object_row)).into_response(),
+    Ok(None) => {
+      log::error!("ObjectInstance not found: {}", object_id);
+      InternalError::not_found()
+        .with_error("object_id", NOT_FOUND_ERROR)
+        .into_response()
+    }
+    Err(err) => {
+      log::error!("Error updating object legal hold: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+#[utoipa::path(
+  delete,
+  path = "/objects/{object_id}",
+  tags = [OBJECT_TAG],
+  responses(
+    (status = 204),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 412, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:delete"])
+  )
+)]
+pub async fn delete_object(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+  headers: HeaderMap,
+) -> impl IntoResponse {
+  let object_row =
+    match repository::object::get_object_by_id(&state.pool, authorization.claims.app, object_id)
+      .await
+    {
+      Ok(Some(object)) => object,
+      Ok(None) => {
+        log::error!("ObjectInstance not found: {}", object_id);
+        return InternalError::not_found()
+          .with_error("object_id", NOT_FOUND_ERROR)
+          .into_response();
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    };
+  if let Err(err) = authorization.require(OBJECTS_DELETE_SCOPE, &object_row.path) {
+    return err.into_response();
+  }
+  if let Some(response) = precondition_response(&headers, false, &object_row) {
+    return response;
+  }
+  if let Err(err) =
+    service::lock::check_unlocked(&object_row, authorization.bypass_governance(&headers))
+  {
+    return err.into_response();
+  }
+
+  match service::object::delete_object(
+    &state.pool,
+    state.storage.clone(),
+    &state.config,
+    object_id,
+    authorization.bypass_governance(&headers),
+  )
+  .await
+  {
+    Ok(Some(_)) => {}
+    Ok(None) => {
+      log::error!("ObjectInstance not found: {}", object_id);
+      return InternalError::not_found()
+        .with_error("object_id", NOT_FOUND_ERROR)
+        .into_response();
+    }
+    Err(err) => {
+      if let Some(object_locked) = service::lock::object_locked(&err) {
+        return InternalError::from(object_locked).into_response();
+      }
+      log::error!("Error deleting object: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  }
+  (StatusCode::NO_CONTENT, ()).into_response()
+}
+
+#[utoipa::path(
+  delete,
+  path = "/objects/by-path/folder",
+  tags = [OBJECT_TAG],
+  params(
+    DeleteFolderQuery,
+  ),
+  responses(
+    (status = 200, content_type = "application/json", body = DeleteFolderResponse),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:delete"])
+  )
+)]
+pub async fn delete_folder(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Query(folder_query): Query<DeleteFolderQuery>,
+  headers: HeaderMap,
+) -> impl IntoResponse {
+  let path = folder_query.path.trim_matches('/').to_owned();
+  if path.is_empty() {
+    log::error!("Deleting a folder requires a path");
+    return InternalError::bad_request()
+      .with_error("path", REQUIRED_ERROR)
+      .into_response();
+  }
+  let prefix = format!("{}/", path);
+  let batch_size = state.config.folder_delete.batch_size.max(1);
+  let bypass_governance = authorization.bypass_governance(&headers);
+  let mut response = DeleteFolderResponse {
+    path,
+    dry_run: folder_query.dry_run,
+    objects: 0,
+    bytes: 0,
+    deleted: 0,
+    locked: 0,
+    items: Vec::new(),
+  };
+
+  // counts the folder a page at a time before anything is deleted so an object that may not be
+  // deleted fails the request up front, locked objects are reported apart from the rest
+  let mut last_id = 0;
+  loop {
+    let object_rows = match get_folder_page(
+      &state,
+      authorization.claims.app,
+      &prefix,
+      last_id,
+      batch_size,
+    )
+    .await
+    {
+      Ok(object_rows) => object_rows,
+      Err(response) => return response,
+    };
+    for object_row in object_rows.iter() {
+      if let Err(err) = authorization.require(OBJECTS_DELETE_SCOPE, &object_row.path) {
+        return err.into_response();
+      }
+    }
+    let done = object_rows.len() < batch_size;
+    for object_row in object_rows {
+      last_id = object_row.id;
+      if service::lock::check_unlocked(&object_row, bypass_governance).is_err() {
+        response.locked += 1;
+        continue;
+      }
+      response.objects += 1;
+      response.bytes += object_row.size.max(0) as u64;
+      if folder_query.dry_run {
+        response.items.push(ObjectInstance::from(object_row));
+      }
+    }
+    if done {
+      break;
+    }
+  }
+  if last_id == 0 {
+    log::error!("Folder not found: {}", response.path);
+    return InternalError::not_found()
+      .with_error("path", NOT_FOUND_ERROR)
+      .into_response();
+  }
+  if folder_query.dry_run {
+    return axum::Json(response).into_response();
+  }
+  let objects = response.objects;
+  if objects > state.config.folder_delete.confirm_threshold && folder_query.confirm != Some(objects)
+  {
+    log::error!(
+      "Deleting folder {} with {} objects requires confirmation",
+      response.path,
+      objects
+    );
+    let parameters = HashMap::from([("objects".to_owned(), objects.into())]);
+    return InternalError::bad_request()
+      .with_error("confirm", (REQUIRED_ERROR, parameters))
+      .into_response();
+  }
+
+  // deletes what was counted page by page, objects created since then have larger ids and those
+  // moved in since then that may not be deleted are left alone, every object counted was already
+  // authorized so a missing right never stops the delete halfway
+  let max_id = last_id;
+  let mut after_id = 0;
+  while after_id < max_id {
+    let object_rows = match get_folder_page(
+      &state,
+      authorization.claims.app,
+      &prefix,
+      after_id,
+      batch_size,
+    )
+    .await
+    {
+      Ok(object_rows) => object_rows,
+      Err(response) => return response,
+    };
+    let Some(last_row) = object_rows.last() else {
+      break;
+    };
+    after_id = last_row.id;
+    let mut batch = Vec::with_capacity(object_rows.len());
+    for object_row in object_rows {
+      if object_row.id > max_id
+        || service::lock::check_unlocked(&object_row, bypass_governance).is_err()
+        || !authorization.can(OBJECTS_DELETE_SCOPE, &object_row.path)
+      {
+        continue;
+      }
+      batch.push(object_row);
+    }
+    if batch.is_empty() {
+      continue;
+    }
+    match service::object::delete_objects(
+      &state.pool,
+      state.storage.clone(),
+      &state.config,
+      &batch,
+      bypass_governance,
+    )
+    .await
+    {
+      Ok(deleted) => response.deleted += deleted,
+      Err(err) => {
+        if let Some(object_locked) = service::lock::object_locked(&err) {
+          return InternalError::from(object_locked).into_response();
+        }
+        log::error!(
+          "Error deleting folder {} after {} objects: {}",
+          response.path,
+          response.deleted,
+          err
+        );
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    }
+  }
+  axum::Json(response).into_response()
+}
+
+#[utoipa::path(
+  get,
+  path = "/objects/versions",
+  tags = [OBJECT_TAG],
+  params(
+    OffsetAndLimit,
+    ObjectQuery,
+  ),
+  responses(
+    (status = 200, content_type = "application/json", body = ObjectVersionPagination),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:read"])
+  )
+)]
+pub async fn get_object_versions(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Query(offset_and_limit_query): Query<OffsetAndLimit>,
+  Query(object_query): Query<ObjectQuery>,
+) -> impl IntoResponse {
+  if let Err(err) = authorization.require(OBJECTS_READ_SCOPE, &object_query.path) {
+    return err.into_response();
+  }
+  let versions = match repository::version::get_object_versions(
+    &state.pool,
+    authorization.claims.app,
+    &object_query.path,
+    offset_and_limit_query.limit,
+    offset_and_limit_query.offset,
+  )
+  .await
+  {
+    Ok(versions) => versions,
+    Err(err) => {
+      log::error!("Error getting object versions from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+
+  axum::Json(Pagination {
+    has_more: versions.len() == offset_and_limit_query.limit.unwrap_or(usize::MAX),
+    items: versions.into_iter().map(ObjectVersion::from).collect(),
+  })
+  .into_response()
+}
+
+#[utoipa::path(
+  get,
+  path = "/objects/versions/{version_id}/read",
+  tags = [OBJECT_TAG],
+  responses(
+    (status = 200, content_type = "*/*"),
+    (status = 206, content_type = "*/*"),
+    (status = 304),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 416, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:read"])
+  )
+)]
+pub async fn read_object_version(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(version_id): Path<i64>,
+  headers: HeaderMap,
+) -> impl IntoResponse {
+  let version_row = match get_object_version(&state, &authorization, version_id).await {
+    Ok(version_row) => version_row,
+    Err(response) => return response,
+  };
+  if let Err(err) = authorization.require(OBJECTS_READ_SCOPE, &version_row.path) {
+    return err.into_response();
+  }
+
+  read_object(&state, version_row.source_object_row(), &headers).await
+}
+
+#[utoipa::path(
+  post,
+  path = "/objects/versions/{version_id}/restore",
+  tags = [OBJECT_TAG],
+  responses(
+    (status = 200, content_type = "application/json", body = ObjectInstance),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn restore_object_version(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(version_id): Path<i64>,
+  headers: HeaderMap,
+) -> impl IntoResponse {
+  let version_row = match get_object_version(&state, &authorization, version_id).await {
+    Ok(version_row) => version_row,
+    Err(response) => return response,
+  };
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &version_row.path) {
+    return err.into_response();
+  }
+  let current_row = match repository::object::get_object_by_path(
+    &state.pool,
+    authorization.claims.app,
+    &version_row.path,
+  )
+  .await
+  {
+    Ok(current_row) => current_row,
+    Err(err) => {
+      log::error!("Error getting objects from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+  if let Some(current_row) = &current_row {
+    if let Err(err) =
+      service::lock::check_unlocked(current_row, authorization.bypass_governance(&headers))
+    {
+      return err.into_response();
+    }
+  }
+  let allowance = match quota_allowance(&state, authorization.claims.app, &version_row.path).await {
+    Ok(allowance) => allowance,
+    Err(response) => return response,
+  };
+  let (objects, size) = match &current_row {
+    Some(current_row) => (0, current_row.size.max(0) as u64),
+    None => (1, 0),
+  };
+  if let Err(err) = allowance.check(
+    objects,
+    (version_row.size.max(0) as u64).saturating_sub(size),
+  ) {
+    return err.into_response();
+  }
+
+  let object_row = match service::version::restore_version(
+    &state.pool,
+    state.storage.clone(),
+    &state.config,
+    &version_row,
+    authorization.bypass_governance(&headers),
+  )
+  .await
+  {
+    Ok(object_row) => object_row,
+    Err(err) => {
+      if let Some(object_locked) = service::lock::object_locked(&err) {
+        return InternalError::from(object_locked).into_response();
+      }
+      log::error!("Error restoring object version: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+
+  axum::Json(ObjectInstance::from(object_row)).into_response()
+}
+
+#[utoipa::path(
+  delete,
+  path = "/objects/versions/{version_id}",
+  tags = [OBJECT_TAG],
+  responses(
+    (status = 204),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:delete"])
+  )
+)]
+pub async fn purge_object_version(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(version_id): Path<i64>,
+) -> impl IntoResponse {
+  let version_row = match get_object_version(&state, &authorization, version_id).await {
+    Ok(version_row) => version_row,
+    Err(response) => return response,
+  };
+  if let Err(err) = authorization.require(OBJECTS_DELETE_SCOPE, &version_row.path) {
+    return err.into_response();
+  }
+
+  match service::version::purge_version(
+    &state.pool,
+    state.storage.as_ref(),
+    authorization.claims.app,
+    version_row.id,
+  )
+  .await
+  {
+    Ok(Some(_)) => {}
+    Ok(None) => {
+      log::error!("ObjectVersion not found: {}", version_id);
+      return InternalError::not_found()
+        .with_error("version_id", NOT_FOUND_ERROR)
+        .into_response();
+    }
+    Err(err) => {
+      log::error!("Error purging object version: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  }
+  (StatusCode::NO_CONTENT, ()).into_response()
+}
+
+#[utoipa::path(
+  delete,
+  path = "/objects/versions",
+  tags = [OBJECT_TAG],
+  params(
+    ObjectQuery,
+  ),
+  responses(
+    (status = 204),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:delete"])
+  )
+)]
+pub async fn purge_object_versions(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Query(object_query): Query<ObjectQuery>,
+) -> impl IntoResponse {
+  if let Err(err) = authorization.require(OBJECTS_DELETE_SCOPE, &object_query.path) {
+    return err.into_response();
+  }
+
+  if let Err(err) = service::version::purge_versions(
+    &state.pool,
+    state.storage.as_ref(),
+    authorization.claims.app,
+    &object_query.path,
+  )
+  .await
+  {
+    log::error!("Error purging object versions: {}", err);
+    return InternalError::internal_error()
+      .with_application_error(INTERNAL_ERROR)
+      .into_response();
+  }
+  (StatusCode::NO_CONTENT, ()).into_response()
+}
+
+#[utoipa::path(
+  post,
+  path = "/objects/presign",
+  tags = [OBJECT_TAG],
+  request_body = PresignObjectRequest,
+  responses(
+    (status = 201, content_type = "application/json", body = PresignedObjectUrl),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:read", "objects:write"])
+  )
+)]
+pub async fn presign_object(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Json(body): Json<PresignObjectRequest>,
+) -> impl IntoResponse {
+  let key = &state.config.presign.key;
+  if key.is_empty() {
+    log::error!("Presigned urls are disabled, presign.key is not set");
+    return InternalError::bad_request()
+      .with_application_error(NOT_ALLOWED_ERROR)
+      .into_response();
+  }
+  let expires_in = body.expires_in.unwrap_or(DEFAULT_PRESIGN_EXPIRES_IN);
+  if expires_in == 0 || expires_in > state.config.presign.max_expires {
+    return InternalError::bad_request()
+      .with_error("expires_in", INVALID_ERROR)
+      .into_response();
+  }
+  let (scope, endpoint) = match body.method {
+    PresignMethod::Get => {
+      if body.content_type.is_some() || body.max_size.is_some() {
+        return InternalError::bad_request()
+          .with_error("method", INVALID_ERROR)
+          .into_response();
+      }
+      (OBJECTS_READ_SCOPE, "read")
+    }
+    PresignMethod::Put => (OBJECTS_WRITE_SCOPE, "write"),
+  };
+
+  let (uri_path, path) = match (body.object_id, body.path) {
+    (Some(object_id), None) => {
+      let object_row = match repository::object::get_object_by_id(
+        &state.pool,
+        authorization.claims.app,
+        object_id,
+      )
+      .await
+      {
+        Ok(Some(object)) => object,
+        Ok(None) => {
+          log::error!("ObjectInstance not found: {}", object_id);
+          return InternalError::not_found()
+            .with_error("object_id", NOT_FOUND_ERROR)
+            .into_response();
+        }
+        Err(err) => {
+          log::error!("Error getting objects from database: {}", err);
+          return InternalError::internal_error()
+            .with_application_error(INTERNAL_ERROR)
+            .into_response();
+        }
+      };
+      if let Err(err) = authorization.require(scope, &object_row.path) {
+        return err.into_response();
+      }
+      (format!("/objects/{}/{}", object_id, endpoint), None)
+    }
+    (None, Some(path)) => {
+      if let Err(err) = authorization.require(scope, &path) {
+        return err.into_response();
+      }
+      (format!("/objects/by-path/{}", endpoint), Some(path))
+    }
+    _ => {
+      return InternalError::bad_request()
+        .with_error("path", REQUIRED_ERROR)
+        .into_response();
+    }
+  };
+
+  let method = match body.method {
+    PresignMethod::Get => Method::GET,
+    PresignMethod::Put => Method::PUT,
+  };
+  let expires_at = Utc::now() + TimeDelta::seconds(expires_in as i64);
+  let presigned = Presigned {
+    method: method.to_string(),
+    uri_path,
+    path,
+    app: authorization.claims.app,
+    expires: expires_at.timestamp(),
+    content_type: body.content_type,
+    max_size: body.max_size,
+  };
+  (
+    StatusCode::CREATED,
+    axum::Json(PresignedObjectUrl {
+      method: body.method,
+      url: format!(
+        "{}{}?{}",
+        state.config.server.url.trim_end_matches('/'),
+        presigned.uri_path,
+        presigned.query(key)
+      ),
+      expires_at,
+    }),
+  )
+    .into_response()
+}
+
+/// replaces the object's retention, or removes it when `retention` is `None`, if its current
+/// retention allows it
+async fn update_object_retention(
+  state: &RouterState,
+  authorization: &Authorization,
+  object_id: i64,
+  headers: &HeaderMap,
+  retention: Option<(&str, i64)>,
+) -> Response {
+  // held until the new retention is stored so writes and deletes of the object see it
+  let _lock = service::object::lock_object(object_id).await;
+  let object_row =
+    match repository::object::get_object_by_id(&state.pool, authorization.claims.app, object_id)
+      .await
+    {
+      Ok(Some(object)) => object,
+      Ok(None) => {
+        log::error!("ObjectInstance not found: {}", object_id);
+        return InternalError::not_found()
+          .with_error("object_id", NOT_FOUND_ERROR)
+          .into_response();
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    };
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &object_row.path) {
+    return err.into_response();
+  }
+  if let Err(err) = service::lock::check_retention_change(
+    &object_row,
+    retention,
+    authorization.bypass_governance(headers),
+  ) {
+    return err.into_response();
+  }
+
+  match repository::object::update_object_retention(
+    &state.pool,
+    authorization.claims.app,
+    object_id,
+    retention.map(|(retention_mode, _)| retention_mode),
+    retention.map(|(_, retain_until)| retain_until),
+  )
+  .await
+  {
+    Ok(Some(object_row)) => axum::Json(ObjectInstance::from(object_row)).into_response(),
+    Ok(None) => {
+      log::error!("ObjectInstance not found: {}", object_id);
+      InternalError::not_found()
+        .with_error("object_id", NOT_FOUND_ERROR)
+        .into_response()
+    }
+    Err(err) => {
+      log::error!("Error updating object retention: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+async fn get_object_version(
+  state: &RouterState,
+  authorization: &Authorization,
+  version_id: i64,
+) -> Result<ObjectVersionRow, Response> {
+  match repository::version::get_object_version(&state.pool, authorization.claims.app, version_id)
+    .await
+  {
+    Ok(Some(version_row)) => Ok(version_row),
+    Ok(None) => {
+      log::error!("ObjectVersion not found: {}", version_id);
+      Err(
+        InternalError::not_found()
+          .with_error("version_id", NOT_FOUND_ERROR)
+          .into_response(),
+      )
+    }
+    Err(err) => {
+      log::error!("Error getting object version from database: {}", err);
+      Err(
+        InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response(),
+      )
+    }
+  }
+}
+
+/// fails unless the upload exists and the token may write to its path
+async fn authorize_multipart_upload(
+  state: &RouterState,
+  authorization: &Authorization,
+  upload_id: &str,
+) -> Result<MultipartUploadRow, Response> {
+  match repository::multipart::get_multipart_upload(
+    &state.pool,
+    authorization.claims.app,
+    upload_id,
+  )
+  .await
+  {
+    Ok(Some(upload_row)) => authorization
+      .require(OBJECTS_WRITE_SCOPE, &upload_row.path)
+      .map(|_| upload_row)
+      .map_err(IntoResponse::into_response),
+    Ok(None) => Err(multipart_error_response(MultipartError::NoSuchUpload)),
+    Err(err) => Err(multipart_error_response(err.into())),
+  }
+}
+
+/// streams the object honouring `Range` and `If-Range`, multiple ranges are sent as
+/// `multipart/byteranges`
+async fn read_object(state: &RouterState, object_row: ObjectRow, headers: &HeaderMap) -> Response {
+  if let Some(response) = precondition_response(headers, true, &object_row) {
+    return response;
+  }
+  let size = object_row.size.max(0) as u64;
+  let etag = object_row.etag();
+  let last_modified = object_row.last_modified();
+  let content_type = object_row
+    .r#type
+    .clone()
+    .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_owned());
+  let content_disposition = format!("attachment; objectname={:?}", object_row.path);
+
+  let ranges = match requested_ranges(headers, size, &etag, &last_modified) {
+    Some(Ok(ranges)) => ranges,
+    Some(Err(RangeError::Unsatisfiable)) => {
+      return (
+        [
+          (header::ACCEPT_RANGES, BYTES_UNIT.to_owned()),
+          (header::CONTENT_RANGE, unsatisfied_content_range(size)),
+        ],
+        InternalError::from(StatusCode::RANGE_NOT_SATISFIABLE)
+          .with_error(header::RANGE.as_str(), INVALID_ERROR),
+      )
+        .into_response();
+    }
+    Some(Err(RangeError::Invalid)) | None => {
+      let object = match state.storage.open_read(object_row.id).await {
+        Ok(object) => object,
+        Err(err) => {
+          log::error!("Error opening object: {}", err);
+          return InternalError::internal_error()
+            .with_application_error(INTERNAL_ERROR)
+            .into_response();
+        }
+      };
+      return (
+        [
+          (header::CONTENT_TYPE, content_type),
+          (header::CONTENT_DISPOSITION, content_disposition),
+          (header::CONTENT_LENGTH, size.to_string()),
+          (header::ACCEPT_RANGES, BYTES_UNIT.to_owned()),
+          (header::ETAG, etag),
+          (header::LAST_MODIFIED, last_modified),
+        ],
+        Body::from_stream(ReaderStream::new(object)),
+      )
+        .into_response();
+    }
+  };
+
+  if let [range] = ranges.as_slice() {
+    let object = match state
+      .storage
+      .open_read_range(object_row.id, range.clone())
+      .await
+    {
+      Ok(object) => object,
+      Err(err) => {
+        log::error!("Error opening object: {}", err);
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    };
+    return (
+      StatusCode::PARTIAL_CONTENT,
+      [
+        (header::CONTENT_TYPE, content_type),
+        (header::CONTENT_DISPOSITION, content_disposition),
+        (
+          header::CONTENT_LENGTH,
+          (range.end - range.start).to_string(),
+        ),
+        (header::CONTENT_RANGE, content_range(range, size)),
+        (header::ACCEPT_RANGES, BYTES_UNIT.to_owned()),
+        (header::ETAG, etag),
+        (header::LAST_MODIFIED, last_modified),
+      ],
+      Body::from_stream(ReaderStream::new(object)),
+    )
+      .into_response();
+  }
+
+  let boundary = uuid::Uuid::new_v4().simple().to_string();
+  let mut content_length = 0;
+  let parts = ranges
+    .into_iter()
+    .enumerate()
+    .map(|(index, range)| {
+      let part_headers = format!(
+        "{}--{}\r\n{}: {}\r\n{}: {}\r\n\r\n",
+        if index == 0 { "" } else { "\r\n" },
+        boundary,
+        header::CONTENT_TYPE,
+        content_type,
+        header::CONTENT_RANGE,
+        content_range(&range, size)
+      );
+      content_length += part_headers.len() as u64 + (range.end - range.start);
+      (Bytes::from(part_headers), range)
+    })
+    .collect::<Vec<_>>();
+  let trailer = Bytes::from(format!("\r\n--{}--\r\n", boundary));
+  content_length += trailer.len() as u64;
+
+  (
+    StatusCode::PARTIAL_CONTENT,
+    [
+      (
+        header::CONTENT_TYPE,
+        format!("multipart/byteranges; boundary={}", boundary),
+      ),
+      (header::CONTENT_DISPOSITION, content_disposition),
+      (header::CONTENT_LENGTH, content_length.to_string()),
+      (header::ACCEPT_RANGES, BYTES_UNIT.to_owned()),
+      (header::ETAG, etag),
+      (header::LAST_MODIFIED, last_modified),
+    ],
+    Body::from_stream(byteranges_stream(
+      state.storage.clone(),
+      object_row.id,
+      parts,
+      trailer,
+    )),
+  )
+    .into_response()
+}
+
+/// the response to send instead of handling the request when a conditional header fails
+fn precondition_response(
+  headers: &HeaderMap,
+  safe: bool,
+  object_row: &ObjectRow,
+) -> Option<Response> {
+  match evaluate_preconditions(headers, safe, &object_row.etag(), object_row.modified()) {
+    Precondition::Passed => None,
+    Precondition::NotModified => {
+      Some((StatusCode::NOT_MODIFIED, validator_headers(object_row)).into_response())
+    }
+    Precondition::Failed(name) => Some(
+      InternalError::precondition_failed()
+        .with_error(name.as_str(), PRECONDITION_FAILED_ERROR)
+        .into_response(),
+    ),
+  }
+}
+
+fn validator_headers(object_row: &ObjectRow) -> [(HeaderName, String); 2] {
+  [
+    (header::ETAG, object_row.etag()),
+    (header::LAST_MODIFIED, object_row.last_modified()),
+  ]
+}
+
+/// every object of `app` under `prefix` in path order
+async fn get_all_objects_by_prefix(
+  state: &RouterState,
+  app: i64,
+  prefix: &str,
+) -> Result<Vec<ObjectRow>, Response> {
+  let mut object_rows: Vec<ObjectRow> = Vec::new();
+  loop {
+    let start_after = object_rows
+      .last()
+      .map(|object_row| object_row.path.as_str());
+    match repository::object::get_objects_by_prefix(&state.pool, app, prefix, start_after, 1000)
+      .await
+    {
+      Ok(page) if page.is_empty() => return Ok(object_rows),
+      Ok(page) => object_rows.extend(page),
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return Err(
+          InternalError::internal_error()
+            .with_application_error(INTERNAL_ERROR)
+            .into_response(),
+        );
+      }
+    }
+  }
+}
+
+/// the objects of `app` under `prefix` after `after_id` in id order, a page of a folder being
+/// deleted
+async fn get_folder_page(
+  state: &RouterState,
+  app: i64,
+  prefix: &str,
+  after_id: i64,
+  limit: usize,
+) -> Result<Vec<ObjectRow>, Response> {
+  repository::object::get_objects_by_prefix_after_id(&state.pool, app, prefix, after_id, limit)
+    .await
+    .map_err(|err| {
+      log::error!("Error getting objects from database: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    })
+}
+
+/// checks the sources may be read and the new paths written and are free before copying
+async fn copy_objects(
+  state: &RouterState,
+  authorization: &Authorization,
+  copies: Vec<ObjectCopy>,
+) -> Result<Vec<ObjectRow>, Response> {
+  for copy in &copies {
+    if copy.path.is_empty() {
+      log::error!("Copying requires a target path");
+      return Err(
+        InternalError::bad_request()
+          .with_error("path", REQUIRED_ERROR)
+          .into_response(),
+      );
+    }
+    authorization
+      .require(OBJECTS_READ_SCOPE, &copy.source.path)
+      .map_err(IntoResponse::into_response)?;
+    authorization
+      .require(OBJECTS_WRITE_SCOPE, &copy.path)
+      .map_err(IntoResponse::into_response)?;
+    match repository::object::get_object_by_path(&state.pool, authorization.claims.app, &copy.path)
+      .await
+    {
+      Ok(None) => {}
+      Ok(Some(_)) => {
+        log::error!("ObjectInstance already exists: {}", copy.path);
+        return Err(
+          InternalError::from(StatusCode::CONFLICT)
+            .with_error("path", ALREADY_EXISTS_ERROR)
+            .into_response(),
+        );
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return Err(
+          InternalError::internal_error()
+            .with_application_error(INTERNAL_ERROR)
+            .into_response(),
+        );
+      }
+    }
+  }
+  match service::object::copy_objects(&state.pool, state.storage.clone(), &state.config, copies)
+    .await
+  {
+    Ok(object_rows) => Ok(object_rows),
+    Err(err) => {
+      if let Some(quota_exceeded) = service::quota::quota_exceeded(&err) {
+        return Err(InternalError::from(quota_exceeded).into_response());
+      }
+      log::error!("Error copying objects: {}", err);
+      Err(
+        InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response(),
+      )
+    }
+  }
+}
+
+/// what may still be written to `path` before one of the app's quotas is exceeded
+async fn quota_allowance(
+  state: &RouterState,
+  app: i64,
+  path: &str,
+) -> Result<QuotaAllowance, Response> {
+  match service::quota::get_quota_allowance(&state.pool, &state.config, app, path).await {
+    Ok(allowance) => Ok(allowance),
+    Err(err) => {
+      log::error!("Error getting quota usage from database: {}", err);
+      Err(
+        InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response(),
+      )
+    }
+  }
+}
+
+fn request_content_length(headers: &HeaderMap) -> Option<u64> {
+  headers
+    .get(header::CONTENT_LENGTH)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.parse::<u64>().ok())
+}
+
+/// rejects the request up front when the declared `Content-Length` is over the limit
+fn content_length_response(headers: &HeaderMap, max_body_size: u64) -> Option<Response> {
+  let content_length = request_content_length(headers)?;
+  if content_length > max_body_size {
+    log::error!(
+      "Request body of {} bytes is larger than {}",
+      content_length,
+      max_body_size
+    );
+    return Some(
+      InternalError::payload_too_large()
+        .with_error(REQUEST_BODY, TOO_LARGE_ERROR)
+        .into_response(),
+    );
+  }
+  None
+}
+
+/// the request body as a stream that fails and sets `exceeded` once more than `limit` bytes are
+/// received, a missing or wrong `Content-Length` is not trusted
+fn limited_body_stream(
+  body: Body,
+  limit: u64,
+  exceeded: Arc<AtomicBool>,
+) -> impl Stream<Item = io::Result<Bytes>> {
+  let mut received = 0u64;
+  body.into_data_stream().map(move |bytes| {
+    let bytes = bytes.map_err(io::Error::other)?;
+    received += bytes.len() as u64;
+    if received > limit {
+      exceeded.store(true, Ordering::Relaxed);
+      return Err(io::Error::other("request body is too large"));
+    }
+    Ok(bytes)
+  })
+}
+
+/// rejects a write to `path` up front when its declared `Content-Length` would exceed a quota,
+/// replacing an object of `size` bytes or creating one when it is `None`, the write itself is
+/// still limited by the service
+async fn quota_content_length_response(
+  state: &RouterState,
+  app: i64,
+  path: &str,
+  headers: &HeaderMap,
+  size: Option<u64>,
+) -> Option<Response> {
+  let content_length = request_content_length(headers)?;
+  let allowance = match quota_allowance(state, app, path).await {
+    Ok(allowance) => allowance,
+    Err(response) => return Some(response),
+  };
+  allowance
+    .check(
+      size.is_none() as u64,
+      content_length.saturating_sub(size.unwrap_or_default()),
+    )
+    .err()
+    .map(IntoResponse::into_response)
+}
+
+fn content_type(headers: &HeaderMap) -> Option<String> {
+  headers
+    .get(header::CONTENT_TYPE)
+    .and_then(|value| value.to_str().ok())
+    .map(ToOwned::to_owned)
+}
+
+fn write_response(result: sqlx::Result<ObjectRow>, exceeded: &AtomicBool) -> Response {
+  if let Err(err) = &result {
+    if let Some(quota_exceeded) = service::quota::quota_exceeded(err) {
+      return InternalError::from(quota_exceeded).into_response();
+    }
+    if let Some(object_locked) = service::lock::object_locked(err) {
+      return InternalError::from(object_locked).into_response();
+    }
+  }
+  match result {
+    Ok(object_row) => (
+      validator_headers(&object_row),
+      axum::Json(ObjectInstance::from(object_row)),
+    )
+      .into_response(),
+    Err(err) if exceeded.load(Ordering::Relaxed) => {
+      log::error!("Error writing object: {}", err);
+      InternalError::payload_too_large()
+        .with_error(REQUEST_BODY, TOO_LARGE_ERROR)
+        .into_response()
+    }
+    Err(err) => {
+      log::error!("Error writing object: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+fn multipart_error_response(err: MultipartError) -> Response {
+  if let MultipartError::Database(err) = &err {
+    if let Some(quota_exceeded) = service::quota::quota_exceeded(err) {
+      return InternalError::from(quota_exceeded).into_response();
+    }
+    if let Some(object_locked) = service::lock::object_locked(err) {
+      return InternalError::from(object_locked).into_response();
+    }
+  }
+  match err {
+    MultipartError::NoSuchUpload => {
+      log::error!("Multipart upload not found");
+      InternalError::not_found()
+        .with_error("upload_id", NOT_FOUND_ERROR)
+        .into_response()
+    }
+    MultipartError::InvalidPartNumber => {
+      log::error!("{}", err);
+      InternalError::bad_request()
+        .with_error("part_number", INVALID_ERROR)
+        .into_response()
+    }
+    MultipartError::InvalidPart | MultipartError::InvalidPartOrder => {
+      log::error!("{}", err);
+      InternalError::bad_request()
+        .with_error("parts", INVALID_ERROR)
+        .into_response()
+    }
+    MultipartError::Io(_) | MultipartError::Database(_) => {
+      log::error!("Error handling multipart upload: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+/// the ranges to send, `None` when the whole object should be sent because there is no `Range`
+/// header, `If-Range` does not match or too many ranges were requested
+fn requested_ranges(
+  headers: &HeaderMap,
+  size: u64,
+  etag: &str,
+  last_modified: &str,
+) -> Option<Result<Vec<Range<u64>>, RangeError>> {
+  let range = headers.get(header::RANGE)?.to_str().ok()?;
+  if let Some(if_range) = headers.get(header::IF_RANGE) {
+    let if_range = if_range.to_str().ok()?.trim();
+    let matches = if if_range.starts_with('"') {
+      if_range == etag
+    } else if if_range.starts_with("W/") {
+      false
+    } else {
+      match (
+        httpdate::parse_http_date(if_range),
+        httpdate::parse_http_date(last_modified),
+      ) {
+        (Ok(if_range), Ok(last_modified)) => if_range == last_modified,
+        _ => false,
+      }
+    };
+    if !matches {
+      return None;
+    }
+  }
+  match parse_range(range, size) {
+    Ok(ranges) if ranges.len() > MAX_RANGES => None,
+    result => Some(result),
+  }
+}
+
+fn byteranges_stream(
+  storage: Arc<dyn StorageBackend>,
+  object_id: i64,
+  parts: Vec<(Bytes, Range<u64>)>,
+  trailer: Bytes,
+) -> impl Stream<Item = io::Result<Bytes>> {
+  futures_util::stream::unfold(
+    (
+      storage,
+      parts.into_iter(),
+      None::<ReaderStream<ObjectReader>>,
+      Some(trailer),
+    ),
+    move |(storage, mut parts, mut current, mut trailer)| async move {
+      if let Some(object) = current.as_mut() {
+        if let Some(bytes) = object.next().await {
+          return Some((bytes, (storage, parts, current, trailer)));
+        }
+      }
+      match parts.next() {
+        Some((part_headers, range)) => match storage.open_read_range(object_id, range).await {
+          Ok(object) => Some((
+            Ok(part_headers),
+            (storage, parts, Some(ReaderStream::new(object)), trailer),
+          )),
+          Err(err) => Some((Err(err), (storage, Vec::new().into_iter(), None, None))),
+        },
+        None => trailer
+          .take()
+          .map(|trailer| (Ok(trailer), (storage, parts, None, None))),
+      }
+    },
+  )
 }
 
 pub fn create_router(state: RouterState) -> OpenApiRouter {
@@ -512,7 +2724,25 @@ pub fn create_router(state: RouterState) -> OpenApiRouter {
     .routes(routes!(read_object_by_path))
     .routes(routes!(create_object))
     .routes(routes!(append_object))
+    .routes(routes!(write_object_by_path))
+    .routes(routes!(write_object_by_id))
+    .routes(routes!(create_multipart_upload))
+    .routes(routes!(upload_multipart_part))
+    .routes(routes!(get_multipart_parts))
+    .routes(routes!(complete_multipart_upload))
+    .routes(routes!(abort_multipart_upload))
     .routes(routes!(move_object))
+    .routes(routes!(move_folder))
+    .routes(routes!(copy_object))
+    .routes(routes!(copy_object_by_path))
+    .routes(routes!(set_object_retention, delete_object_retention))
+    .routes(routes!(set_object_legal_hold))
     .routes(routes!(delete_object))
+    .routes(routes!(delete_folder))
+    .routes(routes!(get_object_versions, purge_object_versions))
+    .routes(routes!(read_object_version))
+    .routes(routes!(restore_object_version))
+    .routes(routes!(purge_object_version))
+    .routes(routes!(presign_object))
     .with_state(state)
 }
@@ -0,0 +1,68 @@
+use crate::{
+  core::error::{Errors, InternalError, INTERNAL_ERROR},
+  middleware::authorization::{Authorization, OBJECTS_LIST_SCOPE},
+  model::quota::{QuotaUsage, Usage},
+  repository, service,
+};
+
+use axum::{extract::State, response::IntoResponse};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use super::RouterState;
+
+pub const QUOTA_TAG: &str = "quota";
+
+#[utoipa::path(
+  get,
+  path = "/usage",
+  tags = [QUOTA_TAG],
+  responses(
+    (status = 200, content_type = "application/json", body = Usage),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:list"])
+  )
+)]
+pub async fn get_usage(
+  State(state): State<RouterState>,
+  Authorization { claims, .. }: Authorization,
+) -> impl IntoResponse {
+  if let Err(err) = claims.require_scope(OBJECTS_LIST_SCOPE) {
+    return err.into_response();
+  }
+  let usage = match repository::object::get_object_usage(&state.pool, claims.app, "").await {
+    Ok(usage) => usage,
+    Err(err) => {
+      log::error!("Error getting usage from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+  let quota_usages =
+    match service::quota::get_quota_usages(&state.pool, &state.config, claims.app, None).await {
+      Ok(quota_usages) => quota_usages,
+      Err(err) => {
+        log::error!("Error getting quota usage from database: {}", err);
+        return InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response();
+      }
+    };
+
+  axum::Json(Usage {
+    bytes: usage.bytes.max(0) as u64,
+    objects: usage.objects.max(0) as u64,
+    quotas: quota_usages.into_iter().map(QuotaUsage::from).collect(),
+  })
+  .into_response()
+}
+
+pub fn create_router(state: RouterState) -> OpenApiRouter {
+  OpenApiRouter::new()
+    .routes(routes!(get_usage))
+    .with_state(state)
+}
@@ -0,0 +1,1066 @@
+use std::{
+  collections::HashMap,
+  io,
+  pin::Pin,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+};
+
+use axum::{
+  body::{Body, Bytes},
+  extract::{Path, RawQuery, State},
+  http::{header, HeaderMap, HeaderValue, StatusCode},
+  response::{IntoResponse, Response},
+  routing::get,
+  Router,
+};
+use futures_util::{Stream, StreamExt};
+use percent_encoding::percent_decode_str;
+use sha2::{Digest, Sha256};
+use tokio_util::io::ReaderStream;
+
+use crate::{
+  core::{
+    range::{content_range, parse_range, unsatisfied_content_range, RangeError},
+    sigv4,
+  },
+  middleware::s3_authorization::{
+    parse_query, ChunkSigning, S3Authorization, STREAMING_PAYLOAD_PREFIX,
+  },
+  model::s3::{xml_values, S3Error, XmlBuilder},
+  repository::{self, object::ObjectRow},
+  service::{self, multipart::MultipartError},
+};
+
+use super::RouterState;
+
+pub const COPY_SOURCE_HEADER: &str = "x-amz-copy-source";
+pub const METADATA_DIRECTIVE_HEADER: &str = "x-amz-metadata-directive";
+/// the length of an `aws-chunked` payload once its chunk signatures are stripped
+pub const DECODED_CONTENT_LENGTH_HEADER: &str = "x-amz-decoded-content-length";
+
+const DEFAULT_MAX_KEYS: usize = 1000;
+const LIST_BATCH_SIZE: usize = 1000;
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+const MAX_CHUNK_HEADER_SIZE: usize = 4096;
+
+type ByteStream = Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send>>;
+
+pub fn create_router(state: RouterState) -> Router {
+  let path = state.config.s3.path.trim_end_matches('/').to_owned();
+  let mut router = Router::new()
+    .route(&format!("{}/", path), get(list_buckets))
+    .route(
+      &format!("{}/{{bucket}}", path),
+      get(get_bucket)
+        .head(head_bucket)
+        .put(create_bucket)
+        .post(post_bucket)
+        .delete(delete_bucket),
+    )
+    .route(
+      &format!("{}/{{bucket}}/", path),
+      get(get_bucket)
+        .head(head_bucket)
+        .put(create_bucket)
+        .post(post_bucket)
+        .delete(delete_bucket),
+    )
+    .route(
+      &format!("{}/{{bucket}}/{{*key}}", path),
+      get(get_object)
+        .head(head_object)
+        .put(put_object)
+        .post(post_object)
+        .delete(delete_object),
+    );
+  if !path.is_empty() {
+    router = router.route(&path, get(list_buckets));
+  }
+  router.with_state(state)
+}
+
+async fn list_buckets(
+  State(state): State<RouterState>,
+  S3Authorization {
+    access_key_id, app, ..
+  }: S3Authorization,
+) -> Result<Response, S3Error> {
+  let folders =
+    repository::object::get_objects_and_folders(&state.pool, app, None, None, None, None)
+      .await
+      .map_err(database_error)?;
+
+  let mut xml = XmlBuilder::new();
+  xml.open_with_xmlns("ListAllMyBucketsResult");
+  xml.open("Owner");
+  xml.element("ID", &access_key_id);
+  xml.element("DisplayName", &access_key_id);
+  xml.close("Owner");
+  xml.open("Buckets");
+  for folder in folders.into_iter().filter(ObjectRow::is_dir) {
+    xml.open("Bucket");
+    xml.element("Name", &folder.path);
+    xml.date_element("CreationDate", folder.created_at);
+    xml.close("Bucket");
+  }
+  xml.close("Buckets");
+  xml.close("ListAllMyBucketsResult");
+  Ok(xml.into_response())
+}
+
+async fn head_bucket(
+  S3Authorization { .. }: S3Authorization,
+  Path(_bucket): Path<String>,
+) -> impl IntoResponse {
+  StatusCode::OK
+}
+
+/// buckets are the top level folders of object paths so they always exist
+async fn create_bucket(
+  State(state): State<RouterState>,
+  S3Authorization { .. }: S3Authorization,
+  Path(bucket): Path<String>,
+) -> impl IntoResponse {
+  (
+    StatusCode::OK,
+    [(
+      header::LOCATION,
+      format!("{}/{}", state.config.s3.path.trim_end_matches('/'), bucket),
+    )],
+  )
+}
+
+async fn delete_bucket(
+  State(state): State<RouterState>,
+  S3Authorization { app, .. }: S3Authorization,
+  Path(bucket): Path<String>,
+) -> Result<Response, S3Error> {
+  let objects =
+    repository::object::get_objects_by_prefix(&state.pool, app, &format!("{}/", bucket), None, 1)
+      .await
+      .map_err(database_error)?;
+  if !objects.is_empty() {
+    return Err(S3Error::bucket_not_empty());
+  }
+  Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn get_bucket(
+  State(state): State<RouterState>,
+  S3Authorization { app, .. }: S3Authorization,
+  Path(bucket): Path<String>,
+  RawQuery(query): RawQuery,
+) -> Result<Response, S3Error> {
+  let query = query_map(query);
+
+  if query.contains_key("location") {
+    let mut xml = XmlBuilder::new();
+    xml.open_with_xmlns("LocationConstraint");
+    xml.text(&state.config.s3.region);
+    xml.close("LocationConstraint");
+    return Ok(xml.into_response());
+  }
+  if query.contains_key("uploads") || query.contains_key("versions") {
+    return Err(S3Error::not_implemented());
+  }
+
+  let list_v2 = query.get("list-type").map(String::as_str) == Some("2");
+  let prefix = query.get("prefix").cloned().unwrap_or_default();
+  let delimiter = query.get("delimiter").filter(|d| !d.is_empty()).cloned();
+  let max_keys = match query.get("max-keys") {
+    Some(max_keys) => max_keys
+      .parse::<usize>()
+      .map_err(|_| S3Error::invalid_argument("max-keys must be a number"))?
+      .min(DEFAULT_MAX_KEYS),
+    None => DEFAULT_MAX_KEYS,
+  };
+  let url_encode = query.get("encoding-type").map(String::as_str) == Some("url");
+  let start_after = if list_v2 {
+    match query.get("continuation-token") {
+      Some(token) => Some(
+        hex::decode(token)
+          .ok()
+          .and_then(|token| String::from_utf8(token).ok())
+          .ok_or_else(|| S3Error::invalid_argument("the continuation token is not valid"))?,
+      ),
+      None => query.get("start-after").cloned(),
+    }
+  } else {
+    query.get("marker").cloned()
+  };
+
+  let list = list_objects(
+    &state.pool,
+    app,
+    &bucket,
+    &prefix,
+    delimiter.as_deref(),
+    start_after.as_deref(),
+    max_keys,
+  )
+  .await
+  .map_err(database_error)?;
+
+  let encode = |value: &str| {
+    if url_encode {
+      sigv4::uri_encode(value, false)
+    } else {
+      value.to_owned()
+    }
+  };
+  let bucket_prefix_len = bucket.len() + 1;
+
+  let mut xml = XmlBuilder::new();
+  xml.open_with_xmlns("ListBucketResult");
+  xml.element("Name", &bucket);
+  xml.element("Prefix", encode(&prefix));
+  if let Some(delimiter) = &delimiter {
+    xml.element("Delimiter", encode(delimiter));
+  }
+  xml.element("MaxKeys", max_keys);
+  xml.element("IsTruncated", list.is_truncated);
+  if url_encode {
+    xml.element("EncodingType", "url");
+  }
+  if list_v2 {
+    xml.element("KeyCount", list.objects.len() + list.common_prefixes.len());
+    if let Some(token) = query.get("continuation-token") {
+      xml.element("ContinuationToken", token);
+    }
+    if let Some(start_after) = query.get("start-after") {
+      xml.element("StartAfter", encode(start_after));
+    }
+    if let (true, Some(next)) = (list.is_truncated, &list.next) {
+      xml.element("NextContinuationToken", hex::encode(next));
+    }
+  } else {
+    xml.element("Marker", encode(&start_after.unwrap_or_default()));
+    if let (true, Some(next)) = (list.is_truncated, &list.next) {
+      xml.element("NextMarker", encode(next));
+    }
+  }
+  for object_row in &list.objects {
+    xml.open("Contents");
+    xml.element("Key", encode(&object_row.path[bucket_prefix_len..]));
+    xml.date_element("LastModified", object_row.updated_at);
+    xml.element("ETag", object_row.etag());
+    xml.element("Size", object_row.size);
+    xml.element("StorageClass", "STANDARD");
+    xml.close("Contents");
+  }
+  for common_prefix in &list.common_prefixes {
+    xml.open("CommonPrefixes");
+    xml.element("Prefix", encode(common_prefix));
+    xml.close("CommonPrefixes");
+  }
+  xml.close("ListBucketResult");
+  Ok(xml.into_response())
+}
+
+async fn post_bucket(
+  State(state): State<RouterState>,
+  authorization: S3Authorization,
+  Path(bucket): Path<String>,
+  RawQuery(query): RawQuery,
+  body: Bytes,
+) -> Result<Response, S3Error> {
+  let query = query_map(query);
+  if !query.contains_key("delete") {
+    return Err(S3Error::not_implemented());
+  }
+  verify_payload(&authorization, &body)?;
+
+  let body = String::from_utf8_lossy(&body);
+  let quiet = xml_values(&body, "Quiet").first().map(String::as_str) == Some("true");
+  let keys = xml_values(&body, "Object")
+    .into_iter()
+    .filter_map(|object| xml_values(&object, "Key").into_iter().next())
+    .collect::<Vec<_>>();
+  if keys.is_empty() {
+    return Err(S3Error::malformed_xml());
+  }
+
+  let mut xml = XmlBuilder::new();
+  xml.open_with_xmlns("DeleteResult");
+  for key in keys {
+    let path = format!("{}/{}", bucket, key);
+    match delete_object_by_path(&state, authorization.app, &path).await {
+      Ok(_) => {
+        if !quiet {
+          xml.open("Deleted");
+          xml.element("Key", &key);
+          xml.close("Deleted");
+        }
+      }
+      Err(err) => {
+        log::error!("Error deleting object {}: {:?}", path, err);
+        xml.open("Error");
+        xml.element("Key", &key);
+        xml.element("Code", err.code());
+        xml.element("Message", err.message());
+        xml.close("Error");
+      }
+    }
+  }
+  xml.close("DeleteResult");
+  Ok(xml.into_response())
+}
+
+async fn head_object(
+  State(state): State<RouterState>,
+  S3Authorization { app, .. }: S3Authorization,
+  Path((bucket, key)): Path<(String, String)>,
+) -> Result<Response, S3Error> {
+  let object_row = get_object_row(&state, app, &bucket, &key).await?;
+  let mut headers = object_headers(&object_row);
+  headers.insert(header::CONTENT_LENGTH, HeaderValue::from(object_row.size));
+  Ok((StatusCode::OK, headers).into_response())
+}
+
+async fn get_object(
+  State(state): State<RouterState>,
+  S3Authorization { app, .. }: S3Authorization,
+  Path((bucket, key)): Path<(String, String)>,
+  RawQuery(query): RawQuery,
+  request_headers: HeaderMap,
+) -> Result<Response, S3Error> {
+  let query = query_map(query);
+  if let Some(upload_id) = query.get("uploadId") {
+    return list_parts(&state.pool, app, &bucket, &key, upload_id).await;
+  }
+
+  let object_row = get_object_row(&state, app, &bucket, &key).await?;
+  let size = object_row.size as u64;
+  let mut headers = object_headers(&object_row);
+
+  let range = match request_headers
+    .get(header::RANGE)
+    .and_then(|range| range.to_str().ok())
+  {
+    Some(range) => match parse_range(range, size) {
+      Ok(ranges) => ranges.into_iter().next(),
+      Err(RangeError::Invalid) => None,
+      Err(RangeError::Unsatisfiable) => {
+        let mut response = S3Error::invalid_range().into_response();
+        if let Ok(value) = HeaderValue::from_str(&unsatisfied_content_range(size)) {
+          response.headers_mut().insert(header::CONTENT_RANGE, value);
+        }
+        return Ok(response);
+      }
+    },
+    None => None,
+  };
+
+  let (status, reader) = match range {
+    Some(range) => {
+      if let Ok(value) = HeaderValue::from_str(&content_range(&range, size)) {
+        headers.insert(header::CONTENT_RANGE, value);
+      }
+      headers.insert(
+        header::CONTENT_LENGTH,
+        HeaderValue::from(range.end - range.start),
+      );
+      (
+        StatusCode::PARTIAL_CONTENT,
+        state.storage.open_read_range(object_row.id, range).await,
+      )
+    }
+    None => {
+      headers.insert(header::CONTENT_LENGTH, HeaderValue::from(size));
+      (StatusCode::OK, state.storage.open_read(object_row.id).await)
+    }
+  };
+  let reader = reader.map_err(|err| {
+    log::error!("Error opening object: {}", err);
+    S3Error::internal_error()
+  })?;
+
+  Ok(
+    (
+      status,
+      headers,
+      Body::from_stream(ReaderStream::new(reader)),
+    )
+      .into_response(),
+  )
+}
+
+async fn put_object(
+  State(state): State<RouterState>,
+  authorization: S3Authorization,
+  Path((bucket, key)): Path<(String, String)>,
+  RawQuery(query): RawQuery,
+  request_headers: HeaderMap,
+  body: Body,
+) -> Result<Response, S3Error> {
+  let query = query_map(query);
+  let path = format!("{}/{}", bucket, key);
+
+  if let (Some(upload_id), Some(part_number)) = (query.get("uploadId"), query.get("partNumber")) {
+    if request_headers.contains_key(COPY_SOURCE_HEADER) {
+      return Err(S3Error::not_implemented());
+    }
+    let part_number = part_number
+      .parse::<u32>()
+      .map_err(|_| S3Error::invalid_argument("Part number must be between 1 and 10000"))?;
+    let exceeded = Arc::new(AtomicBool::new(false));
+    let stream = limited_payload_stream(
+      &authorization,
+      &request_headers,
+      body,
+      state.config.max_body_size,
+      exceeded.clone(),
+    )?;
+    let part = service::multipart::upload_part(
+      &state.pool,
+      state.storage.as_ref(),
+      authorization.app,
+      upload_id,
+      part_number,
+      stream,
+    )
+    .await
+    .map_err(|err| match err {
+      _ if exceeded.load(Ordering::Relaxed) => S3Error::entity_too_large(),
+      err => multipart_error(err),
+    })?;
+    return Ok((StatusCode::OK, [(header::ETAG, part.etag)]).into_response());
+  }
+
+  let kind = request_headers
+    .get(header::CONTENT_TYPE)
+    .and_then(|value| value.to_str().ok())
+    .map(str::to_owned);
+  check_path_unlocked(&state, authorization.app, &path).await?;
+
+  if let Some(copy_source) = request_headers
+    .get(COPY_SOURCE_HEADER)
+    .and_then(|value| value.to_str().ok())
+  {
+    let copy_source = percent_decode_str(copy_source.split('?').next().unwrap_or_default())
+      .decode_utf8_lossy()
+      .trim_start_matches('/')
+      .to_owned();
+    let source =
+      match repository::object::get_object_by_path(&state.pool, authorization.app, &copy_source)
+        .await
+      {
+        Ok(Some(source)) => source,
+        Ok(None) => return Err(S3Error::no_such_key()),
+        Err(err) => return Err(database_error(err)),
+      };
+    let replace_metadata = request_headers
+      .get(METADATA_DIRECTIVE_HEADER)
+      .and_then(|value| value.to_str().ok())
+      == Some("REPLACE");
+    let object_row = service::object::copy_object(
+      &state.pool,
+      state.storage.clone(),
+      &state.config,
+      &source,
+      path,
+      kind.filter(|_| replace_metadata),
+      false,
+    )
+    .await
+    .map_err(database_error)?;
+
+    let mut xml = XmlBuilder::new();
+    xml.open_with_xmlns("CopyObjectResult");
+    xml.date_element("LastModified", object_row.updated_at);
+    xml.element("ETag", object_row.etag());
+    xml.close("CopyObjectResult");
+    return Ok(xml.into_response());
+  }
+
+  let exceeded = Arc::new(AtomicBool::new(false));
+  let stream = limited_payload_stream(
+    &authorization,
+    &request_headers,
+    body,
+    state.config.max_body_size,
+    exceeded.clone(),
+  )?;
+  let object_row = service::object::put_object(
+    &state.pool,
+    state.storage.clone(),
+    &state.config,
+    authorization.app,
+    path,
+    kind,
+    false,
+    stream,
+  )
+  .await
+  .map_err(|err| match err {
+    _ if exceeded.load(Ordering::Relaxed) => S3Error::entity_too_large(),
+    err => database_error(err),
+  })?;
+  Ok((StatusCode::OK, [(header::ETAG, object_row.etag())]).into_response())
+}
+
+async fn post_object(
+  State(state): State<RouterState>,
+  authorization: S3Authorization,
+  Path((bucket, key)): Path<(String, String)>,
+  RawQuery(query): RawQuery,
+  request_headers: HeaderMap,
+  body: Bytes,
+) -> Result<Response, S3Error> {
+  let query = query_map(query);
+
+  if query.contains_key("uploads") {
+    let kind = request_headers
+      .get(header::CONTENT_TYPE)
+      .and_then(|value| value.to_str().ok())
+      .map(str::to_owned);
+    let upload = service::multipart::create_upload(
+      &state.pool,
+      authorization.app,
+      format!("{}/{}", bucket, key),
+      kind,
+    )
+    .await
+    .map_err(database_error)?;
+
+    let mut xml = XmlBuilder::new();
+    xml.open_with_xmlns("InitiateMultipartUploadResult");
+    xml.element("Bucket", &bucket);
+    xml.element("Key", &key);
+    xml.element("UploadId", &upload.id);
+    xml.close("InitiateMultipartUploadResult");
+    return Ok(xml.into_response());
+  }
+
+  let upload_id = match query.get("uploadId") {
+    Some(upload_id) => upload_id,
+    None => return Err(S3Error::not_implemented()),
+  };
+  verify_payload(&authorization, &body)?;
+  let body = String::from_utf8_lossy(&body);
+  let mut parts = Vec::new();
+  for part in xml_values(&body, "Part") {
+    let part_number = xml_values(&part, "PartNumber")
+      .into_iter()
+      .next()
+      .and_then(|part_number| part_number.parse::<u32>().ok());
+    let etag = xml_values(&part, "ETag").into_iter().next();
+    match (part_number, etag) {
+      (Some(part_number), Some(etag)) => parts.push((part_number, etag)),
+      _ => return Err(S3Error::malformed_xml()),
+    }
+  }
+
+  check_path_unlocked(&state, authorization.app, &format!("{}/{}", bucket, key)).await?;
+  let object_row = service::multipart::complete_upload(
+    &state.pool,
+    state.storage.clone(),
+    &state.config,
+    authorization.app,
+    upload_id,
+    Some(&parts),
+    false,
+  )
+  .await
+  .map_err(multipart_error)?;
+
+  let mut xml = XmlBuilder::new();
+  xml.open_with_xmlns("CompleteMultipartUploadResult");
+  xml.element(
+    "Location",
+    format!(
+      "{}/{}/{}",
+      state.config.s3.path.trim_end_matches('/'),
+      bucket,
+      key
+    ),
+  );
+  xml.element("Bucket", &bucket);
+  xml.element("Key", &key);
+  xml.element("ETag", object_row.etag());
+  xml.close("CompleteMultipartUploadResult");
+  Ok(xml.into_response())
+}
+
+async fn delete_object(
+  State(state): State<RouterState>,
+  S3Authorization { app, .. }: S3Authorization,
+  Path((bucket, key)): Path<(String, String)>,
+  RawQuery(query): RawQuery,
+) -> Result<Response, S3Error> {
+  let query = query_map(query);
+  if let Some(upload_id) = query.get("uploadId") {
+    service::multipart::abort_upload(&state.pool, state.storage.as_ref(), app, upload_id)
+      .await
+      .map_err(multipart_error)?;
+    return Ok(StatusCode::NO_CONTENT.into_response());
+  }
+
+  delete_object_by_path(&state, app, &format!("{}/{}", bucket, key)).await?;
+  Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn list_parts(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  bucket: &str,
+  key: &str,
+  upload_id: &str,
+) -> Result<Response, S3Error> {
+  repository::multipart::get_multipart_upload(pool, app, upload_id)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(S3Error::no_such_upload)?;
+  let parts = repository::multipart::get_multipart_parts(pool, upload_id)
+    .await
+    .map_err(database_error)?;
+
+  let mut xml = XmlBuilder::new();
+  xml.open_with_xmlns("ListPartsResult");
+  xml.element("Bucket", bucket);
+  xml.element("Key", key);
+  xml.element("UploadId", upload_id);
+  xml.element("MaxParts", service::multipart::MAX_PART_NUMBER);
+  xml.element("IsTruncated", false);
+  for part in &parts {
+    xml.open("Part");
+    xml.element("PartNumber", part.part_number);
+    xml.date_element("LastModified", part.updated_at);
+    xml.element("ETag", &part.etag);
+    xml.element("Size", part.size);
+    xml.close("Part");
+  }
+  xml.close("ListPartsResult");
+  Ok(xml.into_response())
+}
+
+struct ObjectList {
+  objects: Vec<ObjectRow>,
+  common_prefixes: Vec<String>,
+  is_truncated: bool,
+  next: Option<String>,
+}
+
+/// lists the keys of `bucket` in path order grouping keys that share a `delimiter` after `prefix`
+async fn list_objects(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  bucket: &str,
+  prefix: &str,
+  delimiter: Option<&str>,
+  start_after: Option<&str>,
+  max_keys: usize,
+) -> sqlx::Result<ObjectList> {
+  let bucket_prefix = format!("{}/", bucket);
+  let path_prefix = format!("{}{}", bucket_prefix, prefix);
+  let mut after = start_after.map(|start_after| format!("{}{}", bucket_prefix, start_after));
+  // resuming after a common prefix skips the rest of the keys it grouped
+  let mut skip_prefix = match (start_after, delimiter) {
+    (Some(start_after), Some(delimiter)) if start_after.ends_with(delimiter) => {
+      Some(start_after.to_owned())
+    }
+    _ => None,
+  };
+
+  let mut list = ObjectList {
+    objects: Vec::new(),
+    common_prefixes: Vec::new(),
+    is_truncated: false,
+    next: None,
+  };
+  loop {
+    let object_rows = repository::object::get_objects_by_prefix(
+      pool,
+      app,
+      &path_prefix,
+      after.as_deref(),
+      LIST_BATCH_SIZE,
+    )
+    .await?;
+    let exhausted = object_rows.len() < LIST_BATCH_SIZE;
+    for object_row in object_rows {
+      after = Some(object_row.path.clone());
+      let key = &object_row.path[bucket_prefix.len()..];
+      if let Some(skip_prefix) = &skip_prefix {
+        if key.starts_with(skip_prefix.as_str()) {
+          continue;
+        }
+      }
+      if list.objects.len() + list.common_prefixes.len() >= max_keys {
+        list.is_truncated = true;
+        return Ok(list);
+      }
+      let common_prefix = delimiter.and_then(|delimiter| {
+        key[prefix.len()..]
+          .find(delimiter)
+          .map(|index| key[..(prefix.len() + index + delimiter.len())].to_owned())
+      });
+      match common_prefix {
+        Some(common_prefix) => {
+          list.next = Some(common_prefix.clone());
+          skip_prefix = Some(common_prefix.clone());
+          list.common_prefixes.push(common_prefix);
+        }
+        None => {
+          list.next = Some(key.to_owned());
+          list.objects.push(object_row);
+        }
+      }
+    }
+    if exhausted {
+      return Ok(list);
+    }
+  }
+}
+
+async fn get_object_row(
+  state: &RouterState,
+  app: i64,
+  bucket: &str,
+  key: &str,
+) -> Result<ObjectRow, S3Error> {
+  match repository::object::get_object_by_path(&state.pool, app, &format!("{}/{}", bucket, key))
+    .await
+  {
+    Ok(Some(object_row)) => Ok(object_row),
+    Ok(None) => Err(S3Error::no_such_key()),
+    Err(err) => Err(database_error(err)),
+  }
+}
+
+async fn delete_object_by_path(state: &RouterState, app: i64, path: &str) -> Result<(), S3Error> {
+  if let Some(object_row) = repository::object::get_object_by_path(&state.pool, app, path)
+    .await
+    .map_err(database_error)?
+  {
+    if service::lock::check_unlocked(&object_row, false).is_err() {
+      return Err(S3Error::access_denied());
+    }
+    service::object::delete_object(
+      &state.pool,
+      state.storage.clone(),
+      &state.config,
+      object_row.id,
+      false,
+    )
+    .await
+    .map_err(database_error)?;
+  }
+  Ok(())
+}
+
+/// fails with `AccessDenied` when the object at `path` exists and is locked, governance retention
+/// can not be bypassed through S3
+async fn check_path_unlocked(state: &RouterState, app: i64, path: &str) -> Result<(), S3Error> {
+  match repository::object::get_object_by_path(&state.pool, app, path).await {
+    Ok(Some(object_row)) => {
+      service::lock::check_unlocked(&object_row, false).map_err(|_| S3Error::access_denied())
+    }
+    Ok(None) => Ok(()),
+    Err(err) => Err(database_error(err)),
+  }
+}
+
+fn object_headers(object_row: &ObjectRow) -> HeaderMap {
+  let mut headers = HeaderMap::new();
+  let content_type = object_row
+    .r#type
+    .as_deref()
+    .unwrap_or("application/octet-stream");
+  if let Ok(value) = HeaderValue::from_str(content_type) {
+    headers.insert(header::CONTENT_TYPE, value);
+  }
+  if let Ok(value) = HeaderValue::from_str(&object_row.etag()) {
+    headers.insert(header::ETAG, value);
+  }
+  if let Ok(value) = HeaderValue::from_str(&object_row.last_modified()) {
+    headers.insert(header::LAST_MODIFIED, value);
+  }
+  headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+  headers
+}
+
+fn query_map(query: Option<String>) -> HashMap<String, String> {
+  parse_query(&query.unwrap_or_default())
+    .into_iter()
+    .collect()
+}
+
+fn verify_payload(authorization: &S3Authorization, body: &[u8]) -> Result<(), S3Error> {
+  if is_sha256_hex(&authorization.payload_hash)
+    && !authorization
+      .payload_hash
+      .eq_ignore_ascii_case(&sigv4::sha256_hex(body))
+  {
+    return Err(S3Error::bad_digest());
+  }
+  Ok(())
+}
+
+/// decodes and verifies the request body according to `x-amz-content-sha256`
+fn payload_stream(authorization: &S3Authorization, body: Body) -> Result<ByteStream, S3Error> {
+  let stream = body
+    .into_data_stream()
+    .map(|bytes| bytes.map_err(io::Error::other));
+  let payload_hash = authorization.payload_hash.as_str();
+  if payload_hash.starts_with(STREAMING_PAYLOAD_PREFIX) {
+    Ok(Box::pin(decode_chunked_payload(
+      stream,
+      authorization.chunk_signing.clone(),
+    )))
+  } else if is_sha256_hex(payload_hash) {
+    Ok(Box::pin(verify_sha256_payload(
+      stream,
+      payload_hash.to_lowercase(),
+    )))
+  } else if payload_hash == sigv4::UNSIGNED_PAYLOAD {
+    Ok(Box::pin(stream))
+  } else {
+    Err(S3Error::invalid_argument(
+      "x-amz-content-sha256 must be UNSIGNED-PAYLOAD, STREAMING-* or a SHA-256 hash",
+    ))
+  }
+}
+
+/// the payload of an upload limited to `max_body_size` bytes, rejected up front when its declared
+/// length is over and failing with `exceeded` set once the body outgrows it
+fn limited_payload_stream(
+  authorization: &S3Authorization,
+  headers: &HeaderMap,
+  body: Body,
+  max_body_size: u64,
+  exceeded: Arc<AtomicBool>,
+) -> Result<ByteStream, S3Error> {
+  let declared_length = if authorization
+    .payload_hash
+    .starts_with(STREAMING_PAYLOAD_PREFIX)
+  {
+    headers.get(DECODED_CONTENT_LENGTH_HEADER)
+  } else {
+    headers.get(header::CONTENT_LENGTH)
+  }
+  .and_then(|value| value.to_str().ok())
+  .and_then(|value| value.parse::<u64>().ok());
+  if declared_length.is_some_and(|declared_length| declared_length > max_body_size) {
+    log::error!("S3 upload is larger than {}", max_body_size);
+    return Err(S3Error::entity_too_large());
+  }
+  let mut received = 0u64;
+  Ok(Box::pin(payload_stream(authorization, body)?.map(
+    move |bytes| {
+      let bytes = bytes?;
+      received += bytes.len() as u64;
+      if received > max_body_size {
+        exceeded.store(true, Ordering::Relaxed);
+        return Err(io::Error::other("request body is too large"));
+      }
+      Ok(bytes)
+    },
+  )))
+}
+
+fn is_sha256_hex(value: &str) -> bool {
+  value.len() == 64 && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+fn verify_sha256_payload<S>(stream: S, expected: String) -> impl Stream<Item = io::Result<Bytes>>
+where
+  S: Stream<Item = io::Result<Bytes>> + Send + Unpin,
+{
+  futures_util::stream::unfold(
+    (stream, Sha256::new(), expected, false),
+    |(mut stream, mut hasher, expected, done)| async move {
+      if done {
+        return None;
+      }
+      match stream.next().await {
+        Some(Ok(bytes)) => {
+          hasher.update(&bytes);
+          Some((Ok(bytes), (stream, hasher, expected, false)))
+        }
+        Some(Err(err)) => Some((Err(err), (stream, hasher, expected, true))),
+        None => {
+          if hex::encode(hasher.finalize_reset()) == expected {
+            None
+          } else {
+            Some((
+              Err(payload_error(
+                "x-amz-content-sha256 does not match the payload",
+              )),
+              (stream, hasher, expected, true),
+            ))
+          }
+        }
+      }
+    },
+  )
+}
+
+struct ChunkedPayload<S> {
+  stream: S,
+  buffer: Vec<u8>,
+  signing: Option<ChunkSigning>,
+  previous_signature: String,
+  done: bool,
+}
+
+impl<S> ChunkedPayload<S> {
+  /// takes the next complete chunk out of the buffer, an empty chunk marks the end of the payload
+  fn next_chunk(&mut self) -> io::Result<Option<Bytes>> {
+    let header_end = match self.buffer.windows(2).position(|window| window == b"\r\n") {
+      Some(header_end) => header_end,
+      None if self.buffer.len() > MAX_CHUNK_HEADER_SIZE => {
+        return Err(payload_error("aws-chunked chunk header is too large"))
+      }
+      None => return Ok(None),
+    };
+    let chunk_header = std::str::from_utf8(&self.buffer[..header_end])
+      .map_err(|_| payload_error("aws-chunked chunk header is not valid"))?;
+    let (size, signature) = match chunk_header.split_once(';') {
+      Some((size, extension)) => (
+        size,
+        extension
+          .trim()
+          .strip_prefix("chunk-signature=")
+          .map(str::to_owned),
+      ),
+      None => (chunk_header, None),
+    };
+    let size = usize::from_str_radix(size.trim(), 16)
+      .map_err(|_| payload_error("aws-chunked chunk size is not valid"))?;
+    if size > MAX_CHUNK_SIZE {
+      return Err(payload_error("aws-chunked chunk is too large"));
+    }
+
+    let data_start = header_end + 2;
+    if size == 0 {
+      self.verify_chunk(signature.as_deref(), &[])?;
+      self.buffer.clear();
+      return Ok(Some(Bytes::new()));
+    }
+    if self.buffer.len() < data_start + size + 2 {
+      return Ok(None);
+    }
+    if &self.buffer[(data_start + size)..(data_start + size + 2)] != b"\r\n" {
+      return Err(payload_error("aws-chunked chunk is not terminated"));
+    }
+    let chunk = self.buffer[data_start..(data_start + size)].to_vec();
+    self.verify_chunk(signature.as_deref(), &chunk)?;
+    self.buffer.drain(..(data_start + size + 2));
+    Ok(Some(Bytes::from(chunk)))
+  }
+
+  fn verify_chunk(&mut self, signature: Option<&str>, chunk: &[u8]) -> io::Result<()> {
+    let signing = match &self.signing {
+      Some(signing) => signing,
+      None => return Ok(()),
+    };
+    let signature =
+      signature.ok_or_else(|| payload_error("aws-chunked chunk signature is missing"))?;
+    let string_to_sign = sigv4::chunk_string_to_sign(
+      &signing.date,
+      &signing.scope,
+      &self.previous_signature,
+      chunk,
+    );
+    let expected = hex::encode(sigv4::hmac_sha256(
+      &signing.signing_key,
+      string_to_sign.as_bytes(),
+    ));
+    if !sigv4::signatures_match(&expected, signature) {
+      return Err(payload_error("aws-chunked chunk signature does not match"));
+    }
+    self.previous_signature = expected;
+    Ok(())
+  }
+}
+
+fn decode_chunked_payload<S>(
+  stream: S,
+  signing: Option<ChunkSigning>,
+) -> impl Stream<Item = io::Result<Bytes>>
+where
+  S: Stream<Item = io::Result<Bytes>> + Send + Unpin,
+{
+  let previous_signature = signing
+    .as_ref()
+    .map(|signing| signing.seed_signature.clone())
+    .unwrap_or_default();
+  let payload = ChunkedPayload {
+    stream,
+    buffer: Vec::new(),
+    signing,
+    previous_signature,
+    done: false,
+  };
+  futures_util::stream::unfold(payload, |mut payload| async move {
+    loop {
+      if payload.done {
+        return None;
+      }
+      match payload.next_chunk() {
+        Ok(Some(chunk)) if chunk.is_empty() => {
+          payload.done = true;
+        }
+        Ok(Some(chunk)) => return Some((Ok(chunk), payload)),
+        Ok(None) => match payload.stream.next().await {
+          Some(Ok(bytes)) => payload.buffer.extend_from_slice(&bytes),
+          Some(Err(err)) => {
+            payload.done = true;
+            return Some((Err(err), payload));
+          }
+          None => {
+            payload.done = true;
+            return Some((
+              Err(payload_error("aws-chunked payload ended unexpectedly")),
+              payload,
+            ));
+          }
+        },
+        Err(err) => {
+          payload.done = true;
+          return Some((Err(err), payload));
+        }
+      }
+    }
+  })
+}
+
+fn payload_error(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn database_error(err: sqlx::Error) -> S3Error {
+  if let Some(quota_exceeded) = service::quota::quota_exceeded(&err) {
+    return S3Error::quota_exceeded(quota_exceeded.to_string());
+  }
+  if service::lock::object_locked(&err).is_some() {
+    return S3Error::access_denied();
+  }
+  match err {
+    sqlx::Error::Io(err) if err.kind() == io::ErrorKind::InvalidData => {
+      log::error!("Invalid S3 payload: {}", err);
+      S3Error::bad_digest()
+    }
+    err => {
+      log::error!("Error handling S3 request: {}", err);
+      S3Error::internal_error()
+    }
+  }
+}
+
+fn multipart_error(err: MultipartError) -> S3Error {
+  match err {
+    MultipartError::NoSuchUpload => S3Error::no_such_upload(),
+    MultipartError::InvalidPartNumber => {
+      S3Error::invalid_argument("Part number must be between 1 and 10000")
+    }
+    MultipartError::InvalidPart => S3Error::invalid_part(),
+    MultipartError::InvalidPartOrder => S3Error::invalid_part_order(),
+    MultipartError::Io(err) => database_error(sqlx::Error::Io(err)),
+    MultipartError::Database(err) => database_error(err),
+  }
+}
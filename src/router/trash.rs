@@ -0,0 +1,251 @@
+use crate::{
+  core::error::{Errors, InternalError, ALREADY_EXISTS_ERROR, INTERNAL_ERROR, NOT_FOUND_ERROR},
+  middleware::authorization::{Authorization, OBJECTS_DELETE_SCOPE, OBJECTS_WRITE_SCOPE},
+  model::{
+    object::{ObjectInstance, ObjectsQuery},
+    trash::{TrashedObject, TrashedObjectPagination},
+    util::{OffsetAndLimit, Pagination},
+  },
+  repository::{self, trash::TrashedObjectRow},
+  service,
+};
+
+use axum::{
+  extract::{Path, Query, State},
+  http::StatusCode,
+  response::{IntoResponse, Response},
+};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use super::RouterState;
+
+pub const TRASH_TAG: &str = "trash";
+
+#[utoipa::path(
+  get,
+  path = "/trash",
+  tags = [TRASH_TAG],
+  params(
+    OffsetAndLimit,
+    ObjectsQuery,
+  ),
+  responses(
+    (status = 200, content_type = "application/json", body = TrashedObjectPagination),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:list"])
+  )
+)]
+pub async fn get_trashed_objects(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Query(offset_and_limit_query): Query<OffsetAndLimit>,
+  Query(objects_query): Query<ObjectsQuery>,
+) -> impl IntoResponse {
+  let prefixes = authorization.list_prefixes();
+  let trashed_rows = match repository::trash::get_trashed_objects(
+    &state.pool,
+    authorization.claims.app,
+    objects_query.path.as_deref().unwrap_or_default(),
+    prefixes.as_deref(),
+    offset_and_limit_query.limit,
+    offset_and_limit_query.offset,
+  )
+  .await
+  {
+    Ok(trashed_rows) => trashed_rows,
+    Err(err) => {
+      log::error!("Error getting trashed objects from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+
+  axum::Json(Pagination {
+    has_more: trashed_rows.len() == offset_and_limit_query.limit.unwrap_or(usize::MAX),
+    items: trashed_rows
+      .into_iter()
+      .map(|trashed_row| TrashedObject::new(trashed_row, state.config.trash.retention))
+      .collect(),
+  })
+  .into_response()
+}
+
+#[utoipa::path(
+  post,
+  path = "/trash/{object_id}/restore",
+  tags = [TRASH_TAG],
+  responses(
+    (status = 200, content_type = "application/json", body = ObjectInstance),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 409, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn restore_trashed_object(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+) -> impl IntoResponse {
+  let trashed_row = match get_trashed_object(&state, &authorization, object_id).await {
+    Ok(trashed_row) => trashed_row,
+    Err(response) => return response,
+  };
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &trashed_row.path) {
+    return err.into_response();
+  }
+  match repository::object::get_object_by_path(
+    &state.pool,
+    authorization.claims.app,
+    &trashed_row.path,
+  )
+  .await
+  {
+    Ok(None) => {}
+    Ok(Some(_)) => {
+      log::error!("ObjectInstance already exists: {}", trashed_row.path);
+      return InternalError::from(StatusCode::CONFLICT)
+        .with_error("path", ALREADY_EXISTS_ERROR)
+        .into_response();
+    }
+    Err(err) => {
+      log::error!("Error getting objects from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  }
+  let allowance = match service::quota::get_quota_allowance(
+    &state.pool,
+    &state.config,
+    authorization.claims.app,
+    &trashed_row.path,
+  )
+  .await
+  {
+    Ok(allowance) => allowance,
+    Err(err) => {
+      log::error!("Error getting quota usage from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+  if let Err(err) = allowance.check(1, trashed_row.size.max(0) as u64) {
+    return err.into_response();
+  }
+
+  match service::trash::restore_object(&state.pool, authorization.claims.app, object_id).await {
+    Ok(Some(object_row)) => axum::Json(ObjectInstance::from(object_row)).into_response(),
+    Ok(None) => {
+      log::error!("TrashedObject not found: {}", object_id);
+      InternalError::not_found()
+        .with_error("object_id", NOT_FOUND_ERROR)
+        .into_response()
+    }
+    Err(err) => {
+      log::error!("Error restoring trashed object: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+#[utoipa::path(
+  delete,
+  path = "/trash/{object_id}",
+  tags = [TRASH_TAG],
+  responses(
+    (status = 204),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:delete"])
+  )
+)]
+pub async fn purge_trashed_object(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+) -> impl IntoResponse {
+  let trashed_row = match get_trashed_object(&state, &authorization, object_id).await {
+    Ok(trashed_row) => trashed_row,
+    Err(response) => return response,
+  };
+  if let Err(err) = authorization.require(OBJECTS_DELETE_SCOPE, &trashed_row.path) {
+    return err.into_response();
+  }
+
+  match service::trash::purge_object(
+    &state.pool,
+    state.storage.as_ref(),
+    authorization.claims.app,
+    object_id,
+  )
+  .await
+  {
+    Ok(Some(_)) => {}
+    Ok(None) => {
+      log::error!("TrashedObject not found: {}", object_id);
+      return InternalError::not_found()
+        .with_error("object_id", NOT_FOUND_ERROR)
+        .into_response();
+    }
+    Err(err) => {
+      log::error!("Error purging trashed object: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  }
+  (StatusCode::NO_CONTENT, ()).into_response()
+}
+
+async fn get_trashed_object(
+  state: &RouterState,
+  authorization: &Authorization,
+  object_id: i64,
+) -> Result<TrashedObjectRow, Response> {
+  match repository::trash::get_trashed_object(&state.pool, authorization.claims.app, object_id)
+    .await
+  {
+    Ok(Some(trashed_row)) => Ok(trashed_row),
+    Ok(None) => {
+      log::error!("TrashedObject not found: {}", object_id);
+      Err(
+        InternalError::not_found()
+          .with_error("object_id", NOT_FOUND_ERROR)
+          .into_response(),
+      )
+    }
+    Err(err) => {
+      log::error!("Error getting trashed object from database: {}", err);
+      Err(
+        InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response(),
+      )
+    }
+  }
+}
+
+pub fn create_router(state: RouterState) -> OpenApiRouter {
+  OpenApiRouter::new()
+    .routes(routes!(get_trashed_objects))
+    .routes(routes!(restore_trashed_object))
+    .routes(routes!(purge_trashed_object))
+    .with_state(state)
+}
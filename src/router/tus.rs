@@ -0,0 +1,549 @@
+use std::{collections::HashMap, io};
+
+use axum::{
+  body::Body,
+  extract::{Path, State},
+  http::{header, HeaderMap, HeaderValue, StatusCode},
+  middleware,
+  response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures_util::StreamExt;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+  core::error::{
+    Errors, InternalError, ALREADY_EXISTS_ERROR, INTERNAL_ERROR, INVALID_ERROR, NOT_FOUND_ERROR,
+    REQUIRED_ERROR,
+  },
+  middleware::authorization::{Authorization, OBJECTS_DELETE_SCOPE, OBJECTS_WRITE_SCOPE},
+  repository,
+  service::{
+    self,
+    tus::{TusError, UploadChecksum, CHECKSUM_ALGORITHMS},
+  },
+};
+
+use super::RouterState;
+
+pub const TUS_TAG: &str = "tus";
+
+pub const TUS_VERSION: &str = "1.0.0";
+pub const TUS_EXTENSIONS: &str = "creation,termination,checksum";
+
+pub const TUS_RESUMABLE_HEADER: &str = "tus-resumable";
+pub const TUS_VERSION_HEADER: &str = "tus-version";
+pub const TUS_EXTENSION_HEADER: &str = "tus-extension";
+pub const TUS_CHECKSUM_ALGORITHM_HEADER: &str = "tus-checksum-algorithm";
+pub const UPLOAD_OFFSET_HEADER: &str = "upload-offset";
+pub const UPLOAD_LENGTH_HEADER: &str = "upload-length";
+pub const UPLOAD_METADATA_HEADER: &str = "upload-metadata";
+pub const UPLOAD_CHECKSUM_HEADER: &str = "upload-checksum";
+
+const OFFSET_OCTET_STREAM: &str = "application/offset+octet-stream";
+const CHECKSUM_MISMATCH_STATUS: u16 = 460;
+
+#[utoipa::path(
+  options,
+  path = "/tus",
+  tags = [TUS_TAG],
+  responses(
+    (status = 204),
+  )
+)]
+pub async fn tus_options() -> impl IntoResponse {
+  (
+    StatusCode::NO_CONTENT,
+    [
+      (TUS_VERSION_HEADER, TUS_VERSION.to_owned()),
+      (TUS_EXTENSION_HEADER, TUS_EXTENSIONS.to_owned()),
+      (TUS_CHECKSUM_ALGORITHM_HEADER, CHECKSUM_ALGORITHMS.join(",")),
+    ],
+  )
+}
+
+#[utoipa::path(
+  post,
+  path = "/tus",
+  tags = [TUS_TAG],
+  responses(
+    (status = 201),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 409, content_type = "application/json", body = Errors),
+    (status = 412),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn create_tus_upload(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  headers: HeaderMap,
+) -> impl IntoResponse {
+  if let Some(response) = unsupported_version(&headers) {
+    return response;
+  }
+  let length = match header_str(&headers, UPLOAD_LENGTH_HEADER) {
+    Some(length) => match length.parse::<u64>() {
+      Ok(length) => length,
+      Err(_) => {
+        return InternalError::bad_request()
+          .with_error(UPLOAD_LENGTH_HEADER, INVALID_ERROR)
+          .into_response();
+      }
+    },
+    None => {
+      return InternalError::bad_request()
+        .with_error(UPLOAD_LENGTH_HEADER, REQUIRED_ERROR)
+        .into_response();
+    }
+  };
+  let raw_metadata = header_str(&headers, UPLOAD_METADATA_HEADER).map(str::to_owned);
+  let metadata = match parse_metadata(raw_metadata.as_deref().unwrap_or_default()) {
+    Some(metadata) => metadata,
+    None => {
+      return InternalError::bad_request()
+        .with_error(UPLOAD_METADATA_HEADER, INVALID_ERROR)
+        .into_response();
+    }
+  };
+  let path = match metadata.get("path").or_else(|| metadata.get("filename")) {
+    Some(path) if !path.trim_matches('/').is_empty() => path.clone(),
+    _ => {
+      return InternalError::bad_request()
+        .with_error(UPLOAD_METADATA_HEADER, REQUIRED_ERROR)
+        .into_response();
+    }
+  };
+  let kind = metadata
+    .get("type")
+    .or_else(|| metadata.get("filetype"))
+    .cloned();
+
+  if let Err(err) = authorization.require(OBJECTS_WRITE_SCOPE, &path) {
+    return err.into_response();
+  }
+  match repository::object::get_object_by_path(&state.pool, authorization.claims.app, &path).await {
+    Ok(None) => {}
+    Ok(Some(_)) => {
+      log::error!("ObjectInstance already exists: {}", path);
+      return InternalError::from(StatusCode::CONFLICT)
+        .with_error("path", ALREADY_EXISTS_ERROR)
+        .into_response();
+    }
+    Err(err) => {
+      log::error!("Error getting objects from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  }
+  let allowance = match service::quota::get_quota_allowance(
+    &state.pool,
+    &state.config,
+    authorization.claims.app,
+    &path,
+  )
+  .await
+  {
+    Ok(allowance) => allowance,
+    Err(err) => {
+      log::error!("Error getting quota usage from database: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+  // appends can never go past the upload length so it is checked against the quota up front
+  if let Err(err) = allowance.check(1, length) {
+    return err.into_response();
+  }
+  let object_row = match service::tus::create_upload(
+    &state.pool,
+    state.storage.clone(),
+    authorization.claims.app,
+    path,
+    kind,
+    length,
+    raw_metadata,
+  )
+  .await
+  {
+    Ok(object_row) => object_row,
+    Err(err) => {
+      log::error!("Error creating upload: {}", err);
+      return InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response();
+    }
+  };
+
+  (
+    StatusCode::CREATED,
+    [
+      (
+        header::LOCATION,
+        format!(
+          "{}/tus/{}",
+          state.config.server.url.trim_end_matches('/'),
+          object_row.id
+        ),
+      ),
+      (
+        header::HeaderName::from_static(UPLOAD_OFFSET_HEADER),
+        "0".to_owned(),
+      ),
+    ],
+  )
+    .into_response()
+}
+
+#[utoipa::path(
+  head,
+  path = "/tus/{object_id}",
+  tags = [TUS_TAG],
+  responses(
+    (status = 200),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 412),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn head_tus_upload(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+  headers: HeaderMap,
+) -> impl IntoResponse {
+  if let Some(response) = unsupported_version(&headers) {
+    return response;
+  }
+  let (object_row, upload) =
+    match get_upload(&state, &authorization, OBJECTS_WRITE_SCOPE, object_id).await {
+      Ok(upload) => upload,
+      Err(response) => return response,
+    };
+
+  let mut response_headers = HeaderMap::new();
+  response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+  response_headers.insert(UPLOAD_OFFSET_HEADER, HeaderValue::from(object_row.size));
+  let length = upload
+    .as_ref()
+    .map(|upload| upload.length)
+    .unwrap_or(object_row.size);
+  response_headers.insert(UPLOAD_LENGTH_HEADER, HeaderValue::from(length));
+  if let Some(metadata) = upload
+    .and_then(|upload| upload.metadata)
+    .and_then(|metadata| HeaderValue::from_str(&metadata).ok())
+  {
+    response_headers.insert(UPLOAD_METADATA_HEADER, metadata);
+  }
+  (StatusCode::OK, response_headers).into_response()
+}
+
+#[utoipa::path(
+  patch,
+  path = "/tus/{object_id}",
+  tags = [TUS_TAG],
+  request_body(content = Vec<u8>, content_type = "application/offset+octet-stream"),
+  responses(
+    (status = 204),
+    (status = 400, content_type = "application/json", body = Errors),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 409, content_type = "application/json", body = Errors),
+    (status = 412),
+    (status = 413, content_type = "application/json", body = Errors),
+    (status = 415, content_type = "application/json", body = Errors),
+    (status = 460, content_type = "application/json", body = Errors),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:write"])
+  )
+)]
+pub async fn patch_tus_upload(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+  headers: HeaderMap,
+  body: Body,
+) -> impl IntoResponse {
+  if let Some(response) = unsupported_version(&headers) {
+    return response;
+  }
+  if header_str(&headers, header::CONTENT_TYPE.as_str()) != Some(OFFSET_OCTET_STREAM) {
+    return InternalError::from(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+      .with_error(header::CONTENT_TYPE.as_str(), INVALID_ERROR)
+      .into_response();
+  }
+  let offset = match header_str(&headers, UPLOAD_OFFSET_HEADER).map(str::parse::<u64>) {
+    Some(Ok(offset)) => offset,
+    Some(Err(_)) => {
+      return InternalError::bad_request()
+        .with_error(UPLOAD_OFFSET_HEADER, INVALID_ERROR)
+        .into_response();
+    }
+    None => {
+      return InternalError::bad_request()
+        .with_error(UPLOAD_OFFSET_HEADER, REQUIRED_ERROR)
+        .into_response();
+    }
+  };
+  let checksum = match header_str(&headers, UPLOAD_CHECKSUM_HEADER) {
+    Some(checksum) => match parse_checksum(checksum) {
+      Some(checksum) => Some(checksum),
+      None => {
+        return InternalError::bad_request()
+          .with_error(UPLOAD_CHECKSUM_HEADER, INVALID_ERROR)
+          .into_response();
+      }
+    },
+    None => None,
+  };
+  let (object_row, upload) =
+    match get_upload(&state, &authorization, OBJECTS_WRITE_SCOPE, object_id).await {
+      Ok(upload) => upload,
+      Err(response) => return response,
+    };
+  let length = upload
+    .map(|upload| upload.length)
+    .unwrap_or(object_row.size) as u64;
+
+  let stream = body
+    .into_data_stream()
+    .map(|bytes| bytes.map_err(io::Error::other));
+  match service::tus::append_upload(
+    &state.pool,
+    state.storage.clone(),
+    &object_row,
+    length,
+    offset,
+    checksum,
+    &state.config,
+    authorization.bypass_governance(&headers),
+    stream,
+  )
+  .await
+  {
+    Ok(offset) => (
+      StatusCode::NO_CONTENT,
+      [(UPLOAD_OFFSET_HEADER, offset.to_string())],
+    )
+      .into_response(),
+    Err(TusError::OffsetMismatch) => InternalError::from(StatusCode::CONFLICT)
+      .with_error(UPLOAD_OFFSET_HEADER, INVALID_ERROR)
+      .into_response(),
+    Err(TusError::LengthExceeded) => InternalError::from(StatusCode::PAYLOAD_TOO_LARGE)
+      .with_error(UPLOAD_LENGTH_HEADER, INVALID_ERROR)
+      .into_response(),
+    Err(TusError::ChecksumMismatch) => InternalError::bad_request()
+      .with_status(
+        StatusCode::from_u16(CHECKSUM_MISMATCH_STATUS).unwrap_or(StatusCode::BAD_REQUEST),
+      )
+      .with_error(UPLOAD_CHECKSUM_HEADER, INVALID_ERROR)
+      .into_response(),
+    Err(err) => {
+      if let TusError::Database(err) = &err {
+        if let Some(object_locked) = service::lock::object_locked(err) {
+          return InternalError::from(object_locked).into_response();
+        }
+        if let Some(quota_exceeded) = service::quota::quota_exceeded(err) {
+          return InternalError::from(quota_exceeded).into_response();
+        }
+      }
+      log::error!("Error appending upload: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+#[utoipa::path(
+  delete,
+  path = "/tus/{object_id}",
+  tags = [TUS_TAG],
+  responses(
+    (status = 204),
+    (status = 401, content_type = "application/json", body = Errors),
+    (status = 403, content_type = "application/json", body = Errors),
+    (status = 404, content_type = "application/json", body = Errors),
+    (status = 412),
+    (status = 500, content_type = "application/json", body = Errors),
+  ),
+  security(
+    ("Authorization" = ["objects:delete"])
+  )
+)]
+pub async fn delete_tus_upload(
+  State(state): State<RouterState>,
+  authorization: Authorization,
+  Path(object_id): Path<i64>,
+  headers: HeaderMap,
+) -> impl IntoResponse {
+  if let Some(response) = unsupported_version(&headers) {
+    return response;
+  }
+  let object_row = match get_upload(&state, &authorization, OBJECTS_DELETE_SCOPE, object_id).await {
+    Ok((object_row, _)) => object_row,
+    Err(response) => return response,
+  };
+  if let Err(err) =
+    service::lock::check_unlocked(&object_row, authorization.bypass_governance(&headers))
+  {
+    return err.into_response();
+  }
+  match service::object::delete_object(
+    &state.pool,
+    state.storage.clone(),
+    &state.config,
+    object_id,
+    authorization.bypass_governance(&headers),
+  )
+  .await
+  {
+    Ok(Some(_)) => StatusCode::NO_CONTENT.into_response(),
+    Ok(None) => {
+      log::error!("ObjectInstance not found: {}", object_id);
+      InternalError::not_found()
+        .with_error("object_id", NOT_FOUND_ERROR)
+        .into_response()
+    }
+    Err(err) => {
+      if let Some(object_locked) = service::lock::object_locked(&err) {
+        return InternalError::from(object_locked).into_response();
+      }
+      log::error!("Error deleting object: {}", err);
+      InternalError::internal_error()
+        .with_application_error(INTERNAL_ERROR)
+        .into_response()
+    }
+  }
+}
+
+/// looks the upload up and checks the token may act on it with `scope`
+async fn get_upload(
+  state: &RouterState,
+  authorization: &Authorization,
+  scope: &str,
+  object_id: i64,
+) -> Result<
+  (
+    repository::object::ObjectRow,
+    Option<repository::tus::TusUploadRow>,
+  ),
+  Response,
+> {
+  let object_row =
+    match repository::object::get_object_by_id(&state.pool, authorization.claims.app, object_id)
+      .await
+    {
+      Ok(Some(object_row)) => object_row,
+      Ok(None) => {
+        log::error!("ObjectInstance not found: {}", object_id);
+        return Err(
+          InternalError::not_found()
+            .with_error("object_id", NOT_FOUND_ERROR)
+            .into_response(),
+        );
+      }
+      Err(err) => {
+        log::error!("Error getting objects from database: {}", err);
+        return Err(
+          InternalError::internal_error()
+            .with_application_error(INTERNAL_ERROR)
+            .into_response(),
+        );
+      }
+    };
+  if let Err(err) = authorization.require(scope, &object_row.path) {
+    return Err(err.into_response());
+  }
+  match repository::tus::get_tus_upload(&state.pool, object_id).await {
+    Ok(upload) => Ok((object_row, upload)),
+    Err(err) => {
+      log::error!("Error getting upload from database: {}", err);
+      Err(
+        InternalError::internal_error()
+          .with_application_error(INTERNAL_ERROR)
+          .into_response(),
+      )
+    }
+  }
+}
+
+fn unsupported_version(headers: &HeaderMap) -> Option<Response> {
+  if header_str(headers, TUS_RESUMABLE_HEADER) == Some(TUS_VERSION) {
+    return None;
+  }
+  Some(
+    (
+      StatusCode::PRECONDITION_FAILED,
+      [(TUS_VERSION_HEADER, TUS_VERSION)],
+    )
+      .into_response(),
+  )
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+  headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// parses `key base64value` pairs separated by commas, values are optional
+fn parse_metadata(value: &str) -> Option<HashMap<String, String>> {
+  let mut metadata = HashMap::new();
+  for pair in value
+    .split(',')
+    .map(str::trim)
+    .filter(|pair| !pair.is_empty())
+  {
+    let (key, value) = match pair.split_once(' ') {
+      Some((key, value)) => (key, STANDARD.decode(value.trim()).ok()?),
+      None => (pair, Vec::new()),
+    };
+    metadata.insert(key.to_owned(), String::from_utf8(value).ok()?);
+  }
+  Some(metadata)
+}
+
+fn parse_checksum(value: &str) -> Option<UploadChecksum> {
+  let (algorithm, digest) = value.trim().split_once(' ')?;
+  let algorithm = algorithm.to_lowercase();
+  if !CHECKSUM_ALGORITHMS.contains(&algorithm.as_str()) {
+    return None;
+  }
+  Some(UploadChecksum {
+    algorithm,
+    digest: STANDARD.decode(digest.trim()).ok()?,
+  })
+}
+
+async fn tus_resumable(mut response: Response) -> Response {
+  response
+    .headers_mut()
+    .insert(TUS_RESUMABLE_HEADER, HeaderValue::from_static(TUS_VERSION));
+  response
+}
+
+pub fn create_router(state: RouterState) -> OpenApiRouter {
+  OpenApiRouter::new()
+    .routes(routes!(tus_options, create_tus_upload))
+    .routes(routes!(
+      head_tus_upload,
+      patch_tus_upload,
+      delete_tus_upload
+    ))
+    .layer(middleware::map_response(tus_resumable))
+    .with_state(state)
+}
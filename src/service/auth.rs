@@ -17,20 +17,22 @@ lazy_static! {
 }
 
 pub fn auth_token_configuration(config: &Config, access_token: &str) -> Configuration {
-  let mut configuration = Configuration::default();
-  configuration.base_path = config.auth.uri.to_owned();
-  configuration.bearer_access_token = Some(access_token.to_owned());
-  configuration
+  Configuration {
+    base_path: config.auth.uri.to_owned(),
+    bearer_access_token: Some(access_token.to_owned()),
+    ..Default::default()
+  }
 }
 
 pub fn auth_tenant_configuration(config: &Config, tenant_client_id: &uuid::Uuid) -> Configuration {
-  let mut configuration = Configuration::default();
-  configuration.base_path = config.auth.uri.to_owned();
-  configuration.api_key = Some(ApiKey {
-    prefix: None,
-    key: tenant_client_id.to_string(),
-  });
-  configuration
+  Configuration {
+    base_path: config.auth.uri.to_owned(),
+    api_key: Some(ApiKey {
+      prefix: None,
+      key: tenant_client_id.to_string(),
+    }),
+    ..Default::default()
+  }
 }
 
 pub async fn get_service_account_token(
@@ -46,7 +48,7 @@ pub async fn get_service_account_token(
   let token = create_service_account_token(config, tenant_client_id).await?;
   let access_token = token.access_token.clone();
 
-  SERVICE_ACCOUNT_TOKENS.insert(tenant_client_id.clone(), (token, now));
+  SERVICE_ACCOUNT_TOKENS.insert(*tenant_client_id, (token, now));
 
   Ok(access_token)
 }
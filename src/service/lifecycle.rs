@@ -0,0 +1,113 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+  core::config::Config,
+  repository::{
+    self,
+    lifecycle::{LifecycleRuleRow, UPDATED_AGE},
+  },
+  service,
+  storage::StorageBackend,
+};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const BATCH_SIZE: usize = 100;
+
+/// deletes the objects the rule has expired, locked objects are skipped until their lock is lifted,
+/// returns the number of objects deleted
+pub async fn apply_rule(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  rule: &LifecycleRuleRow,
+) -> sqlx::Result<usize> {
+  let now = chrono::Utc::now().timestamp();
+  let before = now - rule.days * SECONDS_PER_DAY;
+  let mut after_id = 0;
+  let mut deleted = 0;
+  loop {
+    let object_rows = repository::object::get_expired_objects(
+      pool,
+      rule.app,
+      &rule.prefix,
+      rule.age_from == UPDATED_AGE,
+      before,
+      after_id,
+      BATCH_SIZE,
+    )
+    .await?;
+    for object_row in &object_rows {
+      after_id = object_row.id;
+      if object_row.legal_hold != 0 || object_row.is_retained(now) {
+        log::debug!(
+          "Lifecycle rule {} skipped locked object {} \"{}\"",
+          rule.id,
+          object_row.id,
+          object_row.path
+        );
+        continue;
+      }
+      let deleted_row =
+        match service::object::delete_object(pool, storage.clone(), config, object_row.id, false)
+          .await
+        {
+          Ok(deleted_row) => deleted_row,
+          // locked since it was listed
+          Err(err) if service::lock::object_locked(&err).is_some() => continue,
+          Err(err) => return Err(err),
+        };
+      if deleted_row.is_some() {
+        log::info!(
+          "Lifecycle rule {} deleted object {} \"{}\"",
+          rule.id,
+          object_row.id,
+          object_row.path
+        );
+        deleted += 1;
+      }
+    }
+    if object_rows.len() < BATCH_SIZE {
+      break;
+    }
+  }
+  Ok(deleted)
+}
+
+/// applies the lifecycle rules of every app, returns the number of objects deleted
+pub async fn apply_rules(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+) -> sqlx::Result<usize> {
+  let mut deleted = 0;
+  for rule in repository::lifecycle::get_all_lifecycle_rules(pool).await? {
+    match apply_rule(pool, storage.clone(), config, &rule).await {
+      Ok(rule_deleted) => deleted += rule_deleted,
+      Err(err) => log::error!("Error applying lifecycle rule {}: {}", rule.id, err),
+    }
+  }
+  Ok(deleted)
+}
+
+/// runs `apply_rules` every `lifecycle.interval` seconds until cancelled
+pub async fn lifecycle_task(
+  pool: sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: Arc<Config>,
+  cancellation_token: CancellationToken,
+) {
+  let mut interval = tokio::time::interval(Duration::from_secs(config.lifecycle.interval.max(1)));
+  loop {
+    tokio::select! {
+      _ = cancellation_token.cancelled() => break,
+      _ = interval.tick() => {}
+    }
+    match apply_rules(&pool, storage.clone(), config.as_ref()).await {
+      Ok(0) => {}
+      Ok(deleted) => log::info!("Lifecycle rules deleted {} objects", deleted),
+      Err(err) => log::error!("Error applying lifecycle rules: {}", err),
+    }
+  }
+}
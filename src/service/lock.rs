@@ -0,0 +1,129 @@
+use std::{collections::HashMap, error::Error, fmt, io};
+
+use axum::response::{IntoResponse, Response};
+
+use crate::{
+  core::error::{InternalError, OBJECT_LOCKED_ERROR},
+  repository::object::{ObjectRow, COMPLIANCE_MODE, GOVERNANCE_MODE},
+};
+
+/// set to `true` by admins to modify or delete objects under governance retention
+pub const BYPASS_GOVERNANCE_RETENTION_HEADER: &str = "x-bypass-governance-retention";
+
+/// a modification or deletion refused because of a legal hold or retention, service functions
+/// returning `sqlx::Result` carry it as the source of an `io::Error`, see `object_locked`
+#[derive(Debug)]
+pub enum ObjectLocked {
+  LegalHold {
+    id: i64,
+  },
+  Retained {
+    id: i64,
+    mode: Option<String>,
+    retain_until: i64,
+  },
+}
+
+impl fmt::Display for ObjectLocked {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::LegalHold { id } => write!(f, "object {} is under legal hold", id),
+      Self::Retained {
+        id, retain_until, ..
+      } => write!(f, "object {} is retained until {}", id, retain_until),
+    }
+  }
+}
+
+impl Error for ObjectLocked {}
+
+impl From<&ObjectLocked> for InternalError {
+  fn from(err: &ObjectLocked) -> Self {
+    match err {
+      ObjectLocked::LegalHold { .. } => {
+        InternalError::forbidden().with_error("legal_hold", OBJECT_LOCKED_ERROR)
+      }
+      ObjectLocked::Retained {
+        mode, retain_until, ..
+      } => {
+        let parameters = HashMap::from([
+          ("mode".to_owned(), mode.clone().unwrap_or_default().into()),
+          ("retain_until".to_owned(), (*retain_until).into()),
+        ]);
+        InternalError::forbidden().with_error("retain_until", (OBJECT_LOCKED_ERROR, parameters))
+      }
+    }
+  }
+}
+
+impl From<ObjectLocked> for sqlx::Error {
+  fn from(err: ObjectLocked) -> Self {
+    sqlx::Error::Io(io::Error::other(err))
+  }
+}
+
+impl IntoResponse for ObjectLocked {
+  fn into_response(self) -> Response {
+    InternalError::from(&self).into_response()
+  }
+}
+
+/// the lock error `err` was caused by, if any
+pub fn object_locked(err: &sqlx::Error) -> Option<&ObjectLocked> {
+  match err {
+    sqlx::Error::Io(err) => err.get_ref()?.downcast_ref(),
+    _ => None,
+  }
+}
+
+/// fails when a legal hold or an unexpired retention keeps the object from being modified
+/// or deleted, governance retention does not apply when `bypass_governance`
+pub fn check_unlocked(object_row: &ObjectRow, bypass_governance: bool) -> Result<(), ObjectLocked> {
+  if object_row.legal_hold != 0 {
+    let err = ObjectLocked::LegalHold { id: object_row.id };
+    log::error!("{}", err);
+    return Err(err);
+  }
+  if !object_row.is_retained(chrono::Utc::now().timestamp())
+    || (bypass_governance && object_row.retention_mode.as_deref() == Some(GOVERNANCE_MODE))
+  {
+    return Ok(());
+  }
+  Err(retained_error(object_row))
+}
+
+/// fails with 403 when the object's retention may not be replaced by `retention`, or removed when
+/// it is `None`, compliance retention may only be extended and governance retention may only be
+/// shortened or removed when `bypass_governance`
+pub fn check_retention_change(
+  object_row: &ObjectRow,
+  retention: Option<(&str, i64)>,
+  bypass_governance: bool,
+) -> Result<(), InternalError> {
+  if !object_row.is_retained(chrono::Utc::now().timestamp()) {
+    return Ok(());
+  }
+  let extended = retention
+    .is_some_and(|(_, retain_until)| retain_until >= object_row.retain_until.unwrap_or_default());
+  let allowed = match object_row.retention_mode.as_deref() {
+    Some(COMPLIANCE_MODE) => {
+      extended && retention.is_some_and(|(retention_mode, _)| retention_mode == COMPLIANCE_MODE)
+    }
+    _ => extended || bypass_governance,
+  };
+  if allowed {
+    Ok(())
+  } else {
+    Err(InternalError::from(&retained_error(object_row)))
+  }
+}
+
+fn retained_error(object_row: &ObjectRow) -> ObjectLocked {
+  let err = ObjectLocked::Retained {
+    id: object_row.id,
+    mode: object_row.retention_mode.clone(),
+    retain_until: object_row.retain_until.unwrap_or_default(),
+  };
+  log::error!("{}", err);
+  err
+}
@@ -1,2 +1,9 @@
 pub mod auth;
+pub mod lifecycle;
+pub mod lock;
+pub mod multipart;
 pub mod object;
+pub mod quota;
+pub mod trash;
+pub mod tus;
+pub mod version;
@@ -0,0 +1,305 @@
+use std::{fmt, io, sync::Arc, time::Duration};
+
+use axum::body::Bytes;
+use futures_util::{Stream, StreamExt};
+use md5::{Digest, Md5};
+use tokio_util::{io::ReaderStream, sync::CancellationToken};
+
+use crate::{
+  core::{config::Config, database::run_transaction},
+  repository::{
+    self,
+    multipart::{MultipartPartRow, MultipartUploadRow},
+    object::ObjectRow,
+  },
+  service,
+  storage::{ObjectReader, StorageBackend},
+};
+
+pub const MAX_PART_NUMBER: u32 = 10000;
+
+#[derive(Debug)]
+pub enum MultipartError {
+  NoSuchUpload,
+  InvalidPartNumber,
+  InvalidPart,
+  InvalidPartOrder,
+  Io(io::Error),
+  Database(sqlx::Error),
+}
+
+impl fmt::Display for MultipartError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::NoSuchUpload => write!(f, "multipart upload does not exist"),
+      Self::InvalidPartNumber => write!(f, "part number must be between 1 and {}", MAX_PART_NUMBER),
+      Self::InvalidPart => write!(f, "multipart upload part is missing or does not match"),
+      Self::InvalidPartOrder => write!(f, "multipart upload parts are not in ascending order"),
+      Self::Io(err) => write!(f, "{}", err),
+      Self::Database(err) => write!(f, "{}", err),
+    }
+  }
+}
+
+impl From<io::Error> for MultipartError {
+  fn from(err: io::Error) -> Self {
+    Self::Io(err)
+  }
+}
+
+impl From<sqlx::Error> for MultipartError {
+  fn from(err: sqlx::Error) -> Self {
+    Self::Database(err)
+  }
+}
+
+pub async fn create_upload(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  path: String,
+  kind: Option<String>,
+) -> sqlx::Result<MultipartUploadRow> {
+  let upload_id = uuid::Uuid::new_v4().simple().to_string();
+  repository::multipart::create_multipart_upload(pool, &upload_id, app, path, kind).await
+}
+
+/// stages a part, parts can be uploaded in any order and concurrently, uploading the same part
+/// number again replaces it
+pub async fn upload_part<S>(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  app: i64,
+  upload_id: &str,
+  part_number: u32,
+  stream: S,
+) -> Result<MultipartPartRow, MultipartError>
+where
+  S: Stream<Item = io::Result<Bytes>> + Send,
+{
+  if !(1..=MAX_PART_NUMBER).contains(&part_number) {
+    return Err(MultipartError::InvalidPartNumber);
+  }
+  if repository::multipart::get_multipart_upload(pool, app, upload_id)
+    .await?
+    .is_none()
+  {
+    return Err(MultipartError::NoSuchUpload);
+  }
+  let (etag, size) = write_part(storage, upload_id, part_number, stream).await?;
+
+  let part_upload_id = upload_id.to_owned();
+  let result = run_transaction(pool, |transaction| {
+    Box::pin(async move {
+      repository::multipart::upsert_multipart_part(
+        transaction,
+        &part_upload_id,
+        part_number as i64,
+        &etag,
+        size as i64,
+      )
+      .await
+    })
+  })
+  .await;
+  match result {
+    Ok(part_row) => Ok(part_row),
+    Err(err) => {
+      // the upload was aborted while the part was being written
+      if repository::multipart::get_multipart_upload(pool, app, upload_id)
+        .await?
+        .is_none()
+      {
+        storage.delete_parts(upload_id).await?;
+        return Err(MultipartError::NoSuchUpload);
+      }
+      Err(err.into())
+    }
+  }
+}
+
+/// concatenates the parts into the object at the upload's path and removes the upload, `parts`
+/// lists the part numbers and etags to use in order, all uploaded parts are used when it is `None`
+pub async fn complete_upload(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  app: i64,
+  upload_id: &str,
+  parts: Option<&[(u32, String)]>,
+  bypass_governance: bool,
+) -> Result<ObjectRow, MultipartError> {
+  let upload = repository::multipart::get_multipart_upload(pool, app, upload_id)
+    .await?
+    .ok_or(MultipartError::NoSuchUpload)?;
+  let uploaded_parts = repository::multipart::get_multipart_parts(pool, upload_id).await?;
+  let part_numbers = match parts {
+    Some(parts) => {
+      let mut previous_part_number = 0;
+      for (part_number, etag) in parts {
+        if *part_number <= previous_part_number {
+          return Err(MultipartError::InvalidPartOrder);
+        }
+        previous_part_number = *part_number;
+        match uploaded_parts
+          .iter()
+          .find(|part| part.part_number == *part_number as i64)
+        {
+          Some(part) if part.etag.trim_matches('"') == etag.trim_matches('"') => {}
+          _ => return Err(MultipartError::InvalidPart),
+        }
+      }
+      parts
+        .iter()
+        .map(|(part_number, _)| *part_number as i64)
+        .collect::<Vec<_>>()
+    }
+    None => uploaded_parts
+      .iter()
+      .map(|part| part.part_number)
+      .collect::<Vec<_>>(),
+  };
+  if part_numbers.is_empty() {
+    return Err(MultipartError::InvalidPart);
+  }
+
+  let parts_storage = storage.clone();
+  let parts_upload_id = upload_id.to_owned();
+  let stream = futures_util::stream::unfold(
+    (part_numbers.into_iter(), None::<ReaderStream<ObjectReader>>),
+    move |(mut part_numbers, mut current)| {
+      let storage = parts_storage.clone();
+      let upload_id = parts_upload_id.clone();
+      async move {
+        loop {
+          if let Some(part) = current.as_mut() {
+            if let Some(bytes) = part.next().await {
+              return Some((bytes, (part_numbers, current)));
+            }
+          }
+          let part_number = part_numbers.next()? as u32;
+          match storage.open_read_part(&upload_id, part_number).await {
+            Ok(part) => current = Some(ReaderStream::new(part)),
+            Err(err) => return Some((Err(err), (part_numbers, None))),
+          }
+        }
+      }
+    },
+  );
+
+  let object_row = service::object::put_object(
+    pool,
+    storage.clone(),
+    config,
+    app,
+    upload.path,
+    upload.r#type,
+    bypass_governance,
+    stream,
+  )
+  .await?;
+
+  repository::multipart::delete_multipart_upload(pool, app, upload_id).await?;
+  storage.delete_parts(upload_id).await?;
+
+  Ok(object_row)
+}
+
+pub async fn abort_upload(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  app: i64,
+  upload_id: &str,
+) -> Result<(), MultipartError> {
+  if repository::multipart::delete_multipart_upload(pool, app, upload_id)
+    .await?
+    .is_none()
+  {
+    return Err(MultipartError::NoSuchUpload);
+  }
+  storage.delete_parts(upload_id).await?;
+  Ok(())
+}
+
+/// aborts uploads that have not received a part within the configured expiration and removes
+/// staged parts that no longer belong to an upload, returns the number of uploads aborted
+pub async fn cleanup_uploads(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  config: &Config,
+) -> Result<usize, MultipartError> {
+  let updated_before = chrono::Utc::now().timestamp() - config.multipart.expiration as i64;
+  let expired = repository::multipart::get_expired_multipart_uploads(pool, updated_before).await?;
+  let mut aborted = 0;
+  for upload in &expired {
+    match abort_upload(pool, storage, upload.app, &upload.id).await {
+      Ok(_) => aborted += 1,
+      Err(MultipartError::NoSuchUpload) => {}
+      Err(err) => return Err(err),
+    }
+  }
+
+  for upload_id in storage.list_part_uploads().await? {
+    if repository::multipart::get_any_multipart_upload(pool, &upload_id)
+      .await?
+      .is_none()
+    {
+      storage.delete_parts(&upload_id).await?;
+    }
+  }
+  Ok(aborted)
+}
+
+/// runs `cleanup_uploads` every `multipart.cleanup_interval` seconds until cancelled
+pub async fn cleanup_task(
+  pool: sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: Arc<Config>,
+  cancellation_token: CancellationToken,
+) {
+  let mut interval = tokio::time::interval(Duration::from_secs(
+    config.multipart.cleanup_interval.max(1),
+  ));
+  loop {
+    tokio::select! {
+      _ = cancellation_token.cancelled() => break,
+      _ = interval.tick() => {}
+    }
+    match cleanup_uploads(&pool, storage.as_ref(), config.as_ref()).await {
+      Ok(0) => {}
+      Ok(aborted) => log::info!("Aborted {} abandoned multipart uploads", aborted),
+      Err(err) => log::error!("Error cleaning up multipart uploads: {}", err),
+    }
+  }
+}
+
+/// writes the part through the storage backend, which only replaces an earlier upload of the
+/// same part once the whole part is written
+async fn write_part<S>(
+  storage: &dyn StorageBackend,
+  upload_id: &str,
+  part_number: u32,
+  stream: S,
+) -> io::Result<(String, u64)>
+where
+  S: Stream<Item = io::Result<Bytes>> + Send,
+{
+  let mut writer = storage.open_replace_part(upload_id, part_number).await?;
+  let mut hasher = Md5::new();
+  let mut stream = Box::pin(stream);
+  while let Some(bytes) = stream.next().await {
+    let bytes = match bytes {
+      Ok(bytes) => bytes,
+      Err(err) => {
+        writer.abort().await?;
+        return Err(err);
+      }
+    };
+    hasher.update(&bytes);
+    if let Err(err) = writer.write(&bytes).await {
+      writer.abort().await?;
+      return Err(err);
+    }
+  }
+  let size = writer.finish().await?;
+  Ok((format!("\"{}\"", hex::encode(hasher.finalize())), size))
+}
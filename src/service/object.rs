@@ -1,61 +1,1079 @@
-use std::{path::Path, sync::Arc};
+use std::{
+  collections::HashMap,
+  io,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+  },
+};
 
 use axum::body::Bytes;
-use tokio::{fs, io::AsyncWriteExt};
+use dashmap::DashMap;
+use futures_util::{Stream, StreamExt};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tokio_util::io::ReaderStream;
 
 use crate::{
-  core::{config::Config, database::run_transaction},
-  repository::{self, object::ObjectRow},
+  core::{
+    checksum::{Checksums, Hasher},
+    config::Config,
+    database::run_transaction,
+  },
+  repository::{
+    self,
+    object::ObjectRow,
+    object_write::{
+      APPEND_OPERATION, COPY_OPERATION, CREATE_OPERATION, DELETE_OPERATION, REPLACE_OPERATION,
+    },
+    version::ObjectVersionRow,
+  },
+  service::{self, quota::QuotaAllowance},
+  storage::{ObjectWriter, StorageBackend},
 };
 
+/// the most hashers kept in `OBJECT_HASHERS`, the least recently stored are dropped first
+const MAX_OBJECT_HASHERS: usize = 1024;
+
+/// orders the entries of `OBJECT_HASHERS` by when they were stored
+static OBJECT_HASHER_CLOCK: AtomicU64 = AtomicU64::new(0);
+
+lazy_static! {
+  /// hashers of recently written objects keyed by id along with the size they have hashed and
+  /// when they were stored
+  static ref OBJECT_HASHERS: DashMap<i64, (u64, Hasher, u64)> = DashMap::new();
+  /// locks of objects that are being written, entries are removed once nobody holds or waits on them
+  static ref OBJECT_LOCKS: DashMap<i64, Arc<Mutex<()>>> = DashMap::new();
+}
+
+/// exclusive access to write an object's contents, appends and replacements of the same object
+/// wait for each other so the stored size always matches the bytes written
+pub struct ObjectLock {
+  object_id: i64,
+  guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for ObjectLock {
+  fn drop(&mut self) {
+    self.guard.take();
+    OBJECT_LOCKS.remove_if(&self.object_id, |_, lock| Arc::strong_count(lock) == 1);
+  }
+}
+
+pub async fn lock_object(object_id: i64) -> ObjectLock {
+  let lock = OBJECT_LOCKS.entry(object_id).or_default().clone();
+  ObjectLock {
+    object_id,
+    guard: Some(lock.lock_owned().await),
+  }
+}
+
+/// locks each of the objects in id order so tasks locking several of the same objects can not
+/// deadlock
+async fn lock_objects(object_ids: impl IntoIterator<Item = i64>) -> Vec<ObjectLock> {
+  let mut object_ids = object_ids.into_iter().collect::<Vec<_>>();
+  object_ids.sort_unstable();
+  object_ids.dedup();
+  let mut locks = Vec::with_capacity(object_ids.len());
+  for object_id in object_ids {
+    locks.push(lock_object(object_id).await);
+  }
+  locks
+}
+
+/// an append in progress, it is journaled so bytes written before a crash are rolled back and
+/// holds the object's lock until it is finished or aborted
+pub struct ObjectAppend {
+  object_id: i64,
+  write_id: i64,
+  writer: Box<dyn ObjectWriter>,
+  hasher: Hasher,
+  allowance: QuotaAllowance,
+  offset: u64,
+  written: u64,
+  _lock: ObjectLock,
+}
+
+impl ObjectAppend {
+  /// the size of the object when the append started
+  pub fn offset(&self) -> u64 {
+    self.offset
+  }
+}
+
 pub async fn create_object(
   pool: &sqlx::AnyPool,
-  config: Arc<Config>,
+  storage: Arc<dyn StorageBackend>,
+  app: i64,
   path: String,
   kind: Option<String>,
 ) -> sqlx::Result<ObjectRow> {
-  run_transaction(pool, |transaction| {
-    let config = config.clone();
+  // the row commits before the storage is created so a rolled back transaction never leaves an
+  // orphaned object behind
+  let (object_row, write_id) = run_transaction(pool, |transaction| {
     Box::pin(async move {
-      let objects_path = Path::new(&config.objects_dir);
+      let object_row = repository::object::create_object(transaction, app, path, kind, 0).await?;
+      let write_row = repository::object_write::create_object_write(
+        transaction,
+        object_row.id,
+        CREATE_OPERATION,
+        0,
+      )
+      .await?;
+      Ok((object_row, write_row.id))
+    })
+  })
+  .await?;
+
+  if let Err(err) = storage.create(object_row.id).await {
+    let object_id = object_row.id;
+    run_transaction(pool, move |transaction| {
+      Box::pin(async move {
+        repository::object::delete_object(transaction, object_id).await?;
+        repository::object_write::delete_object_write(transaction, write_id).await?;
+        Ok(())
+      })
+    })
+    .await?;
+    return Err(err.into());
+  }
+  end_write(pool, write_id).await?;
+  Ok(object_row)
+}
 
-      let object_row = repository::object::create_object(transaction, path, kind, 0).await?;
-      let object_path = objects_path.join(object_row.id.to_string());
+/// the hasher for the current contents of an object, resumed from the previous append when
+/// possible otherwise the stored bytes are hashed again
+pub async fn object_hasher(storage: &dyn StorageBackend, object_id: i64) -> io::Result<Hasher> {
+  let size = storage.stat(object_id).await?.size;
+  if let Some((_, (hashed, hasher, _))) = OBJECT_HASHERS.remove(&object_id) {
+    if hashed == size {
+      return Ok(hasher);
+    }
+  }
+  let mut hasher = Hasher::new();
+  if size > 0 {
+    let mut object = ReaderStream::new(storage.open_read(object_id).await?);
+    while let Some(bytes) = object.next().await {
+      hasher.update(&bytes?);
+    }
+  }
+  Ok(hasher)
+}
 
-      let _ = fs::File::create(object_path).await?;
+/// keeps the hasher of an object's contents so its next append does not hash them again, only the
+/// most recently written objects are kept so the map stays bounded
+fn keep_hasher(object_id: i64, size: u64, hasher: Hasher) {
+  let stored = OBJECT_HASHER_CLOCK.fetch_add(1, Ordering::Relaxed);
+  OBJECT_HASHERS.insert(object_id, (size, hasher, stored));
+  while OBJECT_HASHERS.len() > MAX_OBJECT_HASHERS {
+    let oldest = OBJECT_HASHERS
+      .iter()
+      .min_by_key(|entry| entry.value().2)
+      .map(|entry| *entry.key());
+    match oldest {
+      Some(oldest) => {
+        OBJECT_HASHERS.remove(&oldest);
+      }
+      None => break,
+    }
+  }
+}
 
-      Ok(object_row)
-    })
-  })
-  .await
+/// starts appending to the object unless it is locked, governance retention does not apply when
+/// `bypass_governance`
+pub async fn open_append(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  config: &Config,
+  object_id: i64,
+  bypass_governance: bool,
+) -> sqlx::Result<ObjectAppend> {
+  let lock = lock_object(object_id).await;
+  // reloaded under the lock so a lock placed since the caller read the row applies
+  let object_row = repository::object::get_any_object_by_id(pool, object_id)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+  service::lock::check_unlocked(&object_row, bypass_governance)?;
+  // also read under the lock so appends to the same object cannot share what is left
+  let allowance =
+    service::quota::get_quota_allowance(pool, config, object_row.app, &object_row.path).await?;
+  let hasher = object_hasher(storage, object_id).await?;
+  let size = storage.stat(object_id).await?.size;
+  let write_id = begin_write(pool, object_id, APPEND_OPERATION, size as i64).await?;
+  match storage.open_append(object_id).await {
+    Ok(writer) => Ok(ObjectAppend {
+      object_id,
+      write_id,
+      writer,
+      hasher,
+      allowance,
+      offset: size,
+      written: 0,
+      _lock: lock,
+    }),
+    Err(err) => {
+      end_write(pool, write_id).await?;
+      Err(err.into())
+    }
+  }
 }
 
+/// appends `bytes` unless they would exceed one of the object's quotas
 pub async fn append_object(
   pool: &sqlx::AnyPool,
-  object_id: i64,
-  object: &mut fs::File,
+  append: &mut ObjectAppend,
   bytes: Bytes,
 ) -> sqlx::Result<usize> {
   let written = bytes.len();
-  object.write_all(&bytes).await?;
-  let _ = repository::object::update_object_size(pool, object_id, written as i64).await?;
+  append.allowance.check(0, append.written + written as u64)?;
+  append.writer.write(&bytes).await?;
+  append.hasher.update(&bytes);
+  append.written += written as u64;
+  repository::object::increment_object_size(pool, append.object_id, written as i64).await?;
   Ok(written)
 }
 
+/// makes the appended bytes durable and commits the size and checksums of the object's new
+/// contents
+pub async fn finish_append(
+  pool: &sqlx::AnyPool,
+  append: ObjectAppend,
+) -> sqlx::Result<Option<ObjectRow>> {
+  let size = append.writer.finish().await?;
+  let checksums = append.hasher.checksums();
+  keep_hasher(append.object_id, size, append.hasher);
+  commit_write(
+    pool,
+    append.write_id,
+    append.object_id,
+    None,
+    size as i64,
+    &checksums,
+  )
+  .await
+}
+
+/// discards the bytes written through the append and resyncs the stored size
+pub async fn abort_append(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  append: ObjectAppend,
+) -> sqlx::Result<()> {
+  append.writer.abort().await?;
+  if append.written > 0 {
+    let size = storage.stat(append.object_id).await?.size;
+    repository::object::update_object_size(pool, append.object_id, size as i64).await?;
+  }
+  end_write(pool, append.write_id).await
+}
+
+/// creates the object at `path` or replaces the contents of the existing one with `stream`
+#[allow(clippy::too_many_arguments)]
+pub async fn put_object<S>(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  app: i64,
+  path: String,
+  kind: Option<String>,
+  bypass_governance: bool,
+  stream: S,
+) -> sqlx::Result<ObjectRow>
+where
+  S: Stream<Item = io::Result<Bytes>> + Send,
+{
+  if let Some(object_row) = repository::object::get_object_by_path(pool, app, &path).await? {
+    return replace_object(
+      pool,
+      storage,
+      config,
+      object_row.id,
+      kind,
+      bypass_governance,
+      stream,
+    )
+    .await;
+  }
+  service::quota::get_quota_allowance(pool, config, app, &path)
+    .await?
+    .check(1, 0)?;
+  let object_row = create_object(pool, storage.clone(), app, path, kind.clone()).await?;
+  // a new object has no previous contents worth keeping as a version
+  match write_object(
+    pool,
+    storage.clone(),
+    config,
+    false,
+    false,
+    object_row.id,
+    kind,
+    stream,
+  )
+  .await
+  {
+    Ok(object_row) => Ok(object_row),
+    Err(err) => {
+      discard_object(pool, storage, object_row.id).await?;
+      Err(err)
+    }
+  }
+}
+
+/// writes `stream` as the new contents of the object, the old contents stay readable until every
+/// byte has been written and are kept if the write fails
+pub async fn replace_object<S>(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  object_id: i64,
+  kind: Option<String>,
+  bypass_governance: bool,
+  stream: S,
+) -> sqlx::Result<ObjectRow>
+where
+  S: Stream<Item = io::Result<Bytes>> + Send,
+{
+  write_object(
+    pool,
+    storage,
+    config,
+    true,
+    bypass_governance,
+    object_id,
+    kind,
+    stream,
+  )
+  .await
+}
+
+/// replaces the contents of the object unless it is locked and within its quotas, keeping the
+/// old ones as a version when `keep_version` is set and the object's path is versioned
+#[allow(clippy::too_many_arguments)]
+async fn write_object<S>(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  keep_version: bool,
+  bypass_governance: bool,
+  object_id: i64,
+  kind: Option<String>,
+  stream: S,
+) -> sqlx::Result<ObjectRow>
+where
+  S: Stream<Item = io::Result<Bytes>> + Send,
+{
+  let _lock = lock_object(object_id).await;
+  let object_row = repository::object::get_any_object_by_id(pool, object_id)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+  service::lock::check_unlocked(&object_row, bypass_governance)?;
+  let allowance =
+    service::quota::get_quota_allowance(pool, config, object_row.app, &object_row.path).await?;
+  let version_row = if keep_version {
+    save_version(pool, storage.as_ref(), config, object_id).await?
+  } else {
+    None
+  };
+  let size = storage.stat(object_id).await?.size;
+  let stream = allowance.limit_stream(size, stream);
+  let write_id = begin_write(pool, object_id, REPLACE_OPERATION, size as i64).await?;
+  let mut hasher = Hasher::new();
+  let size = match write_stream(storage.as_ref(), object_id, &mut hasher, stream).await {
+    Ok(size) => size,
+    Err(err) => {
+      end_write(pool, write_id).await?;
+      discard_version(pool, storage.as_ref(), version_row).await?;
+      return Err(err.into());
+    }
+  };
+  let checksums = hasher.checksums();
+  keep_hasher(object_id, size, hasher);
+  commit_write(pool, write_id, object_id, kind, size as i64, &checksums)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// copies the bytes of `source` to the object at `path` in the same app, creating it if it does
+/// not exist
+pub async fn copy_object(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  source: &ObjectRow,
+  path: String,
+  kind: Option<String>,
+  bypass_governance: bool,
+) -> sqlx::Result<ObjectRow> {
+  let kind = kind.or_else(|| source.r#type.clone());
+  let (object_row, created) =
+    match repository::object::get_object_by_path(pool, source.app, &path).await? {
+      Some(object_row) => (object_row, false),
+      None => {
+        service::quota::get_quota_allowance(pool, config, source.app, &path)
+          .await?
+          .check(1, source.size.max(0) as u64)?;
+        (
+          create_object(pool, storage.clone(), source.app, path, kind.clone()).await?,
+          true,
+        )
+      }
+    };
+  let _locks = lock_objects([source.id, object_row.id]).await;
+  let result = copy_locked(
+    pool,
+    storage.clone(),
+    config,
+    source,
+    object_row.id,
+    created,
+    kind,
+    bypass_governance,
+  )
+  .await;
+  if result.is_err() && created {
+    discard_object(pool, storage, object_row.id).await?;
+  }
+  result
+}
+
+/// `copy_object` once the source and target are locked, both are read again so the copy matches
+/// the source's bytes and the target's lock and quotas are checked as they are now
+#[allow(clippy::too_many_arguments)]
+async fn copy_locked(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  source: &ObjectRow,
+  object_id: i64,
+  created: bool,
+  kind: Option<String>,
+  bypass_governance: bool,
+) -> sqlx::Result<ObjectRow> {
+  let source = reload_source(pool, source).await?;
+  let object_row = repository::object::get_any_object_by_id(pool, object_id)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+  service::lock::check_unlocked(&object_row, bypass_governance)?;
+  // a created target already counts as an object
+  service::quota::get_quota_allowance(pool, config, object_row.app, &object_row.path)
+    .await?
+    .check(
+      0,
+      (source.size.max(0) as u64).saturating_sub(object_row.size.max(0) as u64),
+    )?;
+  let version_row = if created || object_row.id == source.id {
+    None
+  } else {
+    save_version(pool, storage.as_ref(), config, object_row.id).await?
+  };
+  let write_id = begin_write(pool, object_row.id, REPLACE_OPERATION, object_row.size).await?;
+  if object_row.id != source.id {
+    if let Err(err) = storage.copy(source.id, object_row.id).await {
+      end_write(pool, write_id).await?;
+      discard_version(pool, storage.as_ref(), version_row).await?;
+      return Err(err.into());
+    }
+    OBJECT_HASHERS.remove(&object_row.id);
+  }
+  let checksums = match (&source.sha256, &source.md5, &source.crc32c) {
+    (Some(sha256), Some(md5), Some(crc32c)) => Checksums {
+      sha256: sha256.clone(),
+      md5: md5.clone(),
+      crc32c: crc32c.clone(),
+    },
+    _ => object_hasher(storage.as_ref(), object_row.id)
+      .await?
+      .checksums(),
+  };
+  commit_write(pool, write_id, object_row.id, kind, source.size, &checksums)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// the current row of a copy's source, versions are stored under negative ids and never change
+async fn reload_source(pool: &sqlx::AnyPool, source: &ObjectRow) -> sqlx::Result<ObjectRow> {
+  if source.id < 0 {
+    return Ok(source.clone());
+  }
+  repository::object::get_any_object_by_id(pool, source.id)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// an object for `copy_objects` to copy to a new path, keeping the source's type unless `kind` is
+/// set
+pub struct ObjectCopy {
+  pub source: ObjectRow,
+  pub path: String,
+  pub kind: Option<String>,
+}
+
+/// copies each source to a new object at its path within the app's quotas, the rows are inserted
+/// in one transaction and removed again when any of the bytes fail to copy, the caller must make
+/// sure the paths are free
+pub async fn copy_objects(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  copies: Vec<ObjectCopy>,
+) -> sqlx::Result<Vec<ObjectRow>> {
+  if let Some(copy) = copies.first() {
+    service::quota::check_writes(
+      pool,
+      config,
+      copy.source.app,
+      copies
+        .iter()
+        .map(|copy| (copy.path.as_str(), copy.source.size.max(0) as u64)),
+    )
+    .await?;
+  }
+  let sources = copies
+    .iter()
+    .map(|copy| copy.source.clone())
+    .collect::<Vec<_>>();
+  let created = run_transaction(pool, move |transaction| {
+    Box::pin(async move {
+      let mut created = Vec::with_capacity(copies.len());
+      for copy in copies {
+        let object_row =
+          repository::object::create_object_copy(transaction, &copy.source, copy.path, copy.kind)
+            .await?;
+        let write_row = repository::object_write::create_object_write(
+          transaction,
+          object_row.id,
+          COPY_OPERATION,
+          object_row.size,
+        )
+        .await?;
+        created.push((object_row, write_row.id));
+      }
+      Ok(created)
+    })
+  })
+  .await?;
+
+  // the sources are locked too so none is written to while it is copied
+  let _locks = lock_objects(
+    sources
+      .iter()
+      .chain(created.iter().map(|(object_row, _)| object_row))
+      .map(|object_row| object_row.id),
+  )
+  .await;
+  let mut current = Vec::with_capacity(sources.len());
+  for source in &sources {
+    match reload_source(pool, source).await {
+      Ok(source) => current.push(source),
+      Err(err) => {
+        discard_copies(pool, storage.as_ref(), &created).await?;
+        return Err(err);
+      }
+    }
+  }
+  for (source, (object_row, _)) in current.iter().zip(&created) {
+    if let Err(err) = storage.copy(source.id, object_row.id).await {
+      discard_copies(pool, storage.as_ref(), &created).await?;
+      return Err(err.into());
+    }
+  }
+  // copies of sources written since they were read take their current size and checksums, those
+  // written before checksums were stored are hashed from the copied bytes
+  let mut refreshed = Vec::new();
+  for (source, (object_row, _)) in current.iter().zip(&created) {
+    let checksums = match (&source.sha256, &source.md5, &source.crc32c) {
+      (Some(sha256), Some(md5), Some(crc32c)) => Checksums {
+        sha256: sha256.clone(),
+        md5: md5.clone(),
+        crc32c: crc32c.clone(),
+      },
+      _ => object_hasher(storage.as_ref(), object_row.id)
+        .await?
+        .checksums(),
+    };
+    if source.size != object_row.size
+      || object_row.sha256.as_ref() != Some(&checksums.sha256)
+      || object_row.md5.as_ref() != Some(&checksums.md5)
+      || object_row.crc32c.as_ref() != Some(&checksums.crc32c)
+    {
+      refreshed.push((object_row.id, source.size, checksums));
+    }
+  }
+  let write_ids = created
+    .iter()
+    .map(|(_, write_id)| *write_id)
+    .collect::<Vec<_>>();
+  let mut updated = run_transaction(pool, move |transaction| {
+    Box::pin(async move {
+      let mut updated = HashMap::with_capacity(refreshed.len());
+      for (object_id, size, checksums) in refreshed {
+        if let Some(object_row) =
+          repository::object::update_object_contents(transaction, object_id, None, size, &checksums)
+            .await?
+        {
+          updated.insert(object_id, object_row);
+        }
+      }
+      for write_id in write_ids {
+        repository::object_write::delete_object_write(transaction, write_id).await?;
+      }
+      Ok(updated)
+    })
+  })
+  .await?;
+  Ok(
+    created
+      .into_iter()
+      .map(|(object_row, _)| updated.remove(&object_row.id).unwrap_or(object_row))
+      .collect(),
+  )
+}
+
+/// removes the rows and storage of copies that did not go through, their `COPY_OPERATION` writes
+/// are kept until the storage is gone so `recover_writes` can finish the job after a crash
+async fn discard_copies(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  created: &[(ObjectRow, i64)],
+) -> sqlx::Result<()> {
+  let object_ids = created
+    .iter()
+    .map(|(object_row, _)| object_row.id)
+    .collect::<Vec<_>>();
+  run_transaction(pool, move |transaction| {
+    Box::pin(async move {
+      for object_id in object_ids {
+        repository::object::delete_object(transaction, object_id).await?;
+      }
+      Ok(())
+    })
+  })
+  .await?;
+  for (object_row, write_id) in created {
+    OBJECT_HASHERS.remove(&object_row.id);
+    delete_storage(storage, object_row.id).await?;
+    end_write(pool, *write_id).await?;
+  }
+  Ok(())
+}
+
+/// the outcome of `move_folder`
+pub enum FolderMove {
+  /// the number of objects moved
+  Moved(u64),
+  /// paths under the new prefix that are already taken, nothing was moved
+  Conflicts(Vec<String>),
+  /// one prefix is under the other, so moved objects would take each other's paths
+  Nested,
+}
+
+/// moves the object to `path`, replacing its type when `kind` is given, unless a quota covering
+/// the new path would be exceeded
+pub async fn move_object(
+  pool: &sqlx::AnyPool,
+  config: &Config,
+  app: i64,
+  object_id: i64,
+  path: String,
+  kind: Option<String>,
+) -> sqlx::Result<Option<ObjectRow>> {
+  let object_row = match repository::object::get_object_by_id(pool, app, object_id).await? {
+    Some(object_row) => object_row,
+    None => return Ok(None),
+  };
+  service::quota::check_object_move(
+    pool,
+    config,
+    app,
+    &object_row.path,
+    &path,
+    object_row.size.max(0) as u64,
+  )
+  .await?;
+  repository::object::update_object_path(pool, app, object_id, path, kind).await
+}
+
+/// moves every object under `prefix` to `new_prefix` in one transaction unless the prefixes are
+/// nested, one of the new paths is taken or a quota covering them would be exceeded
+pub async fn move_folder(
+  pool: &sqlx::AnyPool,
+  config: &Config,
+  app: i64,
+  prefix: String,
+  new_prefix: String,
+) -> sqlx::Result<FolderMove> {
+  if new_prefix.starts_with(&prefix) || prefix.starts_with(&new_prefix) {
+    return Ok(FolderMove::Nested);
+  }
+  service::quota::check_move(pool, config, app, &prefix, &new_prefix).await?;
+  run_transaction(pool, move |transaction| {
+    Box::pin(async move {
+      let conflicts =
+        repository::object::get_prefix_conflicts(transaction, app, &prefix, &new_prefix, 100)
+          .await?;
+      if !conflicts.is_empty() {
+        return Ok(FolderMove::Conflicts(conflicts));
+      }
+      let moved =
+        repository::object::update_objects_prefix(transaction, app, &prefix, &new_prefix).await?;
+      Ok(FolderMove::Moved(moved))
+    })
+  })
+  .await
+}
+
+/// deletes the object unless it is locked, moving it to the trash when it is enabled and keeping
+/// its contents as a version when its path is versioned
 pub async fn delete_object(
   pool: &sqlx::AnyPool,
-  config: Arc<Config>,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  object_id: i64,
+  bypass_governance: bool,
+) -> sqlx::Result<Option<ObjectRow>> {
+  // read under the lock so a write or lock change in progress is finished before it is checked
+  let _lock = lock_object(object_id).await;
+  let object_row = match repository::object::get_any_object_by_id(pool, object_id).await? {
+    Some(object_row) => object_row,
+    None => return Ok(None),
+  };
+  service::lock::check_unlocked(&object_row, bypass_governance)?;
+  if !service::version::is_versioned(config, object_row.app, &object_row.path) {
+    if config.trash.enabled {
+      return trash_object(pool, object_id).await;
+    }
+    return discard_object(pool, storage, object_id).await;
+  }
+  let version_row = save_version(pool, storage.as_ref(), config, object_id).await?;
+  let deleted = if config.trash.enabled {
+    trash_object(pool, object_id).await?
+  } else {
+    discard_object(pool, storage.clone(), object_id).await?
+  };
+  if deleted.is_none() {
+    discard_version(pool, storage.as_ref(), version_row).await?;
+  }
+  Ok(deleted)
+}
+
+/// deletes a batch of objects like `delete_object`, the rows of those that keep no version are
+/// trashed or removed in a single transaction, returns the number of objects deleted
+pub async fn delete_objects(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  object_rows: &[ObjectRow],
+  bypass_governance: bool,
+) -> sqlx::Result<u64> {
+  let mut deleted = 0;
+  let mut object_ids = Vec::new();
+  for object_row in object_rows {
+    if service::version::is_versioned(config, object_row.app, &object_row.path) {
+      if delete_object(
+        pool,
+        storage.clone(),
+        config,
+        object_row.id,
+        bypass_governance,
+      )
+      .await?
+      .is_some()
+      {
+        deleted += 1;
+      }
+    } else {
+      object_ids.push(object_row.id);
+    }
+  }
+  let trash = config.trash.enabled;
+  let discarded = run_transaction(pool, move |transaction| {
+    Box::pin(async move {
+      // the rows are checked as they are deleted so a lock placed since they were read applies,
+      // failing rolls back the whole batch
+      let mut discarded = Vec::new();
+      for object_id in object_ids {
+        if trash {
+          if let Some(trashed_row) = repository::trash::trash_object(transaction, object_id).await?
+          {
+            service::lock::check_unlocked(&trashed_row.object_row(), bypass_governance)?;
+            discarded.push((object_id, None));
+          }
+          continue;
+        }
+        let object_row = match repository::object::delete_object(transaction, object_id).await? {
+          Some(object_row) => object_row,
+          None => continue,
+        };
+        service::lock::check_unlocked(&object_row, bypass_governance)?;
+        let write_row = repository::object_write::create_object_write(
+          transaction,
+          object_id,
+          DELETE_OPERATION,
+          object_row.size,
+        )
+        .await?;
+        discarded.push((object_id, Some(write_row.id)));
+      }
+      Ok(discarded)
+    })
+  })
+  .await?;
+  for (object_id, write_id) in &discarded {
+    match write_id {
+      Some(write_id) => finish_delete(pool, storage.as_ref(), *object_id, *write_id).await?,
+      None => {
+        OBJECT_HASHERS.remove(object_id);
+      }
+    }
+  }
+  Ok(deleted + discarded.len() as u64)
+}
+
+/// deletes the object without keeping a version, used to roll back objects that were never written
+pub async fn discard_object(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  object_id: i64,
+) -> sqlx::Result<Option<ObjectRow>> {
+  let deleted = run_transaction(pool, move |transaction| {
+    Box::pin(async move {
+      let object_row = match repository::object::delete_object(transaction, object_id).await? {
+        Some(object_row) => object_row,
+        None => return Ok(None),
+      };
+      let write_row = repository::object_write::create_object_write(
+        transaction,
+        object_id,
+        DELETE_OPERATION,
+        object_row.size,
+      )
+      .await?;
+      Ok(Some((object_row, write_row.id)))
+    })
+  })
+  .await?;
+  let (object_row, write_id) = match deleted {
+    Some(deleted) => deleted,
+    None => return Ok(None),
+  };
+  finish_delete(pool, storage.as_ref(), object_id, write_id).await?;
+  Ok(Some(object_row))
+}
+
+/// moves the object to the trash, its bytes are kept until it is purged
+async fn trash_object(pool: &sqlx::AnyPool, object_id: i64) -> sqlx::Result<Option<ObjectRow>> {
+  let trashed_row = run_transaction(pool, move |transaction| {
+    Box::pin(async move { repository::trash::trash_object(transaction, object_id).await })
+  })
+  .await?;
+  OBJECT_HASHERS.remove(&object_id);
+  Ok(trashed_row.map(|trashed_row| trashed_row.object_row()))
+}
+
+/// deletes the storage of an object whose row is already gone along with its `DELETE_OPERATION`
+/// write, storage that fails to delete is retried by `recover_writes`
+pub async fn finish_delete(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  object_id: i64,
+  write_id: i64,
+) -> sqlx::Result<()> {
+  OBJECT_HASHERS.remove(&object_id);
+  if let Err(err) = delete_storage(storage, object_id).await {
+    log::error!("Error deleting object {} from storage: {}", object_id, err);
+    return Ok(());
+  }
+  end_write(pool, write_id).await
+}
+
+/// keeps the current state of the object as a version when its path is versioned, reloaded so it
+/// matches the bytes under the lock the caller holds
+async fn save_version(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  config: &Config,
+  object_id: i64,
+) -> sqlx::Result<Option<ObjectVersionRow>> {
+  match repository::object::get_any_object_by_id(pool, object_id).await? {
+    Some(object_row) => service::version::save_version(pool, storage, config, &object_row).await,
+    None => Ok(None),
+  }
+}
+
+/// removes a version saved for a write that did not go through
+async fn discard_version(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  version_row: Option<ObjectVersionRow>,
+) -> sqlx::Result<()> {
+  if let Some(version_row) = version_row {
+    service::version::purge_version(pool, storage, version_row.app, version_row.id).await?;
+  }
+  Ok(())
+}
+
+/// reconciles writes interrupted by a crash, unfinished replacements, appends and copies are rolled
+/// back and deletes are finished, must run before any requests are served
+pub async fn recover_writes(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+) -> sqlx::Result<usize> {
+  storage.recover().await?;
+  let write_rows = repository::object_write::get_object_writes(pool).await?;
+  for write_row in &write_rows {
+    log::info!(
+      "Recovering {} of object {}",
+      write_row.operation,
+      write_row.object_id
+    );
+    if write_row.operation == DELETE_OPERATION {
+      delete_storage(storage, write_row.object_id).await?;
+    } else if write_row.operation == COPY_OPERATION {
+      let object_id = write_row.object_id;
+      run_transaction(pool, move |transaction| {
+        Box::pin(async move {
+          repository::object::delete_object(transaction, object_id).await?;
+          Ok(())
+        })
+      })
+      .await?;
+      delete_storage(storage, object_id).await?;
+    } else if repository::object::get_any_object_by_id(pool, write_row.object_id)
+      .await?
+      .is_some()
+    {
+      if write_row.operation == APPEND_OPERATION {
+        match storage.stat(write_row.object_id).await {
+          Ok(stat) if stat.size > write_row.size as u64 => {
+            storage
+              .truncate(write_row.object_id, write_row.size as u64)
+              .await?
+          }
+          Ok(_) => {}
+          Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+          Err(err) => return Err(err.into()),
+        }
+      }
+      resync_object(pool, storage, write_row.object_id).await?;
+    }
+    end_write(pool, write_row.id).await?;
+  }
+  Ok(write_rows.len())
+}
+
+/// sets the stored size and checksums from the object's storage, creating it if it is missing
+async fn resync_object(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  object_id: i64,
+) -> sqlx::Result<()> {
+  let size = match storage.stat(object_id).await {
+    Ok(stat) => stat.size,
+    Err(err) if err.kind() == io::ErrorKind::NotFound => {
+      storage.create(object_id).await?;
+      0
+    }
+    Err(err) => return Err(err.into()),
+  };
+  OBJECT_HASHERS.remove(&object_id);
+  let checksums = object_hasher(storage, object_id).await?.checksums();
+  run_transaction(pool, move |transaction| {
+    Box::pin(async move {
+      repository::object::update_object_contents(
+        transaction,
+        object_id,
+        None,
+        size as i64,
+        &checksums,
+      )
+      .await?;
+      Ok(())
+    })
+  })
+  .await
+}
+
+async fn begin_write(
+  pool: &sqlx::AnyPool,
   object_id: i64,
+  operation: &'static str,
+  size: i64,
+) -> sqlx::Result<i64> {
+  run_transaction(pool, move |transaction| {
+    Box::pin(async move {
+      let write_row =
+        repository::object_write::create_object_write(transaction, object_id, operation, size)
+          .await?;
+      Ok(write_row.id)
+    })
+  })
+  .await
+}
+
+/// stores the object's new contents and removes the write from the journal in one transaction
+async fn commit_write(
+  pool: &sqlx::AnyPool,
+  write_id: i64,
+  object_id: i64,
+  kind: Option<String>,
+  size: i64,
+  checksums: &Checksums,
 ) -> sqlx::Result<Option<ObjectRow>> {
+  let checksums = checksums.clone();
   run_transaction(pool, move |transaction| {
-    let config = config.clone();
     Box::pin(async move {
-      let objects_path = Path::new(&config.objects_dir);
-      let object_path = objects_path.join(object_id.to_string());
-      let object_row = repository::object::delete_object(transaction, object_id).await?;
-      fs::remove_file(object_path).await?;
+      let object_row =
+        repository::object::update_object_contents(transaction, object_id, kind, size, &checksums)
+          .await?;
+      repository::object_write::delete_object_write(transaction, write_id).await?;
       Ok(object_row)
     })
   })
   .await
 }
+
+async fn end_write(pool: &sqlx::AnyPool, write_id: i64) -> sqlx::Result<()> {
+  run_transaction(pool, move |transaction| {
+    Box::pin(async move {
+      repository::object_write::delete_object_write(transaction, write_id).await?;
+      Ok(())
+    })
+  })
+  .await
+}
+
+async fn delete_storage(storage: &dyn StorageBackend, object_id: i64) -> io::Result<()> {
+  match storage.delete(object_id).await {
+    Ok(_) => Ok(()),
+    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+    Err(err) => Err(err),
+  }
+}
+
+async fn write_stream<S>(
+  storage: &dyn StorageBackend,
+  object_id: i64,
+  hasher: &mut Hasher,
+  stream: S,
+) -> io::Result<u64>
+where
+  S: Stream<Item = io::Result<Bytes>> + Send,
+{
+  let mut object = storage.open_replace(object_id).await?;
+  let mut stream = Box::pin(stream);
+  while let Some(bytes) = stream.next().await {
+    let result = match bytes {
+      Ok(bytes) => {
+        hasher.update(&bytes);
+        object.write(&bytes).await
+      }
+      Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+      if let Err(abort_err) = object.abort().await {
+        log::error!("Error aborting object write: {}", abort_err);
+      }
+      return Err(err);
+    }
+  }
+  object.finish().await
+}
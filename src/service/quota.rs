@@ -0,0 +1,319 @@
+use std::{collections::HashMap, error::Error, fmt, io};
+
+use axum::{
+  body::Bytes,
+  response::{IntoResponse, Response},
+};
+use futures_util::{Stream, StreamExt};
+
+use crate::{
+  core::{
+    config::{Config, QuotaConfig},
+    error::{InternalError, QUOTA_EXCEEDED_ERROR},
+  },
+  repository,
+};
+
+pub const MAX_BYTES: &str = "max_bytes";
+pub const MAX_OBJECTS: &str = "max_objects";
+
+/// a quota along with what currently counts towards it
+pub struct QuotaUsage<'a> {
+  pub quota: &'a QuotaConfig,
+  pub bytes: u64,
+  pub objects: u64,
+}
+
+/// how much more the tightest quota covering a path allows
+pub struct QuotaLimit {
+  pub remaining: u64,
+  pub max: u64,
+  pub prefix: String,
+}
+
+/// a write refused because it would exceed a quota, service functions returning `sqlx::Result`
+/// carry it as the source of an `io::Error`, see `quota_exceeded`
+#[derive(Debug)]
+pub struct QuotaExceeded {
+  pub name: &'static str,
+  pub max: u64,
+  pub prefix: String,
+}
+
+impl fmt::Display for QuotaExceeded {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} quota of {} on \"{}\" would be exceeded",
+      self.name, self.max, self.prefix
+    )
+  }
+}
+
+impl Error for QuotaExceeded {}
+
+impl From<&QuotaExceeded> for InternalError {
+  fn from(err: &QuotaExceeded) -> Self {
+    let parameters = HashMap::from([
+      ("prefix".to_owned(), err.prefix.clone().into()),
+      ("max".to_owned(), err.max.into()),
+    ]);
+    InternalError::forbidden().with_error(err.name, (QUOTA_EXCEEDED_ERROR, parameters))
+  }
+}
+
+impl From<QuotaExceeded> for sqlx::Error {
+  fn from(err: QuotaExceeded) -> Self {
+    sqlx::Error::Io(io::Error::other(err))
+  }
+}
+
+impl IntoResponse for QuotaExceeded {
+  fn into_response(self) -> Response {
+    InternalError::from(&self).into_response()
+  }
+}
+
+/// the quota error `err` was caused by, if any
+pub fn quota_exceeded(err: &sqlx::Error) -> Option<&QuotaExceeded> {
+  match err {
+    sqlx::Error::Io(err) => err.get_ref()?.downcast_ref(),
+    _ => None,
+  }
+}
+
+/// what may still be written to a path before one of its quotas is exceeded, `None` when no
+/// quota limits it
+#[derive(Default)]
+pub struct QuotaAllowance {
+  pub bytes: Option<QuotaLimit>,
+  pub objects: Option<QuotaLimit>,
+}
+
+impl QuotaAllowance {
+  /// fails when adding `objects` objects and `bytes` bytes would exceed a quota
+  pub fn check(&self, objects: u64, bytes: u64) -> Result<(), QuotaExceeded> {
+    for (name, limit, added) in [
+      (MAX_OBJECTS, &self.objects, objects),
+      (MAX_BYTES, &self.bytes, bytes),
+    ] {
+      if let Some(limit) = limit {
+        if added > limit.remaining {
+          let err = QuotaExceeded {
+            name,
+            max: limit.max,
+            prefix: limit.prefix.clone(),
+          };
+          log::error!("{}", err);
+          return Err(err);
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// the largest an object of `size` bytes may grow to without exceeding a quota
+  pub fn max_size(&self, size: u64) -> Option<u64> {
+    self
+      .bytes
+      .as_ref()
+      .map(|limit| limit.remaining.saturating_add(size))
+  }
+
+  /// fails `stream` once it would grow an object of `size` bytes past the allowance
+  pub fn limit_stream<S>(self, size: u64, stream: S) -> impl Stream<Item = io::Result<Bytes>> + Send
+  where
+    S: Stream<Item = io::Result<Bytes>> + Send,
+  {
+    let max_size = self.max_size(size);
+    let mut received = 0u64;
+    stream.map(move |bytes| {
+      let bytes = bytes?;
+      received += bytes.len() as u64;
+      if max_size.is_some_and(|max_size| received > max_size) {
+        self
+          .check(0, received.saturating_sub(size))
+          .map_err(io::Error::other)?;
+      }
+      Ok(bytes)
+    })
+  }
+
+  /// tightens the allowance to what `quota_usage` still allows
+  fn restrict(&mut self, quota_usage: &QuotaUsage) {
+    let prefix = quota_prefix(quota_usage.quota);
+    for (max, used, limit) in [
+      (
+        quota_usage.quota.max_bytes,
+        quota_usage.bytes,
+        &mut self.bytes,
+      ),
+      (
+        quota_usage.quota.max_objects,
+        quota_usage.objects,
+        &mut self.objects,
+      ),
+    ] {
+      let max = match max {
+        Some(max) => max,
+        None => continue,
+      };
+      let remaining = max.saturating_sub(used);
+      if limit
+        .as_ref()
+        .map_or(true, |limit| remaining < limit.remaining)
+      {
+        *limit = Some(QuotaLimit {
+          remaining,
+          max,
+          prefix: prefix.to_owned(),
+        });
+      }
+    }
+  }
+}
+
+fn quota_prefix(quota: &QuotaConfig) -> &str {
+  quota.prefix.trim_start_matches('/')
+}
+
+/// the quotas of `app` with their usage, only those covering `path` when it is given
+pub async fn get_quota_usages<'a>(
+  pool: &sqlx::AnyPool,
+  config: &'a Config,
+  app: i64,
+  path: Option<&str>,
+) -> sqlx::Result<Vec<QuotaUsage<'a>>> {
+  let mut quota_usages = Vec::new();
+  for quota in &config.quotas {
+    if quota.app != app {
+      continue;
+    }
+    if let Some(path) = path {
+      if !path
+        .trim_start_matches('/')
+        .starts_with(quota_prefix(quota))
+      {
+        continue;
+      }
+    }
+    let usage = repository::object::get_object_usage(pool, app, quota_prefix(quota)).await?;
+    quota_usages.push(QuotaUsage {
+      quota,
+      bytes: usage.bytes.max(0) as u64,
+      objects: usage.objects.max(0) as u64,
+    });
+  }
+  Ok(quota_usages)
+}
+
+/// what may still be written to `path` before one of the quotas covering it is exceeded
+pub async fn get_quota_allowance(
+  pool: &sqlx::AnyPool,
+  config: &Config,
+  app: i64,
+  path: &str,
+) -> sqlx::Result<QuotaAllowance> {
+  let mut allowance = QuotaAllowance::default();
+  for quota_usage in get_quota_usages(pool, config, app, Some(path)).await? {
+    allowance.restrict(&quota_usage);
+  }
+  Ok(allowance)
+}
+
+/// the allowance of each of the app's quotas along with the prefix it covers, for writes spread
+/// over several paths
+pub async fn get_quota_allowances(
+  pool: &sqlx::AnyPool,
+  config: &Config,
+  app: i64,
+) -> sqlx::Result<Vec<(String, QuotaAllowance)>> {
+  let mut allowances = Vec::new();
+  for quota_usage in get_quota_usages(pool, config, app, None).await? {
+    let mut allowance = QuotaAllowance::default();
+    allowance.restrict(&quota_usage);
+    allowances.push((quota_prefix(quota_usage.quota).to_owned(), allowance));
+  }
+  Ok(allowances)
+}
+
+/// fails when writing objects of the given paths and sizes would exceed one of the app's quotas
+pub async fn check_writes<'a>(
+  pool: &sqlx::AnyPool,
+  config: &Config,
+  app: i64,
+  writes: impl Iterator<Item = (&'a str, u64)> + Clone,
+) -> sqlx::Result<()> {
+  for (prefix, allowance) in get_quota_allowances(pool, config, app).await? {
+    let (objects, bytes) = writes
+      .clone()
+      .filter(|(path, _)| path.trim_start_matches('/').starts_with(prefix.as_str()))
+      .fold((0u64, 0u64), |(objects, bytes), (_, size)| {
+        (objects + 1, bytes + size)
+      });
+    if objects > 0 {
+      allowance.check(objects, bytes)?;
+    }
+  }
+  Ok(())
+}
+
+/// fails when moving an object of `size` bytes from `path` to `new_path` would add it to one of
+/// the app's quotas that can not hold it
+pub async fn check_object_move(
+  pool: &sqlx::AnyPool,
+  config: &Config,
+  app: i64,
+  path: &str,
+  new_path: &str,
+  size: u64,
+) -> sqlx::Result<()> {
+  for (prefix, allowance) in get_quota_allowances(pool, config, app).await? {
+    let covers = |path: &str| path.trim_start_matches('/').starts_with(prefix.as_str());
+    if covers(new_path) && !covers(path) {
+      allowance.check(1, size)?;
+    }
+  }
+  Ok(())
+}
+
+/// fails when moving the objects under `prefix` to `new_prefix` would add more to one of the
+/// app's quotas than it allows
+pub async fn check_move(
+  pool: &sqlx::AnyPool,
+  config: &Config,
+  app: i64,
+  prefix: &str,
+  new_prefix: &str,
+) -> sqlx::Result<()> {
+  for (quota_prefix, allowance) in get_quota_allowances(pool, config, app).await? {
+    let moved_in = moved_usage(pool, app, prefix, new_prefix, &quota_prefix).await?;
+    let moved_out = moved_usage(pool, app, prefix, prefix, &quota_prefix).await?;
+    allowance.check(
+      moved_in.0.saturating_sub(moved_out.0),
+      moved_in.1.saturating_sub(moved_out.1),
+    )?;
+  }
+  Ok(())
+}
+
+/// the number and size of the objects under `prefix` that fall under `quota_prefix` once
+/// `prefix` is replaced by `new_prefix`
+async fn moved_usage(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  prefix: &str,
+  new_prefix: &str,
+  quota_prefix: &str,
+) -> sqlx::Result<(u64, u64)> {
+  let new_prefix = new_prefix.trim_start_matches('/');
+  let covered_prefix = if new_prefix.starts_with(quota_prefix) {
+    prefix.to_owned()
+  } else if let Some(rest) = quota_prefix.strip_prefix(new_prefix) {
+    format!("{}{}", prefix, rest)
+  } else {
+    return Ok((0, 0));
+  };
+  let usage = repository::object::get_object_usage(pool, app, &covered_prefix).await?;
+  Ok((usage.objects.max(0) as u64, usage.bytes.max(0) as u64))
+}
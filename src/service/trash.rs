@@ -0,0 +1,99 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+  core::{config::Config, database::run_transaction},
+  repository::{self, object::ObjectRow, object_write::DELETE_OPERATION, trash::TrashedObjectRow},
+  service,
+  storage::StorageBackend,
+};
+
+/// moves the trashed object back to its path, the caller must make sure the path is free
+pub async fn restore_object(
+  pool: &sqlx::AnyPool,
+  app: i64,
+  object_id: i64,
+) -> sqlx::Result<Option<ObjectRow>> {
+  run_transaction(pool, move |transaction| {
+    Box::pin(
+      async move { repository::trash::restore_trashed_object(transaction, app, object_id).await },
+    )
+  })
+  .await
+}
+
+/// permanently removes a trashed object and its bytes
+pub async fn purge_object(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  app: i64,
+  object_id: i64,
+) -> sqlx::Result<Option<TrashedObjectRow>> {
+  let purged = run_transaction(pool, move |transaction| {
+    Box::pin(async move {
+      let trashed_row =
+        match repository::trash::delete_trashed_object(transaction, app, object_id).await? {
+          Some(trashed_row) => trashed_row,
+          None => return Ok(None),
+        };
+      let write_row = repository::object_write::create_object_write(
+        transaction,
+        object_id,
+        DELETE_OPERATION,
+        trashed_row.size,
+      )
+      .await?;
+      Ok(Some((trashed_row, write_row.id)))
+    })
+  })
+  .await?;
+  let (trashed_row, write_id) = match purged {
+    Some(purged) => purged,
+    None => return Ok(None),
+  };
+  service::object::finish_delete(pool, storage, object_id, write_id).await?;
+  Ok(Some(trashed_row))
+}
+
+/// permanently removes objects that have been in the trash longer than `trash.retention` seconds,
+/// returns the number of objects purged
+pub async fn purge_expired(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  config: &Config,
+) -> sqlx::Result<usize> {
+  let trashed_before = chrono::Utc::now().timestamp() - config.trash.retention as i64;
+  let expired = repository::trash::get_expired_trashed_objects(pool, trashed_before).await?;
+  let mut purged = 0;
+  for trashed_row in &expired {
+    if purge_object(pool, storage, trashed_row.app, trashed_row.id)
+      .await?
+      .is_some()
+    {
+      purged += 1;
+    }
+  }
+  Ok(purged)
+}
+
+/// runs `purge_expired` every `trash.purge_interval` seconds until cancelled
+pub async fn purge_task(
+  pool: sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: Arc<Config>,
+  cancellation_token: CancellationToken,
+) {
+  let mut interval = tokio::time::interval(Duration::from_secs(config.trash.purge_interval.max(1)));
+  loop {
+    tokio::select! {
+      _ = cancellation_token.cancelled() => break,
+      _ = interval.tick() => {}
+    }
+    match purge_expired(&pool, storage.as_ref(), config.as_ref()).await {
+      Ok(0) => {}
+      Ok(purged) => log::info!("Purged {} objects from the trash", purged),
+      Err(err) => log::error!("Error purging the trash: {}", err),
+    }
+  }
+}
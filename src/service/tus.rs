@@ -0,0 +1,165 @@
+use std::{fmt, io, sync::Arc};
+
+use axum::body::Bytes;
+use futures_util::{Stream, StreamExt};
+
+use crate::{
+  core::{checksum::Hasher, config::Config},
+  repository::{self, object::ObjectRow},
+  service,
+  storage::StorageBackend,
+};
+
+pub const CHECKSUM_ALGORITHMS: &[&str] = &["sha256", "md5", "crc32c"];
+
+#[derive(Debug)]
+pub enum TusError {
+  OffsetMismatch,
+  LengthExceeded,
+  ChecksumMismatch,
+  Io(io::Error),
+  Database(sqlx::Error),
+}
+
+impl fmt::Display for TusError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::OffsetMismatch => write!(f, "upload offset does not match the object size"),
+      Self::LengthExceeded => write!(f, "upload is larger than its declared length"),
+      Self::ChecksumMismatch => write!(f, "upload checksum does not match"),
+      Self::Io(err) => write!(f, "{}", err),
+      Self::Database(err) => write!(f, "{}", err),
+    }
+  }
+}
+
+impl From<io::Error> for TusError {
+  fn from(err: io::Error) -> Self {
+    Self::Io(err)
+  }
+}
+
+impl From<sqlx::Error> for TusError {
+  fn from(err: sqlx::Error) -> Self {
+    Self::Database(err)
+  }
+}
+
+/// an `Upload-Checksum` of the bytes in a single PATCH request
+pub struct UploadChecksum {
+  pub algorithm: String,
+  pub digest: Vec<u8>,
+}
+
+impl UploadChecksum {
+  fn matches(&self, hasher: &Hasher) -> bool {
+    let checksums = hasher.checksums();
+    let expected = hex::encode(&self.digest);
+    match self.algorithm.as_str() {
+      "sha256" => checksums.sha256 == expected,
+      "md5" => checksums.md5 == expected,
+      "crc32c" => checksums.crc32c == expected,
+      _ => false,
+    }
+  }
+}
+
+pub async fn create_upload(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  app: i64,
+  path: String,
+  kind: Option<String>,
+  length: u64,
+  metadata: Option<String>,
+) -> sqlx::Result<ObjectRow> {
+  let object_row = service::object::create_object(pool, storage.clone(), app, path, kind).await?;
+  if let Err(err) =
+    repository::tus::create_tus_upload(pool, object_row.id, length as i64, metadata).await
+  {
+    service::object::discard_object(pool, storage, object_row.id).await?;
+    return Err(err);
+  }
+  Ok(object_row)
+}
+
+/// appends `stream` at `offset` and returns the new offset, bytes received before the client
+/// disconnects are kept unless a checksum was given since they can no longer be verified
+#[allow(clippy::too_many_arguments)]
+pub async fn append_upload<S>(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  object_row: &ObjectRow,
+  length: u64,
+  offset: u64,
+  checksum: Option<UploadChecksum>,
+  config: &Config,
+  bypass_governance: bool,
+  stream: S,
+) -> Result<u64, TusError>
+where
+  S: Stream<Item = io::Result<Bytes>> + Send,
+{
+  let mut content_hasher = Hasher::new();
+  let mut append = service::object::open_append(
+    pool,
+    storage.as_ref(),
+    config,
+    object_row.id,
+    bypass_governance,
+  )
+  .await?;
+  // checked while holding the append's lock so concurrent requests cannot both pass
+  if offset != append.offset() {
+    service::object::abort_append(pool, storage.as_ref(), append).await?;
+    return Err(TusError::OffsetMismatch);
+  }
+
+  let mut stream = Box::pin(stream);
+  let mut received = 0u64;
+  let mut interrupted = None;
+  while let Some(bytes) = stream.next().await {
+    let bytes = match bytes {
+      Ok(bytes) => bytes,
+      Err(err) => {
+        interrupted = Some(err);
+        break;
+      }
+    };
+    received += bytes.len() as u64;
+    if offset + received > length {
+      service::object::abort_append(pool, storage.as_ref(), append).await?;
+      return Err(TusError::LengthExceeded);
+    }
+    if checksum.is_some() {
+      content_hasher.update(&bytes);
+    }
+    if let Err(err) = service::object::append_object(pool, &mut append, bytes).await {
+      service::object::abort_append(pool, storage.as_ref(), append).await?;
+      return Err(err.into());
+    }
+  }
+
+  if let Some(checksum) = checksum {
+    if let Some(err) = interrupted {
+      service::object::abort_append(pool, storage.as_ref(), append).await?;
+      return Err(err.into());
+    }
+    if !checksum.matches(&content_hasher) {
+      service::object::abort_append(pool, storage.as_ref(), append).await?;
+      return Err(TusError::ChecksumMismatch);
+    }
+  }
+
+  let object_row = service::object::finish_append(pool, append)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+  let offset = object_row.size as u64;
+  if offset == length {
+    repository::tus::delete_tus_upload(pool, object_row.id).await?;
+  }
+  match interrupted {
+    Some(err) => Err(err.into()),
+    None => Ok(offset),
+  }
+}
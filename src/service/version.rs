@@ -0,0 +1,102 @@
+use std::{io, sync::Arc};
+
+use crate::{
+  core::{config::Config, database::run_transaction},
+  repository::{self, object::ObjectRow, version::ObjectVersionRow},
+  service,
+  storage::StorageBackend,
+};
+
+/// whether overwrites and deletes of `path` in `app` keep the previous state as a version
+pub fn is_versioned(config: &Config, app: i64, path: &str) -> bool {
+  let path = path.trim_start_matches('/');
+  config.versioning.iter().any(|versioning| {
+    versioning.app == app && path.starts_with(versioning.prefix.trim_start_matches('/'))
+  })
+}
+
+/// keeps the current bytes and metadata of `object_row` as the next version of its path when it
+/// is versioned, the caller must hold the object's lock
+pub async fn save_version(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  config: &Config,
+  object_row: &ObjectRow,
+) -> sqlx::Result<Option<ObjectVersionRow>> {
+  if !is_versioned(config, object_row.app, &object_row.path) {
+    return Ok(None);
+  }
+  let object_id = object_row.id;
+  let object_row = object_row.clone();
+  let version_row = run_transaction(pool, move |transaction| {
+    Box::pin(
+      async move { repository::version::create_object_version(transaction, &object_row).await },
+    )
+  })
+  .await?;
+  if let Err(err) = storage.copy(object_id, version_row.storage_id()).await {
+    repository::version::delete_object_version(pool, version_row.app, version_row.id).await?;
+    return Err(err.into());
+  }
+  Ok(Some(version_row))
+}
+
+/// writes the version back to its path, the current contents are kept as a version themselves
+pub async fn restore_version(
+  pool: &sqlx::AnyPool,
+  storage: Arc<dyn StorageBackend>,
+  config: &Config,
+  version_row: &ObjectVersionRow,
+  bypass_governance: bool,
+) -> sqlx::Result<ObjectRow> {
+  service::object::copy_object(
+    pool,
+    storage,
+    config,
+    &version_row.source_object_row(),
+    version_row.path.clone(),
+    None,
+    bypass_governance,
+  )
+  .await
+}
+
+/// permanently removes a version and its bytes
+pub async fn purge_version(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  app: i64,
+  version_id: i64,
+) -> sqlx::Result<Option<ObjectVersionRow>> {
+  let version_row = match repository::version::delete_object_version(pool, app, version_id).await? {
+    Some(version_row) => version_row,
+    None => return Ok(None),
+  };
+  delete_version_storage(storage, &version_row).await?;
+  Ok(Some(version_row))
+}
+
+/// permanently removes every version of `path` and returns how many there were
+pub async fn purge_versions(
+  pool: &sqlx::AnyPool,
+  storage: &dyn StorageBackend,
+  app: i64,
+  path: &str,
+) -> sqlx::Result<usize> {
+  let version_rows = repository::version::delete_object_versions(pool, app, path).await?;
+  for version_row in &version_rows {
+    delete_version_storage(storage, version_row).await?;
+  }
+  Ok(version_rows.len())
+}
+
+async fn delete_version_storage(
+  storage: &dyn StorageBackend,
+  version_row: &ObjectVersionRow,
+) -> io::Result<()> {
+  match storage.delete(version_row.storage_id()).await {
+    Ok(_) => Ok(()),
+    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+    Err(err) => Err(err),
+  }
+}
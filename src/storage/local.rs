@@ -0,0 +1,283 @@
+use std::{
+  io::{self, SeekFrom},
+  ops::Range,
+  path::{Path, PathBuf},
+};
+
+use tokio::{
+  fs,
+  io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+};
+
+use super::{
+  ObjectReader, ObjectStat, ObjectWriter, StorageBackend, StorageFuture, UPLOADS_PREFIX,
+};
+
+/// replacements are written here and renamed over the object once they are durable
+pub const STAGING_DIR: &str = ".staging";
+
+pub struct LocalStorage {
+  objects_dir: PathBuf,
+}
+
+impl LocalStorage {
+  pub fn new(objects_dir: impl AsRef<Path>) -> Self {
+    Self {
+      objects_dir: objects_dir.as_ref().to_owned(),
+    }
+  }
+
+  fn object_path(&self, id: i64) -> PathBuf {
+    self.objects_dir.join(id.to_string())
+  }
+
+  fn staging_dir(&self) -> PathBuf {
+    self.objects_dir.join(STAGING_DIR)
+  }
+
+  fn upload_dir(&self, upload_id: &str) -> PathBuf {
+    self.objects_dir.join(UPLOADS_PREFIX).join(upload_id)
+  }
+
+  /// a staging file unique to one write so concurrent replacements never share a file
+  async fn staging_path(&self, id: i64) -> io::Result<PathBuf> {
+    let staging_dir = self.staging_dir();
+    fs::create_dir_all(&staging_dir).await?;
+    Ok(staging_dir.join(format!("{}.{}", id, uuid::Uuid::new_v4().simple())))
+  }
+}
+
+impl StorageBackend for LocalStorage {
+  fn create(&self, id: i64) -> StorageFuture<'_, ()> {
+    Box::pin(async move {
+      let object = fs::File::create(self.object_path(id)).await?;
+      object.sync_all().await?;
+      sync_dir(&self.objects_dir).await
+    })
+  }
+
+  fn open_read(&self, id: i64) -> StorageFuture<'_, ObjectReader> {
+    Box::pin(async move {
+      let object = fs::OpenOptions::new()
+        .create(false)
+        .read(true)
+        .open(self.object_path(id))
+        .await?;
+      Ok(Box::pin(object) as ObjectReader)
+    })
+  }
+
+  fn open_read_range(&self, id: i64, range: Range<u64>) -> StorageFuture<'_, ObjectReader> {
+    Box::pin(async move {
+      let mut object = fs::OpenOptions::new()
+        .create(false)
+        .read(true)
+        .open(self.object_path(id))
+        .await?;
+      object.seek(SeekFrom::Start(range.start)).await?;
+      let length = range.end.saturating_sub(range.start);
+      Ok(Box::pin(object.take(length)) as ObjectReader)
+    })
+  }
+
+  fn open_append(&self, id: i64) -> StorageFuture<'_, Box<dyn ObjectWriter>> {
+    Box::pin(async move {
+      let object = fs::OpenOptions::new()
+        .create(false)
+        .read(false)
+        .append(true)
+        .open(self.object_path(id))
+        .await?;
+      let start = object.metadata().await?.len();
+      Ok(Box::new(LocalObjectWriter { object, start }) as Box<dyn ObjectWriter>)
+    })
+  }
+
+  fn open_replace(&self, id: i64) -> StorageFuture<'_, Box<dyn ObjectWriter>> {
+    Box::pin(async move {
+      let staging_path = self.staging_path(id).await?;
+      let object = fs::File::create(&staging_path).await?;
+      Ok(Box::new(LocalReplaceWriter {
+        object,
+        staging_path,
+        object_path: self.object_path(id),
+        object_dir: self.objects_dir.clone(),
+      }) as Box<dyn ObjectWriter>)
+    })
+  }
+
+  fn stat(&self, id: i64) -> StorageFuture<'_, ObjectStat> {
+    Box::pin(async move {
+      let metadata = fs::metadata(self.object_path(id)).await?;
+      Ok(ObjectStat {
+        size: metadata.len(),
+        modified: metadata.modified().ok(),
+      })
+    })
+  }
+
+  fn copy(&self, from_id: i64, to_id: i64) -> StorageFuture<'_, ()> {
+    Box::pin(async move {
+      let staging_path = self.staging_path(to_id).await?;
+      // fs::copy uses copy_file_range on linux which clones the blocks on filesystems that support
+      // reflinks and otherwise copies them in the kernel
+      if let Err(err) = fs::copy(self.object_path(from_id), &staging_path).await {
+        remove_file(&staging_path).await?;
+        return Err(err);
+      }
+      fs::File::open(&staging_path).await?.sync_all().await?;
+      fs::rename(&staging_path, self.object_path(to_id)).await?;
+      sync_dir(&self.objects_dir).await
+    })
+  }
+
+  fn truncate(&self, id: i64, size: u64) -> StorageFuture<'_, ()> {
+    Box::pin(async move {
+      let object = fs::OpenOptions::new()
+        .write(true)
+        .open(self.object_path(id))
+        .await?;
+      object.set_len(size).await?;
+      object.sync_all().await
+    })
+  }
+
+  fn delete(&self, id: i64) -> StorageFuture<'_, ()> {
+    Box::pin(async move {
+      fs::remove_file(self.object_path(id)).await?;
+      sync_dir(&self.objects_dir).await
+    })
+  }
+
+  fn recover(&self) -> StorageFuture<'_, ()> {
+    Box::pin(remove_dir(self.staging_dir()))
+  }
+
+  fn open_replace_part(
+    &self,
+    upload_id: &str,
+    part_number: u32,
+  ) -> StorageFuture<'_, Box<dyn ObjectWriter>> {
+    let upload_dir = self.upload_dir(upload_id);
+    Box::pin(async move {
+      fs::create_dir_all(&upload_dir).await?;
+      // each write stages its own file so parallel uploads of the same part never interleave
+      let staging_path =
+        upload_dir.join(format!("{}.{}", part_number, uuid::Uuid::new_v4().simple()));
+      let object = fs::File::create(&staging_path).await?;
+      Ok(Box::new(LocalReplaceWriter {
+        object,
+        staging_path,
+        object_path: upload_dir.join(part_number.to_string()),
+        object_dir: upload_dir,
+      }) as Box<dyn ObjectWriter>)
+    })
+  }
+
+  fn open_read_part(&self, upload_id: &str, part_number: u32) -> StorageFuture<'_, ObjectReader> {
+    let part_path = self.upload_dir(upload_id).join(part_number.to_string());
+    Box::pin(async move { Ok(Box::pin(fs::File::open(part_path).await?) as ObjectReader) })
+  }
+
+  fn delete_parts(&self, upload_id: &str) -> StorageFuture<'_, ()> {
+    Box::pin(remove_dir(self.upload_dir(upload_id)))
+  }
+
+  fn list_part_uploads(&self) -> StorageFuture<'_, Vec<String>> {
+    Box::pin(async move {
+      let mut entries = match fs::read_dir(self.objects_dir.join(UPLOADS_PREFIX)).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+      };
+      let mut upload_ids = Vec::new();
+      while let Some(entry) = entries.next_entry().await? {
+        upload_ids.push(entry.file_name().to_string_lossy().into_owned());
+      }
+      Ok(upload_ids)
+    })
+  }
+}
+
+struct LocalObjectWriter {
+  object: fs::File,
+  start: u64,
+}
+
+impl ObjectWriter for LocalObjectWriter {
+  fn write<'a>(&'a mut self, bytes: &'a [u8]) -> StorageFuture<'a, ()> {
+    Box::pin(async move { self.object.write_all(bytes).await })
+  }
+
+  fn finish(mut self: Box<Self>) -> StorageFuture<'static, u64> {
+    Box::pin(async move {
+      self.object.flush().await?;
+      self.object.sync_data().await?;
+      Ok(self.object.metadata().await?.len())
+    })
+  }
+
+  fn abort(self: Box<Self>) -> StorageFuture<'static, ()> {
+    Box::pin(async move { self.object.set_len(self.start).await })
+  }
+}
+
+struct LocalReplaceWriter {
+  object: fs::File,
+  staging_path: PathBuf,
+  object_path: PathBuf,
+  /// the directory holding `object_path`, synced once the rename is done
+  object_dir: PathBuf,
+}
+
+impl ObjectWriter for LocalReplaceWriter {
+  fn write<'a>(&'a mut self, bytes: &'a [u8]) -> StorageFuture<'a, ()> {
+    Box::pin(async move { self.object.write_all(bytes).await })
+  }
+
+  fn finish(mut self: Box<Self>) -> StorageFuture<'static, u64> {
+    Box::pin(async move {
+      self.object.flush().await?;
+      self.object.sync_all().await?;
+      let size = self.object.metadata().await?.len();
+      drop(self.object);
+      fs::rename(&self.staging_path, &self.object_path).await?;
+      sync_dir(&self.object_dir).await?;
+      Ok(size)
+    })
+  }
+
+  fn abort(self: Box<Self>) -> StorageFuture<'static, ()> {
+    Box::pin(async move {
+      drop(self.object);
+      remove_file(&self.staging_path).await
+    })
+  }
+}
+
+async fn remove_file(path: &Path) -> io::Result<()> {
+  match fs::remove_file(path).await {
+    Ok(_) => Ok(()),
+    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+    Err(err) => Err(err),
+  }
+}
+
+async fn remove_dir(path: PathBuf) -> io::Result<()> {
+  match fs::remove_dir_all(path).await {
+    Ok(_) => Ok(()),
+    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+    Err(err) => Err(err),
+  }
+}
+
+/// makes renames, creations and removals in `dir` durable
+#[cfg(unix)]
+async fn sync_dir(dir: &Path) -> io::Result<()> {
+  fs::File::open(dir).await?.sync_all().await
+}
+
+#[cfg(not(unix))]
+async fn sync_dir(_dir: &Path) -> io::Result<()> {
+  Ok(())
+}
@@ -0,0 +1,272 @@
+use std::{io, ops::Range, sync::Arc, time::SystemTime};
+
+use dashmap::DashMap;
+
+use super::{ObjectReader, ObjectStat, ObjectWriter, StorageBackend, StorageFuture};
+
+struct MemoryObject {
+  bytes: Vec<u8>,
+  modified: SystemTime,
+}
+
+impl Default for MemoryObject {
+  fn default() -> Self {
+    Self {
+      bytes: Vec::new(),
+      modified: SystemTime::now(),
+    }
+  }
+}
+
+#[derive(Default, Clone)]
+pub struct MemoryStorage {
+  objects: Arc<DashMap<i64, MemoryObject>>,
+  parts: Arc<DashMap<(String, u32), Vec<u8>>>,
+}
+
+impl MemoryStorage {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+fn not_found(id: i64) -> io::Error {
+  io::Error::new(io::ErrorKind::NotFound, format!("object {} not found", id))
+}
+
+impl StorageBackend for MemoryStorage {
+  fn create(&self, id: i64) -> StorageFuture<'_, ()> {
+    self.objects.insert(id, MemoryObject::default());
+    Box::pin(async { Ok(()) })
+  }
+
+  fn open_read(&self, id: i64) -> StorageFuture<'_, ObjectReader> {
+    let result = match self.objects.get(&id) {
+      Some(object) => Ok(Box::pin(io::Cursor::new(object.bytes.clone())) as ObjectReader),
+      None => Err(not_found(id)),
+    };
+    Box::pin(async move { result })
+  }
+
+  fn open_read_range(&self, id: i64, range: Range<u64>) -> StorageFuture<'_, ObjectReader> {
+    let result = match self.objects.get(&id) {
+      Some(object) => {
+        let end = (range.end as usize).min(object.bytes.len());
+        let start = (range.start as usize).min(end);
+        Ok(Box::pin(io::Cursor::new(object.bytes[start..end].to_vec())) as ObjectReader)
+      }
+      None => Err(not_found(id)),
+    };
+    Box::pin(async move { result })
+  }
+
+  fn open_append(&self, id: i64) -> StorageFuture<'_, Box<dyn ObjectWriter>> {
+    let result = match self.objects.get(&id) {
+      Some(object) => Ok(Box::new(MemoryObjectWriter {
+        objects: self.objects.clone(),
+        id,
+        start: object.bytes.len(),
+      }) as Box<dyn ObjectWriter>),
+      None => Err(not_found(id)),
+    };
+    Box::pin(async move { result })
+  }
+
+  fn open_replace(&self, id: i64) -> StorageFuture<'_, Box<dyn ObjectWriter>> {
+    let writer = Box::new(MemoryReplaceWriter {
+      objects: self.objects.clone(),
+      id,
+      bytes: Vec::new(),
+    }) as Box<dyn ObjectWriter>;
+    Box::pin(async move { Ok(writer) })
+  }
+
+  fn stat(&self, id: i64) -> StorageFuture<'_, ObjectStat> {
+    let result = match self.objects.get(&id) {
+      Some(object) => Ok(ObjectStat {
+        size: object.bytes.len() as u64,
+        modified: Some(object.modified),
+      }),
+      None => Err(not_found(id)),
+    };
+    Box::pin(async move { result })
+  }
+
+  fn copy(&self, from_id: i64, to_id: i64) -> StorageFuture<'_, ()> {
+    let result = match self
+      .objects
+      .get(&from_id)
+      .map(|object| object.bytes.clone())
+    {
+      Some(bytes) => {
+        self.objects.insert(
+          to_id,
+          MemoryObject {
+            bytes,
+            modified: SystemTime::now(),
+          },
+        );
+        Ok(())
+      }
+      None => Err(not_found(from_id)),
+    };
+    Box::pin(async move { result })
+  }
+
+  fn truncate(&self, id: i64, size: u64) -> StorageFuture<'_, ()> {
+    let result = match self.objects.get_mut(&id) {
+      Some(mut object) => {
+        object.bytes.truncate(size as usize);
+        object.modified = SystemTime::now();
+        Ok(())
+      }
+      None => Err(not_found(id)),
+    };
+    Box::pin(async move { result })
+  }
+
+  fn delete(&self, id: i64) -> StorageFuture<'_, ()> {
+    let result = match self.objects.remove(&id) {
+      Some(_) => Ok(()),
+      None => Err(not_found(id)),
+    };
+    Box::pin(async move { result })
+  }
+
+  fn recover(&self) -> StorageFuture<'_, ()> {
+    Box::pin(async { Ok(()) })
+  }
+
+  fn open_replace_part(
+    &self,
+    upload_id: &str,
+    part_number: u32,
+  ) -> StorageFuture<'_, Box<dyn ObjectWriter>> {
+    let writer = Box::new(MemoryPartWriter {
+      parts: self.parts.clone(),
+      key: (upload_id.to_owned(), part_number),
+      bytes: Vec::new(),
+    }) as Box<dyn ObjectWriter>;
+    Box::pin(async move { Ok(writer) })
+  }
+
+  fn open_read_part(&self, upload_id: &str, part_number: u32) -> StorageFuture<'_, ObjectReader> {
+    let result = match self.parts.get(&(upload_id.to_owned(), part_number)) {
+      Some(part) => Ok(Box::pin(io::Cursor::new(part.clone())) as ObjectReader),
+      None => Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("part {} of upload {} not found", part_number, upload_id),
+      )),
+    };
+    Box::pin(async move { result })
+  }
+
+  fn delete_parts(&self, upload_id: &str) -> StorageFuture<'_, ()> {
+    self
+      .parts
+      .retain(|(part_upload_id, _), _| part_upload_id != upload_id);
+    Box::pin(async { Ok(()) })
+  }
+
+  fn list_part_uploads(&self) -> StorageFuture<'_, Vec<String>> {
+    let mut upload_ids = self
+      .parts
+      .iter()
+      .map(|part| part.key().0.clone())
+      .collect::<Vec<_>>();
+    upload_ids.sort();
+    upload_ids.dedup();
+    Box::pin(async move { Ok(upload_ids) })
+  }
+}
+
+struct MemoryObjectWriter {
+  objects: Arc<DashMap<i64, MemoryObject>>,
+  id: i64,
+  start: usize,
+}
+
+impl ObjectWriter for MemoryObjectWriter {
+  fn write<'a>(&'a mut self, bytes: &'a [u8]) -> StorageFuture<'a, ()> {
+    let result = match self.objects.get_mut(&self.id) {
+      Some(mut object) => {
+        object.bytes.extend_from_slice(bytes);
+        object.modified = SystemTime::now();
+        Ok(())
+      }
+      None => Err(not_found(self.id)),
+    };
+    Box::pin(async move { result })
+  }
+
+  fn finish(self: Box<Self>) -> StorageFuture<'static, u64> {
+    let result = match self.objects.get(&self.id) {
+      Some(object) => Ok(object.bytes.len() as u64),
+      None => Err(not_found(self.id)),
+    };
+    Box::pin(async move { result })
+  }
+
+  fn abort(self: Box<Self>) -> StorageFuture<'static, ()> {
+    let result = match self.objects.get_mut(&self.id) {
+      Some(mut object) => {
+        object.bytes.truncate(self.start);
+        Ok(())
+      }
+      None => Err(not_found(self.id)),
+    };
+    Box::pin(async move { result })
+  }
+}
+
+struct MemoryReplaceWriter {
+  objects: Arc<DashMap<i64, MemoryObject>>,
+  id: i64,
+  bytes: Vec<u8>,
+}
+
+impl ObjectWriter for MemoryReplaceWriter {
+  fn write<'a>(&'a mut self, bytes: &'a [u8]) -> StorageFuture<'a, ()> {
+    self.bytes.extend_from_slice(bytes);
+    Box::pin(async { Ok(()) })
+  }
+
+  fn finish(self: Box<Self>) -> StorageFuture<'static, u64> {
+    let size = self.bytes.len() as u64;
+    self.objects.insert(
+      self.id,
+      MemoryObject {
+        bytes: self.bytes,
+        modified: SystemTime::now(),
+      },
+    );
+    Box::pin(async move { Ok(size) })
+  }
+
+  fn abort(self: Box<Self>) -> StorageFuture<'static, ()> {
+    Box::pin(async { Ok(()) })
+  }
+}
+
+struct MemoryPartWriter {
+  parts: Arc<DashMap<(String, u32), Vec<u8>>>,
+  key: (String, u32),
+  bytes: Vec<u8>,
+}
+
+impl ObjectWriter for MemoryPartWriter {
+  fn write<'a>(&'a mut self, bytes: &'a [u8]) -> StorageFuture<'a, ()> {
+    self.bytes.extend_from_slice(bytes);
+    Box::pin(async { Ok(()) })
+  }
+
+  fn finish(self: Box<Self>) -> StorageFuture<'static, u64> {
+    let size = self.bytes.len() as u64;
+    self.parts.insert(self.key, self.bytes);
+    Box::pin(async move { Ok(size) })
+  }
+
+  fn abort(self: Box<Self>) -> StorageFuture<'static, ()> {
+    Box::pin(async { Ok(()) })
+  }
+}
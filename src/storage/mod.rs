@@ -0,0 +1,85 @@
+pub mod local;
+pub mod memory;
+pub mod s3;
+
+use std::{future::Future, io, ops::Range, pin::Pin, sync::Arc, time::SystemTime};
+
+use local::LocalStorage;
+use s3::S3Storage;
+use tokio::io::AsyncRead;
+
+use crate::core::config::{Config, StorageConfig};
+
+/// multipart upload parts are staged under this prefix, which object ids never start with
+pub const UPLOADS_PREFIX: &str = ".uploads";
+
+pub type StorageFuture<'a, T> = Pin<Box<dyn Send + Future<Output = io::Result<T>> + 'a>>;
+
+pub type ObjectReader = Pin<Box<dyn AsyncRead + Send>>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectStat {
+  pub size: u64,
+  pub modified: Option<SystemTime>,
+}
+
+pub trait ObjectWriter: Send {
+  fn write<'a>(&'a mut self, bytes: &'a [u8]) -> StorageFuture<'a, ()>;
+
+  /// flushes everything written so far and returns the total size of the object
+  fn finish(self: Box<Self>) -> StorageFuture<'static, u64>;
+
+  /// gives up on the write and discards every byte written through this writer
+  fn abort(self: Box<Self>) -> StorageFuture<'static, ()>;
+}
+
+pub trait StorageBackend: Send + Sync {
+  fn create(&self, id: i64) -> StorageFuture<'_, ()>;
+
+  fn open_read(&self, id: i64) -> StorageFuture<'_, ObjectReader>;
+
+  fn open_read_range(&self, id: i64, range: Range<u64>) -> StorageFuture<'_, ObjectReader>;
+
+  fn open_append(&self, id: i64) -> StorageFuture<'_, Box<dyn ObjectWriter>>;
+
+  /// opens a writer for new contents of the object, nothing written is visible until `finish`
+  /// durably and atomically replaces the old contents
+  fn open_replace(&self, id: i64) -> StorageFuture<'_, Box<dyn ObjectWriter>>;
+
+  fn stat(&self, id: i64) -> StorageFuture<'_, ObjectStat>;
+
+  /// atomically replaces the contents of `to_id` with those of `from_id`
+  fn copy(&self, from_id: i64, to_id: i64) -> StorageFuture<'_, ()>;
+
+  /// cuts the object down to `size` bytes, used to roll back appends interrupted by a crash
+  fn truncate(&self, id: i64, size: u64) -> StorageFuture<'_, ()>;
+
+  fn delete(&self, id: i64) -> StorageFuture<'_, ()>;
+
+  /// discards replacements left unfinished by a crash, called once at startup
+  fn recover(&self) -> StorageFuture<'_, ()>;
+
+  /// opens a writer for a part of a multipart upload, staged apart from objects under the
+  /// reserved `UPLOADS_PREFIX`, nothing written is visible until `finish` replaces the previous
+  /// contents of the part
+  fn open_replace_part(
+    &self,
+    upload_id: &str,
+    part_number: u32,
+  ) -> StorageFuture<'_, Box<dyn ObjectWriter>>;
+
+  fn open_read_part(&self, upload_id: &str, part_number: u32) -> StorageFuture<'_, ObjectReader>;
+
+  /// removes every part staged for the upload
+  fn delete_parts(&self, upload_id: &str) -> StorageFuture<'_, ()>;
+
+  /// the ids of the uploads that have parts staged, used to find parts of abandoned uploads
+  fn list_part_uploads(&self) -> StorageFuture<'_, Vec<String>>;
+}
+
+pub fn create_storage(config: &Config) -> io::Result<Arc<dyn StorageBackend>> {
+  match &config.storage {
+    StorageConfig::Local => Ok(Arc::new(LocalStorage::new(&config.objects_dir))),
+    StorageConfig::S3(s3_config) => Ok(Arc::new(S3Storage::new(s3_config.clone())?)),
+  }
+}
@@ -0,0 +1,621 @@
+use std::{io, ops::Range, sync::Arc};
+
+use chrono::Utc;
+use http::{header, Method, StatusCode};
+use reqwest::Url;
+use tokio_util::io::StreamReader;
+
+use crate::{
+  core::{
+    config::S3StorageConfig,
+    sigv4::{self, Credentials},
+  },
+  model::s3::xml_values,
+};
+
+use super::{
+  ObjectReader, ObjectStat, ObjectWriter, StorageBackend, StorageFuture, UPLOADS_PREFIX,
+};
+
+/// S3 requires every part but the last of a multipart upload to be at least 5MiB
+pub const PART_SIZE: usize = 8 * 1024 * 1024;
+
+const S3_SERVICE: &str = "s3";
+
+#[derive(Clone)]
+pub struct S3Storage {
+  client: reqwest::Client,
+  config: Arc<S3StorageConfig>,
+  endpoint: Url,
+}
+
+impl S3Storage {
+  pub fn new(config: S3StorageConfig) -> io::Result<Self> {
+    let endpoint = Url::parse(&config.endpoint)
+      .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+    Ok(Self {
+      client: reqwest::Client::new(),
+      config: Arc::new(config),
+      endpoint,
+    })
+  }
+
+  fn object_key(&self, id: i64) -> String {
+    format!("{}{}", self.config.prefix, id)
+  }
+
+  fn part_key(&self, upload_id: &str, part_number: u32) -> String {
+    format!("{}{}", self.upload_key_prefix(upload_id), part_number)
+  }
+
+  fn upload_key_prefix(&self, upload_id: &str) -> String {
+    format!("{}{}/{}/", self.config.prefix, UPLOADS_PREFIX, upload_id)
+  }
+
+  fn copy_source(&self, key: &str) -> String {
+    format!("/{}/{}", self.config.bucket, sigv4::uri_encode(key, false))
+  }
+
+  /// sends a request for `key`, an empty key addresses the bucket itself
+  async fn send(
+    &self,
+    method: Method,
+    key: &str,
+    query: &[(String, String)],
+    headers: &[(String, String)],
+    body: Vec<u8>,
+  ) -> io::Result<reqwest::Response> {
+    let mut url = self.endpoint.clone();
+    let path = if self.config.path_style {
+      format!("/{}/{}", self.config.bucket, key)
+    } else {
+      let host = format!(
+        "{}.{}",
+        self.config.bucket,
+        self.endpoint.host_str().unwrap_or_default()
+      );
+      url.set_host(Some(&host)).map_err(io::Error::other)?;
+      format!("/{}", key)
+    };
+    let canonical_uri = sigv4::uri_encode(&path, false);
+    url.set_path(&canonical_uri);
+    let canonical_query = sigv4::canonical_query_string(query);
+    if !canonical_query.is_empty() {
+      url.set_query(Some(&canonical_query));
+    }
+    let host = match url.port() {
+      Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+      None => url.host_str().unwrap_or_default().to_owned(),
+    };
+
+    let now = Utc::now();
+    let payload_hash = sigv4::sha256_hex(&body);
+    let mut signed_headers = vec![
+      ("host".to_owned(), host),
+      (
+        "x-amz-date".to_owned(),
+        now.format(sigv4::AMZ_DATE_FORMAT).to_string(),
+      ),
+      ("x-amz-content-sha256".to_owned(), payload_hash.clone()),
+    ];
+    signed_headers.extend(headers.iter().cloned());
+    let credentials = Credentials {
+      access_key_id: &self.config.access_key_id,
+      secret_access_key: &self.config.secret_access_key,
+      region: &self.config.region,
+      service: S3_SERVICE,
+    };
+    let authorization = sigv4::sign(
+      &now,
+      &credentials,
+      method.as_str(),
+      &canonical_uri,
+      query,
+      &signed_headers,
+      &payload_hash,
+    );
+
+    let mut request = self
+      .client
+      .request(method, url)
+      .header(header::AUTHORIZATION, authorization)
+      .body(body);
+    for (name, value) in signed_headers.into_iter().skip(1) {
+      request = request.header(name, value);
+    }
+    let response = request.send().await.map_err(io::Error::other)?;
+
+    match response.status() {
+      status if status.is_success() => Ok(response),
+      StatusCode::NOT_FOUND => Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found", key),
+      )),
+      status => {
+        let body = response.text().await.unwrap_or_default();
+        Err(io::Error::other(format!(
+          "S3 request failed with status {}: {}",
+          status, body
+        )))
+      }
+    }
+  }
+
+  async fn read_all(&self, key: &str) -> io::Result<Vec<u8>> {
+    let response = self.send(Method::GET, key, &[], &[], Vec::new()).await?;
+    let bytes = response.bytes().await.map_err(io::Error::other)?;
+    Ok(bytes.to_vec())
+  }
+
+  async fn put(&self, key: &str, body: Vec<u8>) -> io::Result<()> {
+    let _ = self.send(Method::PUT, key, &[], &[], body).await?;
+    Ok(())
+  }
+
+  async fn create_multipart_upload(&self, key: &str) -> io::Result<String> {
+    let response = self
+      .send(
+        Method::POST,
+        key,
+        &[("uploads".to_owned(), String::new())],
+        &[],
+        Vec::new(),
+      )
+      .await?;
+    let body = response.text().await.map_err(io::Error::other)?;
+    xml_values(&body, "UploadId")
+      .into_iter()
+      .next()
+      .ok_or_else(|| io::Error::other("S3 did not return an UploadId for multipart upload"))
+  }
+
+  async fn upload_part(
+    &self,
+    key: &str,
+    upload_id: &str,
+    part_number: usize,
+    body: Vec<u8>,
+  ) -> io::Result<String> {
+    let response = self
+      .send(
+        Method::PUT,
+        key,
+        &part_query(upload_id, part_number),
+        &[],
+        body,
+      )
+      .await?;
+    response
+      .headers()
+      .get(header::ETAG)
+      .and_then(|etag| etag.to_str().ok())
+      .map(str::to_owned)
+      .ok_or_else(|| io::Error::other("S3 did not return an ETag for uploaded part"))
+  }
+
+  async fn upload_part_copy(
+    &self,
+    key: &str,
+    upload_id: &str,
+    part_number: usize,
+  ) -> io::Result<String> {
+    let response = self
+      .send(
+        Method::PUT,
+        key,
+        &part_query(upload_id, part_number),
+        &[("x-amz-copy-source".to_owned(), self.copy_source(key))],
+        Vec::new(),
+      )
+      .await?;
+    let body = response.text().await.map_err(io::Error::other)?;
+    xml_values(&body, "ETag")
+      .into_iter()
+      .next()
+      .ok_or_else(|| io::Error::other("S3 did not return an ETag for copied part"))
+  }
+
+  async fn complete_multipart_upload(
+    &self,
+    key: &str,
+    upload_id: &str,
+    etags: &[String],
+  ) -> io::Result<()> {
+    let mut body = String::from("<CompleteMultipartUpload>");
+    for (index, etag) in etags.iter().enumerate() {
+      body.push_str(&format!(
+        "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+        index + 1,
+        etag
+      ));
+    }
+    body.push_str("</CompleteMultipartUpload>");
+    let response = self
+      .send(
+        Method::POST,
+        key,
+        &[("uploadId".to_owned(), upload_id.to_owned())],
+        &[],
+        body.into_bytes(),
+      )
+      .await?;
+    // S3 can report a failed completion with a 200 status and an error document
+    let body = response.text().await.map_err(io::Error::other)?;
+    if let Some(message) = xml_values(&body, "Error").into_iter().next() {
+      return Err(io::Error::other(format!(
+        "S3 failed to complete multipart upload: {}",
+        message
+      )));
+    }
+    Ok(())
+  }
+
+  async fn abort_multipart_upload(&self, key: &str, upload_id: &str) -> io::Result<()> {
+    let _ = self
+      .send(
+        Method::DELETE,
+        key,
+        &[("uploadId".to_owned(), upload_id.to_owned())],
+        &[],
+        Vec::new(),
+      )
+      .await?;
+    Ok(())
+  }
+
+  /// lists the bucket with ListObjectsV2 and returns the `name` elements of every page, with
+  /// a delimiter S3 groups keys into common prefixes
+  async fn list(
+    &self,
+    prefix: &str,
+    delimiter: Option<&str>,
+    name: &str,
+  ) -> io::Result<Vec<String>> {
+    let mut values = Vec::new();
+    let mut continuation_token = None;
+    loop {
+      let mut query = vec![
+        ("list-type".to_owned(), "2".to_owned()),
+        ("prefix".to_owned(), prefix.to_owned()),
+      ];
+      if let Some(delimiter) = delimiter {
+        query.push(("delimiter".to_owned(), delimiter.to_owned()));
+      }
+      if let Some(token) = continuation_token.take() {
+        query.push(("continuation-token".to_owned(), token));
+      }
+      let response = self.send(Method::GET, "", &query, &[], Vec::new()).await?;
+      let body = response.text().await.map_err(io::Error::other)?;
+      values.extend(xml_values(&body, name));
+      if xml_values(&body, "IsTruncated").first().map(String::as_str) != Some("true") {
+        return Ok(values);
+      }
+      continuation_token = xml_values(&body, "NextContinuationToken")
+        .into_iter()
+        .next();
+      if continuation_token.is_none() {
+        return Ok(values);
+      }
+    }
+  }
+
+  fn writer(
+    &self,
+    key: String,
+    size: u64,
+    buffer: Vec<u8>,
+    copy_existing: bool,
+    written: bool,
+  ) -> Box<dyn ObjectWriter> {
+    Box::new(S3ObjectWriter {
+      storage: self.clone(),
+      key,
+      size,
+      buffer,
+      copy_existing,
+      written,
+      upload: None,
+    })
+  }
+}
+
+impl StorageBackend for S3Storage {
+  fn create(&self, id: i64) -> StorageFuture<'_, ()> {
+    Box::pin(async move { self.put(&self.object_key(id), Vec::new()).await })
+  }
+
+  fn open_read(&self, id: i64) -> StorageFuture<'_, ObjectReader> {
+    Box::pin(async move {
+      let response = self
+        .send(Method::GET, &self.object_key(id), &[], &[], Vec::new())
+        .await?;
+      Ok(response_reader(response))
+    })
+  }
+
+  fn open_read_range(&self, id: i64, range: Range<u64>) -> StorageFuture<'_, ObjectReader> {
+    Box::pin(async move {
+      if range.is_empty() {
+        return Ok(Box::pin(io::Cursor::new(Vec::new())) as ObjectReader);
+      }
+      let range = format!("bytes={}-{}", range.start, range.end - 1);
+      let response = self
+        .send(
+          Method::GET,
+          &self.object_key(id),
+          &[],
+          &[("range".to_owned(), range)],
+          Vec::new(),
+        )
+        .await?;
+      Ok(response_reader(response))
+    })
+  }
+
+  fn open_append(&self, id: i64) -> StorageFuture<'_, Box<dyn ObjectWriter>> {
+    Box::pin(async move {
+      let key = self.object_key(id);
+      let existing_size = self.stat(id).await?.size;
+      // small objects cannot be used as a copied part so they are re-uploaded with the new bytes
+      let (buffer, copy_existing) = if existing_size == 0 {
+        (Vec::new(), false)
+      } else if existing_size < PART_SIZE as u64 {
+        (self.read_all(&key).await?, false)
+      } else {
+        (Vec::new(), true)
+      };
+      Ok(self.writer(key, existing_size, buffer, copy_existing, false))
+    })
+  }
+
+  fn open_replace(&self, id: i64) -> StorageFuture<'_, Box<dyn ObjectWriter>> {
+    // uploads only become visible once they complete, `written` makes an empty replacement
+    // still overwrite the old contents
+    let writer = self.writer(self.object_key(id), 0, Vec::new(), false, true);
+    Box::pin(async move { Ok(writer) })
+  }
+
+  fn stat(&self, id: i64) -> StorageFuture<'_, ObjectStat> {
+    Box::pin(async move {
+      let response = self
+        .send(Method::HEAD, &self.object_key(id), &[], &[], Vec::new())
+        .await?;
+      let headers = response.headers();
+      let size = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or_default();
+      let modified = headers
+        .get(header::LAST_MODIFIED)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| httpdate::parse_http_date(value).ok());
+      Ok(ObjectStat { size, modified })
+    })
+  }
+
+  fn copy(&self, from_id: i64, to_id: i64) -> StorageFuture<'_, ()> {
+    Box::pin(async move {
+      let _ = self
+        .send(
+          Method::PUT,
+          &self.object_key(to_id),
+          &[],
+          &[(
+            "x-amz-copy-source".to_owned(),
+            self.copy_source(&self.object_key(from_id)),
+          )],
+          Vec::new(),
+        )
+        .await?;
+      Ok(())
+    })
+  }
+
+  fn truncate(&self, id: i64, size: u64) -> StorageFuture<'_, ()> {
+    Box::pin(async move {
+      if self.stat(id).await?.size <= size {
+        return Ok(());
+      }
+      let key = self.object_key(id);
+      let mut bytes = self.read_all(&key).await?;
+      bytes.truncate(size as usize);
+      self.put(&key, bytes).await
+    })
+  }
+
+  fn delete(&self, id: i64) -> StorageFuture<'_, ()> {
+    Box::pin(async move {
+      let _ = self
+        .send(Method::DELETE, &self.object_key(id), &[], &[], Vec::new())
+        .await?;
+      Ok(())
+    })
+  }
+
+  /// writes only become visible when they complete so there is nothing to discard, incomplete
+  /// multipart uploads are left to the bucket's lifecycle rules
+  fn recover(&self) -> StorageFuture<'_, ()> {
+    Box::pin(async { Ok(()) })
+  }
+
+  fn open_replace_part(
+    &self,
+    upload_id: &str,
+    part_number: u32,
+  ) -> StorageFuture<'_, Box<dyn ObjectWriter>> {
+    let writer = self.writer(
+      self.part_key(upload_id, part_number),
+      0,
+      Vec::new(),
+      false,
+      true,
+    );
+    Box::pin(async move { Ok(writer) })
+  }
+
+  fn open_read_part(&self, upload_id: &str, part_number: u32) -> StorageFuture<'_, ObjectReader> {
+    let key = self.part_key(upload_id, part_number);
+    Box::pin(async move {
+      let response = self.send(Method::GET, &key, &[], &[], Vec::new()).await?;
+      Ok(response_reader(response))
+    })
+  }
+
+  fn delete_parts(&self, upload_id: &str) -> StorageFuture<'_, ()> {
+    let prefix = self.upload_key_prefix(upload_id);
+    Box::pin(async move {
+      for key in self.list(&prefix, None, "Key").await? {
+        match self.send(Method::DELETE, &key, &[], &[], Vec::new()).await {
+          Ok(_) => {}
+          Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+          Err(err) => return Err(err),
+        }
+      }
+      Ok(())
+    })
+  }
+
+  fn list_part_uploads(&self) -> StorageFuture<'_, Vec<String>> {
+    let prefix = format!("{}{}/", self.config.prefix, UPLOADS_PREFIX);
+    Box::pin(async move {
+      // every upload's parts share a common prefix ending in the upload id
+      let upload_ids = self
+        .list(&prefix, Some("/"), "Prefix")
+        .await?
+        .into_iter()
+        .filter_map(|common_prefix| {
+          common_prefix
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix('/'))
+            .filter(|upload_id| !upload_id.is_empty())
+            .map(str::to_owned)
+        })
+        .collect();
+      Ok(upload_ids)
+    })
+  }
+}
+
+struct MultipartUpload {
+  upload_id: String,
+  etags: Vec<String>,
+}
+
+struct S3ObjectWriter {
+  storage: S3Storage,
+  key: String,
+  size: u64,
+  buffer: Vec<u8>,
+  copy_existing: bool,
+  written: bool,
+  upload: Option<MultipartUpload>,
+}
+
+impl S3ObjectWriter {
+  async fn flush_part(&mut self) -> io::Result<()> {
+    if self.upload.is_none() {
+      let upload_id = self.storage.create_multipart_upload(&self.key).await?;
+      let mut etags = Vec::new();
+      if self.copy_existing {
+        etags.push(
+          self
+            .storage
+            .upload_part_copy(&self.key, &upload_id, 1)
+            .await?,
+        );
+      }
+      self.upload = Some(MultipartUpload { upload_id, etags });
+    }
+    let upload = self.upload.as_mut().expect("multipart upload started");
+    let part = std::mem::take(&mut self.buffer);
+    let etag = self
+      .storage
+      .upload_part(&self.key, &upload.upload_id, upload.etags.len() + 1, part)
+      .await?;
+    upload.etags.push(etag);
+    Ok(())
+  }
+
+  async fn complete(&mut self) -> io::Result<()> {
+    if !self.written {
+      return Ok(());
+    }
+    if self.upload.is_none() && !self.copy_existing {
+      let body = std::mem::take(&mut self.buffer);
+      return self.storage.put(&self.key, body).await;
+    }
+    if !self.buffer.is_empty() || self.upload.is_none() {
+      self.flush_part().await?;
+    }
+    let upload = self.upload.as_ref().expect("multipart upload started");
+    self
+      .storage
+      .complete_multipart_upload(&self.key, &upload.upload_id, &upload.etags)
+      .await
+  }
+
+  async fn abort_upload(&mut self) {
+    if let Some(upload) = self.upload.take() {
+      if let Err(e) = self
+        .storage
+        .abort_multipart_upload(&self.key, &upload.upload_id)
+        .await
+      {
+        log::error!("Error aborting multipart upload: {}", e);
+      }
+    }
+  }
+}
+
+impl ObjectWriter for S3ObjectWriter {
+  fn write<'a>(&'a mut self, bytes: &'a [u8]) -> StorageFuture<'a, ()> {
+    Box::pin(async move {
+      self.buffer.extend_from_slice(bytes);
+      self.size += bytes.len() as u64;
+      self.written = true;
+      if self.buffer.len() >= PART_SIZE {
+        if let Err(e) = self.flush_part().await {
+          self.abort_upload().await;
+          return Err(e);
+        }
+      }
+      Ok(())
+    })
+  }
+
+  fn finish(mut self: Box<Self>) -> StorageFuture<'static, u64> {
+    Box::pin(async move {
+      if let Err(e) = self.complete().await {
+        self.abort_upload().await;
+        return Err(e);
+      }
+      Ok(self.size)
+    })
+  }
+
+  fn abort(mut self: Box<Self>) -> StorageFuture<'static, ()> {
+    Box::pin(async move {
+      self.abort_upload().await;
+      Ok(())
+    })
+  }
+}
+
+fn response_reader(response: reqwest::Response) -> ObjectReader {
+  let stream = futures_util::stream::unfold(response, |mut response| async move {
+    match response.chunk().await {
+      Ok(Some(chunk)) => Some((Ok(chunk), response)),
+      Ok(None) => None,
+      Err(e) => Some((Err(io::Error::other(e)), response)),
+    }
+  });
+  Box::pin(StreamReader::new(stream))
+}
+
+fn part_query(upload_id: &str, part_number: usize) -> Vec<(String, String)> {
+  vec![
+    ("partNumber".to_owned(), part_number.to_string()),
+    ("uploadId".to_owned(), upload_id.to_owned()),
+  ]
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use object_storage::{repository, service};
+use tokio::io::AsyncReadExt;
+
+const APPENDS: usize = 16;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_appends() {
+  let (config, pool, storage) = common::memory_store(serde_json::json!({})).await;
+  let config = Arc::new(config);
+  let object_row =
+    service::object::create_object(&pool, storage.clone(), 1, "log.txt".to_owned(), None)
+      .await
+      .unwrap();
+
+  let handles = (0..APPENDS)
+    .map(|index| {
+      let config = config.clone();
+      let pool = pool.clone();
+      let storage = storage.clone();
+      // every append has its own byte and length so a misplaced write is detectable
+      let bytes = Bytes::from(vec![b'a' + index as u8; index + 1]);
+      tokio::spawn(async move {
+        let mut append =
+          service::object::open_append(&pool, storage.as_ref(), &config, object_row.id, false)
+            .await
+            .unwrap();
+        let offset = append.offset();
+        for chunk in bytes.chunks(3) {
+          service::object::append_object(&pool, &mut append, Bytes::copy_from_slice(chunk))
+            .await
+            .unwrap();
+          tokio::task::yield_now().await;
+        }
+        service::object::finish_append(&pool, append).await.unwrap();
+        (offset, bytes)
+      })
+    })
+    .collect::<Vec<_>>();
+  let mut appends = Vec::with_capacity(APPENDS);
+  for handle in handles {
+    appends.push(handle.await.unwrap());
+  }
+
+  let total = appends.iter().map(|(_, bytes)| bytes.len()).sum::<usize>();
+  appends.sort_by_key(|(offset, _)| *offset);
+  let mut expected_offset = 0;
+  for (offset, bytes) in &appends {
+    assert_eq!(*offset, expected_offset as u64);
+    expected_offset += bytes.len();
+  }
+  assert_eq!(expected_offset, total);
+
+  let mut contents = Vec::new();
+  storage
+    .open_read(object_row.id)
+    .await
+    .unwrap()
+    .read_to_end(&mut contents)
+    .await
+    .unwrap();
+  assert_eq!(contents.len(), total);
+  for (offset, bytes) in &appends {
+    let offset = *offset as usize;
+    assert_eq!(&contents[offset..(offset + bytes.len())], bytes.as_ref());
+  }
+
+  let object_row = repository::object::get_object_by_id(&pool, 1, object_row.id)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(object_row.size, total as i64);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn copy_waits_for_append() {
+  let (config, pool, storage) = common::memory_store(serde_json::json!({})).await;
+  let config = Arc::new(config);
+  let source = common::put(&config, &pool, &storage, "source", b"123")
+    .await
+    .unwrap();
+  let mut append = service::object::open_append(&pool, storage.as_ref(), &config, source.id, false)
+    .await
+    .unwrap();
+  service::object::append_object(&pool, &mut append, Bytes::from_static(b"456"))
+    .await
+    .unwrap();
+
+  let copy = tokio::spawn({
+    let config = config.clone();
+    let pool = pool.clone();
+    let storage = storage.clone();
+    let source = source.clone();
+    async move {
+      service::object::copy_object(
+        &pool,
+        storage,
+        &config,
+        &source,
+        "copy".to_owned(),
+        None,
+        false,
+      )
+      .await
+      .unwrap()
+    }
+  });
+  tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+  assert!(!copy.is_finished());
+  let source = service::object::finish_append(&pool, append)
+    .await
+    .unwrap()
+    .unwrap();
+
+  let copy = copy.await.unwrap();
+  assert_eq!(copy.size, 6);
+  assert_eq!(copy.sha256, source.sha256);
+  let mut contents = Vec::new();
+  storage
+    .open_read(copy.id)
+    .await
+    .unwrap()
+    .read_to_end(&mut contents)
+    .await
+    .unwrap();
+  assert_eq!(contents, b"123456");
+}
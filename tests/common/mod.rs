@@ -0,0 +1,69 @@
+// every test crate includes this module but uses only some of the helpers
+#![allow(dead_code)]
+
+use std::{io, sync::Arc};
+
+use axum::body::Bytes;
+use futures_util::Stream;
+use object_storage::{
+  core::{config::Config, database::init_pool},
+  repository::object::ObjectRow,
+  service,
+  storage::{memory::MemoryStorage, StorageBackend},
+};
+use serde_json::{json, Value};
+
+/// the app objects are written for
+pub const APP: i64 = 1;
+
+/// a config for `settings` backed by a fresh in-memory database, along with its pool
+pub async fn memory_database(settings: Value) -> (Config, sqlx::AnyPool) {
+  sqlx::any::install_default_drivers();
+  // an in-memory database is private to its connection so the pool must keep exactly one
+  let mut config = json!({
+    "database": { "url": "sqlite::memory:", "min_connections": 1, "max_connections": 1 }
+  });
+  if let (Some(config), Value::Object(settings)) = (config.as_object_mut(), settings) {
+    config.extend(settings);
+  }
+  let config_path = std::env::temp_dir().join(format!("config-{}.json", uuid::Uuid::new_v4()));
+  tokio::fs::write(&config_path, config.to_string())
+    .await
+    .unwrap();
+  let config = Config::new(config_path.to_str().unwrap()).await.unwrap();
+  tokio::fs::remove_file(&config_path).await.unwrap();
+  let pool = init_pool(&config).await.unwrap();
+  (config, pool)
+}
+
+/// `memory_database` along with an in-memory storage backend
+pub async fn memory_store(settings: Value) -> (Config, sqlx::AnyPool, Arc<dyn StorageBackend>) {
+  let (config, pool) = memory_database(settings).await;
+  (config, pool, Arc::new(MemoryStorage::new()))
+}
+
+/// a request body of `bytes` in a single chunk
+pub fn body(bytes: &'static [u8]) -> impl Stream<Item = io::Result<Bytes>> + Send {
+  futures_util::stream::iter(vec![Ok(Bytes::from_static(bytes))])
+}
+
+/// creates or replaces the object of `APP` at `path` with `bytes`
+pub async fn put(
+  config: &Config,
+  pool: &sqlx::AnyPool,
+  storage: &Arc<dyn StorageBackend>,
+  path: &str,
+  bytes: &'static [u8],
+) -> sqlx::Result<ObjectRow> {
+  service::object::put_object(
+    pool,
+    storage.clone(),
+    config,
+    APP,
+    path.to_owned(),
+    None,
+    false,
+    body(bytes),
+  )
+  .await
+}
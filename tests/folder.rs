@@ -0,0 +1,30 @@
+mod common;
+
+use common::{put, APP};
+use object_storage::service::{self, object::FolderMove};
+use serde_json::json;
+
+#[tokio::test]
+async fn nested_folder_moves() {
+  let (config, pool, storage) = common::memory_store(json!({})).await;
+  put(&config, &pool, &storage, "a/x", b"1").await.unwrap();
+  put(&config, &pool, &storage, "a/b/x", b"2").await.unwrap();
+
+  for (prefix, new_prefix) in [("a/", "a/b/"), ("a/b/", "a/"), ("a/", "a/")] {
+    let moved = service::object::move_folder(
+      &pool,
+      &config,
+      APP,
+      prefix.to_owned(),
+      new_prefix.to_owned(),
+    )
+    .await
+    .unwrap();
+    assert!(matches!(moved, FolderMove::Nested));
+  }
+
+  let moved = service::object::move_folder(&pool, &config, APP, "a/".to_owned(), "c/".to_owned())
+    .await
+    .unwrap();
+  assert!(matches!(moved, FolderMove::Moved(2)));
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{put, APP};
+use object_storage::{
+  core::config::Config,
+  repository::{self, object::GOVERNANCE_MODE},
+  service::{self, lock::object_locked},
+  storage::StorageBackend,
+};
+use serde_json::json;
+
+async fn lock_store() -> (Config, sqlx::AnyPool, Arc<dyn StorageBackend>) {
+  common::memory_store(json!({ "trash": { "enabled": true } })).await
+}
+
+fn far_future() -> i64 {
+  chrono::Utc::now().timestamp() + 24 * 60 * 60
+}
+
+#[tokio::test]
+async fn legal_hold_blocks_writes_and_deletes() {
+  let (config, pool, storage) = lock_store().await;
+  let object_row = put(&config, &pool, &storage, "held", b"123456")
+    .await
+    .unwrap();
+  repository::object::update_object_legal_hold(&pool, APP, object_row.id, true)
+    .await
+    .unwrap();
+
+  // a legal hold applies even when governance retention is bypassed
+  let err = service::object::put_object(
+    &pool,
+    storage.clone(),
+    &config,
+    APP,
+    "held".to_owned(),
+    None,
+    true,
+    common::body(b"123456"),
+  )
+  .await
+  .err()
+  .unwrap();
+  assert!(object_locked(&err).is_some());
+  let err = service::object::open_append(&pool, storage.as_ref(), &config, object_row.id, true)
+    .await
+    .err()
+    .unwrap();
+  assert!(object_locked(&err).is_some());
+  let err = service::object::delete_object(&pool, storage.clone(), &config, object_row.id, true)
+    .await
+    .err()
+    .unwrap();
+  assert!(object_locked(&err).is_some());
+  let err = service::object::delete_objects(
+    &pool,
+    storage.clone(),
+    &config,
+    std::slice::from_ref(&object_row),
+    true,
+  )
+  .await
+  .err()
+  .unwrap();
+  assert!(object_locked(&err).is_some());
+  assert!(repository::object::get_object_by_path(&pool, APP, "held")
+    .await
+    .unwrap()
+    .is_some());
+}
+
+#[tokio::test]
+async fn trash_keeps_retention() {
+  let (config, pool, storage) = lock_store().await;
+  let object_row = put(&config, &pool, &storage, "retained", b"123456")
+    .await
+    .unwrap();
+  let retain_until = far_future();
+  repository::object::update_object_retention(
+    &pool,
+    APP,
+    object_row.id,
+    Some(GOVERNANCE_MODE),
+    Some(retain_until),
+  )
+  .await
+  .unwrap();
+
+  let err = service::object::delete_object(&pool, storage.clone(), &config, object_row.id, false)
+    .await
+    .err()
+    .unwrap();
+  assert!(object_locked(&err).is_some());
+  service::object::delete_object(&pool, storage.clone(), &config, object_row.id, true)
+    .await
+    .unwrap()
+    .unwrap();
+
+  let trashed_row = repository::trash::get_trashed_object(&pool, APP, object_row.id)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(trashed_row.retention_mode.as_deref(), Some(GOVERNANCE_MODE));
+  assert_eq!(trashed_row.retain_until, Some(retain_until));
+
+  let restored = service::trash::restore_object(&pool, APP, object_row.id)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(restored.retention_mode.as_deref(), Some(GOVERNANCE_MODE));
+  assert_eq!(restored.retain_until, Some(retain_until));
+  let err = put(&config, &pool, &storage, "retained", b"123456")
+    .await
+    .err()
+    .unwrap();
+  assert!(object_locked(&err).is_some());
+}
@@ -0,0 +1,293 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use common::{put, APP};
+use object_storage::{
+  core::config::Config,
+  repository,
+  service::{
+    self,
+    multipart::MultipartError,
+    object::{FolderMove, ObjectCopy},
+    quota::{quota_exceeded, MAX_BYTES, MAX_OBJECTS},
+  },
+  storage::StorageBackend,
+};
+use serde_json::json;
+
+/// at most 2 objects and 10 bytes under `limited/`, nothing else is limited
+async fn quota_store() -> (Config, sqlx::AnyPool, Arc<dyn StorageBackend>) {
+  common::memory_store(json!({
+    "quotas": [{ "app": APP, "prefix": "limited/", "max_bytes": 10, "max_objects": 2 }]
+  }))
+  .await
+}
+
+fn assert_quota_exceeded(err: &sqlx::Error, name: &str) {
+  let quota_exceeded = quota_exceeded(err).expect("a quota error");
+  assert_eq!(quota_exceeded.name, name);
+  assert_eq!(quota_exceeded.prefix, "limited/");
+}
+
+async fn object_exists(pool: &sqlx::AnyPool, path: &str) -> bool {
+  repository::object::get_object_by_path(pool, APP, path)
+    .await
+    .unwrap()
+    .is_some()
+}
+
+#[tokio::test]
+async fn put_object_quota() {
+  let (config, pool, storage) = quota_store().await;
+  put(&config, &pool, &storage, "limited/a", b"123456")
+    .await
+    .unwrap();
+
+  let err = put(&config, &pool, &storage, "limited/b", b"123456")
+    .await
+    .err()
+    .unwrap();
+  assert_quota_exceeded(&err, MAX_BYTES);
+  assert!(!object_exists(&pool, "limited/b").await);
+
+  // replacing an object only counts what it grows by
+  let object_row = put(&config, &pool, &storage, "limited/a", b"1234567890")
+    .await
+    .unwrap();
+  assert_eq!(object_row.size, 10);
+  let err = put(&config, &pool, &storage, "limited/a", b"12345678901")
+    .await
+    .err()
+    .unwrap();
+  assert_quota_exceeded(&err, MAX_BYTES);
+
+  put(&config, &pool, &storage, "limited/b", b"")
+    .await
+    .unwrap();
+  let err = put(&config, &pool, &storage, "limited/c", b"")
+    .await
+    .err()
+    .unwrap();
+  assert_quota_exceeded(&err, MAX_OBJECTS);
+  put(&config, &pool, &storage, "free/c", b"12345678901")
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn append_quota() {
+  let (config, pool, storage) = quota_store().await;
+  let object_row = put(&config, &pool, &storage, "limited/a", b"123456")
+    .await
+    .unwrap();
+
+  let mut append =
+    service::object::open_append(&pool, storage.as_ref(), &config, object_row.id, false)
+      .await
+      .unwrap();
+  service::object::append_object(&pool, &mut append, Bytes::from_static(b"789"))
+    .await
+    .unwrap();
+  let err = service::object::append_object(&pool, &mut append, Bytes::from_static(b"01"))
+    .await
+    .err()
+    .unwrap();
+  assert_quota_exceeded(&err, MAX_BYTES);
+  service::object::abort_append(&pool, storage.as_ref(), append)
+    .await
+    .unwrap();
+
+  // aborted bytes are discarded so they no longer count towards the quota
+  let mut append =
+    service::object::open_append(&pool, storage.as_ref(), &config, object_row.id, false)
+      .await
+      .unwrap();
+  service::object::append_object(&pool, &mut append, Bytes::from_static(b"7890"))
+    .await
+    .unwrap();
+  let object_row = service::object::finish_append(&pool, append)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(object_row.size, 10);
+}
+
+#[tokio::test]
+async fn copy_object_quota() {
+  let (config, pool, storage) = quota_store().await;
+  let source = put(&config, &pool, &storage, "free/source", b"12345678")
+    .await
+    .unwrap();
+  service::object::copy_object(
+    &pool,
+    storage.clone(),
+    &config,
+    &source,
+    "limited/a".to_owned(),
+    None,
+    false,
+  )
+  .await
+  .unwrap();
+
+  let err = service::object::copy_object(
+    &pool,
+    storage.clone(),
+    &config,
+    &source,
+    "limited/b".to_owned(),
+    None,
+    false,
+  )
+  .await
+  .err()
+  .unwrap();
+  assert_quota_exceeded(&err, MAX_BYTES);
+  assert!(!object_exists(&pool, "limited/b").await);
+}
+
+#[tokio::test]
+async fn copy_objects_quota() {
+  let (config, pool, storage) = quota_store().await;
+  let source = put(&config, &pool, &storage, "free/source", b"123456")
+    .await
+    .unwrap();
+  let copies = ["limited/a", "limited/b"]
+    .into_iter()
+    .map(|path| ObjectCopy {
+      source: source.clone(),
+      path: path.to_owned(),
+      kind: None,
+    })
+    .collect();
+
+  let err = service::object::copy_objects(&pool, storage.clone(), &config, copies)
+    .await
+    .err()
+    .unwrap();
+  assert_quota_exceeded(&err, MAX_BYTES);
+  assert!(!object_exists(&pool, "limited/a").await);
+  assert!(!object_exists(&pool, "limited/b").await);
+}
+
+#[tokio::test]
+async fn complete_upload_quota() {
+  let (config, pool, storage) = quota_store().await;
+  let upload = service::multipart::create_upload(&pool, APP, "limited/a".to_owned(), None)
+    .await
+    .unwrap();
+  for (part_number, bytes) in [(1, b"123456".as_slice()), (2, b"78901".as_slice())] {
+    service::multipart::upload_part(
+      &pool,
+      storage.as_ref(),
+      APP,
+      &upload.id,
+      part_number,
+      futures_util::stream::iter(vec![Ok(Bytes::copy_from_slice(bytes))]),
+    )
+    .await
+    .unwrap();
+  }
+
+  let err = service::multipart::complete_upload(
+    &pool,
+    storage.clone(),
+    &config,
+    APP,
+    &upload.id,
+    None,
+    false,
+  )
+  .await
+  .err()
+  .unwrap();
+  match err {
+    MultipartError::Database(err) => assert_quota_exceeded(&err, MAX_BYTES),
+    err => panic!("expected a quota error, got {}", err),
+  }
+  assert!(!object_exists(&pool, "limited/a").await);
+}
+
+#[tokio::test]
+async fn move_object_quota() {
+  let (config, pool, storage) = quota_store().await;
+  put(&config, &pool, &storage, "limited/a", b"123456")
+    .await
+    .unwrap();
+  let object_row = put(&config, &pool, &storage, "free/b", b"123456")
+    .await
+    .unwrap();
+
+  let err = service::object::move_object(
+    &pool,
+    &config,
+    APP,
+    object_row.id,
+    "limited/b".to_owned(),
+    None,
+  )
+  .await
+  .err()
+  .unwrap();
+  assert_quota_exceeded(&err, MAX_BYTES);
+  assert!(object_exists(&pool, "free/b").await);
+
+  // moving within the quota's prefix adds nothing to it
+  let object_row = put(&config, &pool, &storage, "limited/c", b"1234")
+    .await
+    .unwrap();
+  service::object::move_object(
+    &pool,
+    &config,
+    APP,
+    object_row.id,
+    "limited/d".to_owned(),
+    None,
+  )
+  .await
+  .unwrap()
+  .unwrap();
+  assert!(object_exists(&pool, "limited/d").await);
+}
+
+#[tokio::test]
+async fn move_folder_quota() {
+  let (config, pool, storage) = quota_store().await;
+  put(&config, &pool, &storage, "free/dir/a", b"123456")
+    .await
+    .unwrap();
+  put(&config, &pool, &storage, "free/dir/b", b"123456")
+    .await
+    .unwrap();
+
+  let err = service::object::move_folder(
+    &pool,
+    &config,
+    APP,
+    "free/dir/".to_owned(),
+    "limited/dir/".to_owned(),
+  )
+  .await
+  .err()
+  .unwrap();
+  assert_quota_exceeded(&err, MAX_BYTES);
+  assert!(object_exists(&pool, "free/dir/a").await);
+
+  // moving within the quota's prefix adds nothing to it
+  put(&config, &pool, &storage, "limited/x/a", b"1234567890")
+    .await
+    .unwrap();
+  let moved = service::object::move_folder(
+    &pool,
+    &config,
+    APP,
+    "limited/x/".to_owned(),
+    "limited/y/".to_owned(),
+  )
+  .await
+  .unwrap();
+  assert!(matches!(moved, FolderMove::Moved(1)));
+  assert!(object_exists(&pool, "limited/y/a").await);
+}
@@ -0,0 +1,107 @@
+use std::io;
+
+use object_storage::storage::{
+  local::LocalStorage, memory::MemoryStorage, ObjectReader, StorageBackend,
+};
+use tokio::io::AsyncReadExt;
+
+async fn read_all(reader: ObjectReader) -> Vec<u8> {
+  let mut reader = reader;
+  let mut bytes = Vec::new();
+  reader.read_to_end(&mut bytes).await.unwrap();
+  bytes
+}
+
+async fn write_all(storage: &dyn StorageBackend, id: i64, bytes: &[u8]) -> u64 {
+  let mut writer = storage.open_replace(id).await.unwrap();
+  writer.write(bytes).await.unwrap();
+  writer.finish().await.unwrap()
+}
+
+async fn append_all(storage: &dyn StorageBackend, id: i64, bytes: &[u8]) -> u64 {
+  let mut writer = storage.open_append(id).await.unwrap();
+  writer.write(bytes).await.unwrap();
+  writer.finish().await.unwrap()
+}
+
+/// the behaviour every backend must share
+async fn check_backend(storage: &dyn StorageBackend) {
+  storage.create(1).await.unwrap();
+  assert_eq!(storage.stat(1).await.unwrap().size, 0);
+  assert!(read_all(storage.open_read(1).await.unwrap())
+    .await
+    .is_empty());
+
+  assert_eq!(write_all(storage, 1, b"hello world").await, 11);
+  assert_eq!(
+    read_all(storage.open_read(1).await.unwrap()).await,
+    b"hello world"
+  );
+
+  let mut writer = storage.open_replace(1).await.unwrap();
+  writer.write(b"discarded").await.unwrap();
+  writer.abort().await.unwrap();
+  assert_eq!(
+    read_all(storage.open_read(1).await.unwrap()).await,
+    b"hello world"
+  );
+
+  assert_eq!(
+    read_all(storage.open_read_range(1, 6..11).await.unwrap()).await,
+    b"world"
+  );
+  assert_eq!(
+    read_all(storage.open_read_range(1, 0..5).await.unwrap()).await,
+    b"hello"
+  );
+
+  assert_eq!(append_all(storage, 1, b"!").await, 12);
+  let mut writer = storage.open_append(1).await.unwrap();
+  writer.write(b"discarded").await.unwrap();
+  writer.abort().await.unwrap();
+  assert_eq!(
+    read_all(storage.open_read(1).await.unwrap()).await,
+    b"hello world!"
+  );
+
+  storage.copy(1, 2).await.unwrap();
+  append_all(storage, 1, b"?").await;
+  assert_eq!(
+    read_all(storage.open_read(2).await.unwrap()).await,
+    b"hello world!"
+  );
+  assert_eq!(storage.stat(2).await.unwrap().size, 12);
+
+  storage.truncate(1, 5).await.unwrap();
+  assert_eq!(
+    read_all(storage.open_read(1).await.unwrap()).await,
+    b"hello"
+  );
+
+  storage.delete(2).await.unwrap();
+  assert_eq!(
+    storage.stat(2).await.unwrap_err().kind(),
+    io::ErrorKind::NotFound
+  );
+  assert_eq!(
+    storage.open_read(2).await.err().unwrap().kind(),
+    io::ErrorKind::NotFound
+  );
+  assert_eq!(
+    storage.copy(2, 3).await.unwrap_err().kind(),
+    io::ErrorKind::NotFound
+  );
+}
+
+#[tokio::test]
+async fn memory_storage() {
+  check_backend(&MemoryStorage::new()).await;
+}
+
+#[tokio::test]
+async fn local_storage() {
+  let objects_dir = std::env::temp_dir().join(format!("objects-{}", uuid::Uuid::new_v4()));
+  tokio::fs::create_dir_all(&objects_dir).await.unwrap();
+  check_backend(&LocalStorage::new(&objects_dir)).await;
+  tokio::fs::remove_dir_all(&objects_dir).await.unwrap();
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{body, APP};
+use object_storage::{
+  core::{config::Config, sigv4::sha256_hex},
+  repository::{self, object::ObjectRow},
+  service::{
+    self,
+    tus::{TusError, UploadChecksum},
+  },
+  storage::StorageBackend,
+};
+use serde_json::json;
+
+const LENGTH: u64 = 6;
+
+async fn tus_store() -> (Config, sqlx::AnyPool, Arc<dyn StorageBackend>, ObjectRow) {
+  let (config, pool, storage) = common::memory_store(json!({})).await;
+  let object_row = service::tus::create_upload(
+    &pool,
+    storage.clone(),
+    APP,
+    "upload.bin".to_owned(),
+    None,
+    LENGTH,
+    None,
+  )
+  .await
+  .unwrap();
+  (config, pool, storage, object_row)
+}
+
+async fn append(
+  config: &Config,
+  pool: &sqlx::AnyPool,
+  storage: &Arc<dyn StorageBackend>,
+  object_row: &ObjectRow,
+  offset: u64,
+  checksum: Option<UploadChecksum>,
+  bytes: &'static [u8],
+) -> Result<u64, TusError> {
+  service::tus::append_upload(
+    pool,
+    storage.clone(),
+    object_row,
+    LENGTH,
+    offset,
+    checksum,
+    config,
+    false,
+    body(bytes),
+  )
+  .await
+}
+
+async fn size(pool: &sqlx::AnyPool, object_row: &ObjectRow) -> i64 {
+  repository::object::get_object_by_id(pool, APP, object_row.id)
+    .await
+    .unwrap()
+    .unwrap()
+    .size
+}
+
+fn sha256(bytes: &[u8]) -> Option<UploadChecksum> {
+  Some(UploadChecksum {
+    algorithm: "sha256".to_owned(),
+    digest: hex::decode(sha256_hex(bytes)).unwrap(),
+  })
+}
+
+#[tokio::test]
+async fn offset_mismatch() {
+  let (config, pool, storage, object_row) = tus_store().await;
+  let offset = append(&config, &pool, &storage, &object_row, 0, None, b"123")
+    .await
+    .unwrap();
+  assert_eq!(offset, 3);
+
+  for offset in [0, 2, 4] {
+    let err = append(&config, &pool, &storage, &object_row, offset, None, b"456")
+      .await
+      .err()
+      .unwrap();
+    assert!(matches!(err, TusError::OffsetMismatch));
+  }
+  assert_eq!(size(&pool, &object_row).await, 3);
+}
+
+#[tokio::test]
+async fn length_exceeded() {
+  let (config, pool, storage, object_row) = tus_store().await;
+  append(&config, &pool, &storage, &object_row, 0, None, b"123")
+    .await
+    .unwrap();
+
+  let err = append(&config, &pool, &storage, &object_row, 3, None, b"4567")
+    .await
+    .err()
+    .unwrap();
+  assert!(matches!(err, TusError::LengthExceeded));
+  assert_eq!(size(&pool, &object_row).await, 3);
+  assert!(repository::tus::get_tus_upload(&pool, object_row.id)
+    .await
+    .unwrap()
+    .is_some());
+
+  let offset = append(&config, &pool, &storage, &object_row, 3, None, b"456")
+    .await
+    .unwrap();
+  assert_eq!(offset, LENGTH);
+  assert!(repository::tus::get_tus_upload(&pool, object_row.id)
+    .await
+    .unwrap()
+    .is_none());
+}
+
+#[tokio::test]
+async fn checksum_mismatch() {
+  let (config, pool, storage, object_row) = tus_store().await;
+  let err = append(
+    &config,
+    &pool,
+    &storage,
+    &object_row,
+    0,
+    sha256(b"other"),
+    b"123",
+  )
+  .await
+  .err()
+  .unwrap();
+  assert!(matches!(err, TusError::ChecksumMismatch));
+  let unknown = Some(UploadChecksum {
+    algorithm: "sha1".to_owned(),
+    digest: Vec::new(),
+  });
+  let err = append(&config, &pool, &storage, &object_row, 0, unknown, b"123")
+    .await
+    .err()
+    .unwrap();
+  assert!(matches!(err, TusError::ChecksumMismatch));
+  assert_eq!(size(&pool, &object_row).await, 0);
+
+  let offset = append(
+    &config,
+    &pool,
+    &storage,
+    &object_row,
+    0,
+    sha256(b"123"),
+    b"123",
+  )
+  .await
+  .unwrap();
+  assert_eq!(offset, 3);
+}